@@ -0,0 +1,257 @@
+// Aadhaar Photo Handling
+// Decodes the embedded JPEG / JPEG 2000 photo, checks its header and commits to it so the raw image can be dropped
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Largest photo accepted; UIDAI photos are a few KB
+pub const MAX_PHOTO_BYTES: usize = 256 * 1024;
+pub const MAX_PHOTO_DIMENSION: u32 = 2048;
+
+const COMMITMENT_DOMAIN: &[u8] = b"PatriconID/aadhaar-photo/v1";
+const JP2_SIGNATURE: &[u8] = &[0x00, 0x00, 0x00, 0x0C, b'j', b'P', b' ', b' ', 0x0D, 0x0A, 0x87, 0x0A];
+
+#[derive(Debug, Error)]
+pub enum PhotoError {
+    #[error("Photo is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Photo is {0} bytes, larger than allowed")]
+    TooLarge(usize),
+    #[error("Photo is neither JPEG nor JPEG 2000")]
+    UnsupportedFormat,
+    #[error("Photo header is truncated or malformed")]
+    MalformedHeader,
+    #[error("Photo dimensions {width}x{height} are out of range")]
+    InvalidDimensions { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhotoFormat {
+    Jpeg,
+    Jpeg2000, // JP2 container or raw J2K codestream
+}
+
+/// Decoded and header-checked Aadhaar photo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AadhaarPhoto {
+    pub format: PhotoFormat,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// What remains of the photo once the image itself is discarded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhotoCommitment {
+    pub format: PhotoFormat,
+    pub width: u32,
+    pub height: u32,
+    pub commitment: String, // SHA-256(domain || image bytes), hex
+}
+
+impl AadhaarPhoto {
+    pub fn from_base64(photo_base64: &str) -> Result<Self, PhotoError> {
+        let cleaned: String = photo_base64.chars().filter(|c| !c.is_whitespace()).collect();
+        Self::from_bytes(general_purpose::STANDARD.decode(cleaned)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, PhotoError> {
+        if data.len() > MAX_PHOTO_BYTES {
+            return Err(PhotoError::TooLarge(data.len()));
+        }
+
+        let (format, (width, height)) = if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            (PhotoFormat::Jpeg, jpeg_dimensions(&data)?)
+        } else if data.starts_with(JP2_SIGNATURE) {
+            (PhotoFormat::Jpeg2000, jp2_dimensions(&data)?)
+        } else if data.starts_with(&[0xFF, 0x4F, 0xFF, 0x51]) {
+            (PhotoFormat::Jpeg2000, j2k_dimensions(&data)?)
+        } else {
+            return Err(PhotoError::UnsupportedFormat);
+        };
+
+        if width == 0 || height == 0 || width > MAX_PHOTO_DIMENSION || height > MAX_PHOTO_DIMENSION {
+            return Err(PhotoError::InvalidDimensions { width, height });
+        }
+        Ok(Self { format, width, height, data })
+    }
+
+    pub fn commitment(&self) -> PhotoCommitment {
+        PhotoCommitment {
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            commitment: commit(&self.data),
+        }
+    }
+}
+
+impl PhotoCommitment {
+    /// Check a photo presented later (e.g. from a fresh e-KYC download) against this commitment
+    pub fn matches(&self, photo: &[u8]) -> bool {
+        commit(photo) == self.commitment
+    }
+}
+
+fn commit(photo: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(COMMITMENT_DOMAIN);
+    hasher.update(photo);
+    hex::encode(hasher.finalize())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, PhotoError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or(PhotoError::MalformedHeader)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, PhotoError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(PhotoError::MalformedHeader)
+}
+
+/// Walk JPEG marker segments up to the first start-of-frame
+fn jpeg_dimensions(data: &[u8]) -> Result<(u32, u32), PhotoError> {
+    let mut offset = 2;
+    loop {
+        if data.get(offset) != Some(&0xFF) {
+            return Err(PhotoError::MalformedHeader);
+        }
+        while data.get(offset) == Some(&0xFF) {
+            offset += 1;
+        }
+        let marker = *data.get(offset).ok_or(PhotoError::MalformedHeader)?;
+        offset += 1;
+
+        match marker {
+            // Standalone markers carry no length
+            0x01 | 0xD0..=0xD7 => continue,
+            // SOF0-SOF15, excluding DHT (C4), JPG (C8) and DAC (CC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = read_u16(data, offset + 3)?;
+                let width = read_u16(data, offset + 5)?;
+                return Ok((width as u32, height as u32));
+            }
+            // Image data started without a frame header
+            0xDA | 0xD9 => return Err(PhotoError::MalformedHeader),
+            _ => offset += read_u16(data, offset)? as usize,
+        }
+    }
+}
+
+/// Find jp2h > ihdr in a JP2 container
+fn jp2_dimensions(data: &[u8]) -> Result<(u32, u32), PhotoError> {
+    let header = find_box(data, b"jp2h")?;
+    let ihdr = find_box(header, b"ihdr")?;
+    let height = read_u32(ihdr, 0)?;
+    let width = read_u32(ihdr, 4)?;
+    Ok((width, height))
+}
+
+fn find_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Result<&'a [u8], PhotoError> {
+    let mut offset = 0;
+    while offset + 8 <= data.len() {
+        let length = read_u32(data, offset)? as u64;
+        let (header_length, length) = match length {
+            0 => (8, (data.len() - offset) as u64),
+            1 => (16, data.get(offset + 8..offset + 16)
+                .map(|bytes| u64::from_be_bytes(bytes.try_into().expect("slice is 8 bytes")))
+                .ok_or(PhotoError::MalformedHeader)?),
+            length => (8, length),
+        };
+        let end = usize::try_from(length).ok()
+            .and_then(|length| offset.checked_add(length))
+            .filter(|end| *end <= data.len() && length >= header_length)
+            .ok_or(PhotoError::MalformedHeader)?;
+
+        if &data[offset + 4..offset + 8] == box_type {
+            return Ok(&data[offset + header_length as usize..end]);
+        }
+        offset = end;
+    }
+    Err(PhotoError::MalformedHeader)
+}
+
+/// Image size from the SIZ segment of a raw J2K codestream
+fn j2k_dimensions(data: &[u8]) -> Result<(u32, u32), PhotoError> {
+    // SOC, SIZ marker, Lsiz, Rsiz, then Xsiz Ysiz XOsiz YOsiz
+    let width = read_u32(data, 8)?.saturating_sub(read_u32(data, 16)?);
+    let height = read_u32(data, 12)?.saturating_sub(read_u32(data, 20)?);
+    Ok((width, height))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Minimal JPEG: SOI, APP0, SOF0 (width x height), EOI
+    pub(crate) fn test_jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        jpeg.extend_from_slice(b"JFIF\0\x01\x01\x00\x00\x01\x00\x01\x00\x00");
+        jpeg.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x0B, 0x08]);
+        jpeg.extend_from_slice(&height.to_be_bytes());
+        jpeg.extend_from_slice(&width.to_be_bytes());
+        jpeg.extend_from_slice(&[0x01, 0x01, 0x11, 0x00, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    /// Minimal J2K codestream: SOC and a single-component SIZ segment
+    pub(crate) fn test_j2k(width: u32, height: u32) -> Vec<u8> {
+        let mut j2k = vec![0xFF, 0x4F, 0xFF, 0x51, 0x00, 0x29, 0x00, 0x00];
+        for value in [width, height, 0, 0, width, height, 0, 0] {
+            j2k.extend_from_slice(&value.to_be_bytes());
+        }
+        j2k.extend_from_slice(&[0x00, 0x01, 0x07, 0x01, 0x01, 0xFF, 0xD9]);
+        j2k
+    }
+
+    #[test]
+    fn test_decode_jpeg() {
+        let jpeg = test_jpeg(160, 200);
+        let photo = AadhaarPhoto::from_base64(&general_purpose::STANDARD.encode(&jpeg)).unwrap();
+        assert_eq!(photo.format, PhotoFormat::Jpeg);
+        assert_eq!((photo.width, photo.height), (160, 200));
+
+        let commitment = photo.commitment();
+        assert!(commitment.matches(&jpeg));
+        assert!(!commitment.matches(&test_jpeg(160, 201)));
+    }
+
+    #[test]
+    fn test_decode_jpeg2000() {
+        let photo = AadhaarPhoto::from_bytes(test_j2k(120, 150)).unwrap();
+        assert_eq!(photo.format, PhotoFormat::Jpeg2000);
+        assert_eq!((photo.width, photo.height), (120, 150));
+
+        // The same image wrapped in a JP2 container
+        let mut jp2 = JP2_SIGNATURE.to_vec();
+        jp2.extend_from_slice(&[0x00, 0x00, 0x00, 0x14, b'f', b't', b'y', b'p', b'j', b'p', b'2', b' ']);
+        jp2.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, b'j', b'p', b'2', b' ']);
+        jp2.extend_from_slice(&[0x00, 0x00, 0x00, 0x1E, b'j', b'p', b'2', b'h']);
+        jp2.extend_from_slice(&[0x00, 0x00, 0x00, 0x16, b'i', b'h', b'd', b'r']);
+        jp2.extend_from_slice(&150u32.to_be_bytes());
+        jp2.extend_from_slice(&120u32.to_be_bytes());
+        jp2.extend_from_slice(&[0x00, 0x03, 0x07, 0x07, 0x00, 0x00]);
+        let photo = AadhaarPhoto::from_bytes(jp2).unwrap();
+        assert_eq!((photo.width, photo.height), (120, 150));
+    }
+
+    #[test]
+    fn test_rejects_invalid_photos() {
+        assert!(matches!(AadhaarPhoto::from_bytes(b"GIF89a".to_vec()), Err(PhotoError::UnsupportedFormat)));
+        assert!(matches!(AadhaarPhoto::from_bytes(vec![0xFF, 0xD8, 0xFF, 0xD9]), Err(PhotoError::MalformedHeader)));
+        assert!(matches!(
+            AadhaarPhoto::from_bytes(test_jpeg(0, 200)),
+            Err(PhotoError::InvalidDimensions { width: 0, height: 200 })
+        ));
+        assert!(matches!(
+            AadhaarPhoto::from_bytes(test_j2k(4096, 100)),
+            Err(PhotoError::InvalidDimensions { width: 4096, .. })
+        ));
+        assert!(matches!(AadhaarPhoto::from_base64("not base64!"), Err(PhotoError::Base64(_))));
+    }
+}
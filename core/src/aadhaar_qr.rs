@@ -0,0 +1,373 @@
+// Aadhaar Secure QR Decoder
+// Decodes and verifies the signed QR printed on Aadhaar letters, e-Aadhaar and PVC cards
+
+use std::io::Read;
+
+use crate::aadhaar_xml_parser::{AadhaarAddress, AadhaarVerificationError, VerifiedAadhaarData};
+use crate::birth_date::BirthDate;
+use crate::freshness::ReferenceId;
+use crate::parse_limits::ParseLimits;
+use crate::trust_store::{KeyInfoSelector, TrustStore};
+
+/// Byte separating the text fields of the decompressed payload
+const FIELD_DELIMITER: u8 = 255;
+/// RSA-2048 SHA256withRSA signature appended to the payload
+const SIGNATURE_LENGTH: usize = 256;
+/// Raw SHA-256 digest length of the mobile and email hashes
+const HASH_LENGTH: usize = 32;
+
+/// Field layout of the Secure QR text section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecureQrVersion {
+    /// Original layout: 16 fields starting with the email/mobile indicator
+    V1,
+    /// Adds a leading "V2" marker and a trailing last-4-digits-of-mobile field
+    V2,
+}
+
+impl SecureQrVersion {
+    fn text_field_count(&self) -> usize {
+        match self {
+            SecureQrVersion::V1 => 16,
+            SecureQrVersion::V2 => 18,
+        }
+    }
+}
+
+/// Which of the mobile/email hashes are embedded before the signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContactIndicator {
+    pub email_present: bool,
+    pub mobile_present: bool,
+}
+
+impl ContactIndicator {
+    fn from_field(value: &str) -> Result<Self, AadhaarVerificationError> {
+        let bits: u8 = value.trim().parse()
+            .ok()
+            .filter(|bits| *bits <= 3)
+            .ok_or_else(|| AadhaarVerificationError::InvalidQrCode(
+                format!("Invalid email/mobile indicator: {}", value)
+            ))?;
+
+        Ok(Self {
+            email_present: bits & 0b01 != 0,
+            mobile_present: bits & 0b10 != 0,
+        })
+    }
+}
+
+/// Decoded but not yet verified Secure QR contents
+#[derive(Debug, Clone)]
+pub struct SecureQrData {
+    pub version: SecureQrVersion,
+    pub contact_indicator: ContactIndicator,
+    pub reference_id: String,
+    pub name: String,
+    pub date_of_birth: String,
+    pub gender: String,
+    pub address: AadhaarAddress,
+    pub mobile_last_4_digits: Option<String>,
+    pub photo: Vec<u8>, // JPEG 2000
+    pub mobile_hash: Option<[u8; HASH_LENGTH]>,
+    pub email_hash: Option<[u8; HASH_LENGTH]>,
+    signed_data: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl SecureQrData {
+    /// Bytes covered by the UIDAI signature
+    pub fn signed_data(&self) -> &[u8] {
+        &self.signed_data
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+}
+
+/// Decode the decimal big-integer QR payload without checking the signature
+pub fn decode_secure_qr(qr_payload: &str) -> Result<SecureQrData, AadhaarVerificationError> {
+    decode_secure_qr_with_limits(qr_payload, &ParseLimits::default())
+}
+
+/// Decode an untrusted payload, rejecting oversized input and gzip bombs
+pub fn decode_secure_qr_with_limits(qr_payload: &str, limits: &ParseLimits) -> Result<SecureQrData, AadhaarVerificationError> {
+    let digits = qr_payload.trim();
+    if digits.len() > limits.max_qr_payload_length {
+        return Err(AadhaarVerificationError::QrPayloadTooLong {
+            length: digits.len(),
+            limit: limits.max_qr_payload_length,
+        });
+    }
+    let number = rsa::BigUint::parse_bytes(digits.as_bytes(), 10)
+        .ok_or_else(|| AadhaarVerificationError::InvalidQrCode(
+            "Payload is not a decimal number".to_string()
+        ))?;
+
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(number.to_bytes_be().as_slice())
+        .take(limits.max_uncompressed_size + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| AadhaarVerificationError::InvalidQrCode(format!("Decompression failed: {}", e)))?;
+    if decompressed.len() as u64 > limits.max_uncompressed_size {
+        return Err(AadhaarVerificationError::UncompressedSizeExceeded(limits.max_uncompressed_size));
+    }
+
+    parse_decompressed(decompressed)
+}
+
+/// Decode the QR payload and verify its signature against the trust store
+pub fn parse_secure_qr(
+    qr_payload: &str,
+    trust_store: &TrustStore,
+) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+    parse_secure_qr_with_limits(qr_payload, trust_store, &ParseLimits::default())
+}
+
+pub fn parse_secure_qr_with_limits(
+    qr_payload: &str,
+    trust_store: &TrustStore,
+    limits: &ParseLimits,
+) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+    use base64::{Engine as _, engine::general_purpose};
+
+    let qr = decode_secure_qr_with_limits(qr_payload, limits)?;
+
+    // The QR carries no KeyInfo, so every trusted certificate is a candidate
+    let signer = trust_store.verify_rsa_sha256(&KeyInfoSelector::default(), &qr.signed_data, &qr.signature)?;
+
+    let reference = ReferenceId::parse(&qr.reference_id)?;
+
+    Ok(VerifiedAadhaarData {
+        name: qr.name.into(),
+        date_of_birth: BirthDate::parse(&qr.date_of_birth)?.to_string().into(),
+        gender: qr.gender,
+        address: qr.address.into(),
+        photo_base64: (!qr.photo.is_empty()).then(|| general_purpose::STANDARD.encode(&qr.photo)).into(),
+        photo_commitment: None,
+        mobile_hash: qr.mobile_hash.map(hex::encode).into(),
+        email_hash: qr.email_hash.map(hex::encode).into(),
+        reference_id: qr.reference_id,
+        generated_date: reference.generated_date(),
+        generated_at: Some(reference.generated_at),
+        signature_valid: signer.is_some(),
+        certificate_valid: signer.is_some_and(|certificate| trust_store.is_currently_valid(certificate)),
+        signer_certificate: signer.map(|certificate| certificate.summary()),
+        aadhaar_last_4_digits: reference.aadhaar_last_4_digits,
+    })
+}
+
+fn parse_decompressed(data: Vec<u8>) -> Result<SecureQrData, AadhaarVerificationError> {
+    if data.len() <= SIGNATURE_LENGTH {
+        return Err(AadhaarVerificationError::InvalidQrCode("Payload too short".to_string()));
+    }
+    let (signed_data, signature) = data.split_at(data.len() - SIGNATURE_LENGTH);
+
+    let version = if signed_data.starts_with(b"V2") && signed_data.get(2) == Some(&FIELD_DELIMITER) {
+        SecureQrVersion::V2
+    } else {
+        SecureQrVersion::V1
+    };
+
+    // Text fields are delimited by 255; the photo follows the last delimiter
+    let mut fields = Vec::with_capacity(version.text_field_count());
+    let mut offset = 0;
+    for _ in 0..version.text_field_count() {
+        let end = signed_data[offset..].iter()
+            .position(|byte| *byte == FIELD_DELIMITER)
+            .map(|position| offset + position)
+            .ok_or_else(|| AadhaarVerificationError::InvalidQrCode(
+                format!("Expected {} fields, found {}", version.text_field_count(), fields.len())
+            ))?;
+        // Text fields are ISO-8859-1 encoded
+        fields.push(signed_data[offset..end].iter().map(|&byte| byte as char).collect::<String>());
+        offset = end + 1;
+    }
+
+    let fields = match version {
+        SecureQrVersion::V1 => &fields[..],
+        SecureQrVersion::V2 => &fields[1..],
+    };
+    let contact_indicator = ContactIndicator::from_field(&fields[0])?;
+
+    // Hashes sit between the photo and the signature: email first, then mobile
+    let mut trailer_end = signed_data.len();
+    let mut take_hash = |present: bool| -> Result<Option<[u8; HASH_LENGTH]>, AadhaarVerificationError> {
+        if !present {
+            return Ok(None);
+        }
+        if trailer_end < offset + HASH_LENGTH {
+            return Err(AadhaarVerificationError::InvalidQrCode("Truncated contact hash".to_string()));
+        }
+        let mut hash = [0u8; HASH_LENGTH];
+        hash.copy_from_slice(&signed_data[trailer_end - HASH_LENGTH..trailer_end]);
+        trailer_end -= HASH_LENGTH;
+        Ok(Some(hash))
+    };
+    let mobile_hash = take_hash(contact_indicator.mobile_present)?;
+    let email_hash = take_hash(contact_indicator.email_present)?;
+
+    let optional = |value: &String| (!value.trim().is_empty()).then(|| value.clone());
+    let required = |value: &String, field: &str| {
+        optional(value).ok_or_else(|| AadhaarVerificationError::MissingDemographicData(field.to_string()))
+    };
+
+    let address = AadhaarAddress {
+        care_of: optional(&fields[5]),
+        house: optional(&fields[8]),
+        street: optional(&fields[13]),
+        landmark: optional(&fields[7]),
+        locality: optional(&fields[9]),
+        vtc: required(&fields[15], "vtc")?,
+        post_office: optional(&fields[11]),
+        subdist: optional(&fields[14]),
+        district: required(&fields[6], "district")?,
+        state: required(&fields[12], "state")?,
+        pincode: required(&fields[10], "pincode")?,
+        country: "India".to_string(),
+    };
+
+    Ok(SecureQrData {
+        version,
+        contact_indicator,
+        reference_id: required(&fields[1], "referenceId")?,
+        name: required(&fields[2], "name")?,
+        date_of_birth: required(&fields[3], "dob")?,
+        gender: required(&fields[4], "gender")?,
+        address,
+        mobile_last_4_digits: fields.get(16).and_then(optional),
+        photo: signed_data[offset..trailer_end].to_vec(),
+        mobile_hash,
+        email_hash,
+        signed_data: signed_data.to_vec(),
+        signature: signature.to_vec(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TEST_CERT_PEM: &str = include_str!("../testdata/test_uidai_cert.pem");
+    const TEST_KEY_PEM: &str = include_str!("../testdata/test_uidai_key.pem");
+    const TEST_PHOTO: &[u8] = &[0xFF, 0x4F, 0xFF, 0x51, 0x00, 0x2F, 0xFF, 0xD9];
+
+    pub(crate) const V2_FIELDS: [&str; 18] = [
+        "V2", "3", "123420250101120000000", "Test User", "15-08-1990", "M",
+        "S/O Father", "Bengaluru Urban", "Near Park", "12", "Indiranagar", "560038",
+        "Indiranagar", "Karnataka", "100 Feet Road", "Bengaluru East", "Bengaluru", "9876",
+    ];
+
+    pub(crate) fn build_payload(fields: &[&str], trailer: &[&[u8]]) -> String {
+        use rsa::pkcs1v15::SigningKey;
+        use rsa::pkcs8::DecodePrivateKey;
+        use rsa::signature::{SignatureEncoding, Signer};
+        use sha2::Sha256;
+
+        let mut data = Vec::new();
+        for field in fields {
+            data.extend_from_slice(field.as_bytes());
+            data.push(FIELD_DELIMITER);
+        }
+        for part in trailer {
+            data.extend_from_slice(part);
+        }
+
+        let private_key = rsa::RsaPrivateKey::from_pkcs8_pem(TEST_KEY_PEM).unwrap();
+        let signature = SigningKey::<Sha256>::new(private_key).sign(&data).to_bytes();
+        data.extend_from_slice(&signature);
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        rsa::BigUint::from_bytes_be(&encoder.finish().unwrap()).to_string()
+    }
+
+    fn test_trust_store() -> TrustStore {
+        let mut trust_store = TrustStore::new();
+        trust_store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        trust_store
+    }
+
+    #[test]
+    fn test_decode_v2_with_email_and_mobile() {
+        let email_hash = [0xAA; HASH_LENGTH];
+        let mobile_hash = [0xBB; HASH_LENGTH];
+        let payload = build_payload(&V2_FIELDS, &[TEST_PHOTO, &email_hash, &mobile_hash]);
+
+        let qr = decode_secure_qr(&payload).unwrap();
+        assert_eq!(qr.version, SecureQrVersion::V2);
+        assert!(qr.contact_indicator.email_present && qr.contact_indicator.mobile_present);
+        assert_eq!(qr.photo, TEST_PHOTO);
+        assert_eq!(qr.email_hash, Some(email_hash));
+        assert_eq!(qr.mobile_hash, Some(mobile_hash));
+        assert_eq!(qr.mobile_last_4_digits.as_deref(), Some("9876"));
+        assert_eq!(qr.address.vtc, "Bengaluru");
+        assert_eq!(qr.address.state_code(), 29);
+
+        let verified = parse_secure_qr(&payload, &test_trust_store()).unwrap();
+        assert!(verified.signature_valid);
+        assert!(verified.certificate_valid);
+        assert_eq!(verified.name.expose(), "Test User");
+        assert_eq!(verified.aadhaar_last_4_digits, "1234");
+        assert_eq!(verified.generated_date, "2025-01-01");
+        assert_eq!(verified.mobile_hash.expose(), &Some(hex::encode(mobile_hash)));
+        assert!(verified.photo_base64.expose().is_some());
+    }
+
+    #[test]
+    fn test_decode_v1_mobile_only() {
+        let mut fields = V2_FIELDS[1..17].to_vec();
+        fields[0] = "2";
+        let mobile_hash = [0x11; HASH_LENGTH];
+        let payload = build_payload(&fields, &[TEST_PHOTO, &mobile_hash]);
+
+        let qr = decode_secure_qr(&payload).unwrap();
+        assert_eq!(qr.version, SecureQrVersion::V1);
+        assert_eq!(qr.mobile_hash, Some(mobile_hash));
+        assert_eq!(qr.email_hash, None);
+        assert_eq!(qr.photo, TEST_PHOTO);
+        assert_eq!(qr.mobile_last_4_digits, None);
+    }
+
+    #[test]
+    fn test_untrusted_signature_rejected() {
+        let payload = build_payload(&V2_FIELDS, &[TEST_PHOTO, &[0; 64]]);
+
+        // A store holding a different certificate cannot verify the payload
+        let mut other_store = TrustStore::new();
+        other_store.add_pem(include_bytes!("../testdata/test_uidai_expired_cert.pem")).unwrap();
+        assert!(!parse_secure_qr(&payload, &other_store).unwrap().signature_valid);
+    }
+
+    #[test]
+    fn test_malformed_payloads() {
+        assert!(matches!(decode_secure_qr("12ab"), Err(AadhaarVerificationError::InvalidQrCode(_))));
+        assert!(matches!(decode_secure_qr("123456789"), Err(AadhaarVerificationError::InvalidQrCode(_))));
+
+        let truncated = build_payload(&V2_FIELDS[..10], &[]);
+        assert!(matches!(decode_secure_qr(&truncated), Err(AadhaarVerificationError::InvalidQrCode(_))));
+    }
+
+    #[test]
+    fn test_payload_limits() {
+        let payload = build_payload(&V2_FIELDS, &[TEST_PHOTO]);
+        let limits = ParseLimits { max_qr_payload_length: 64, ..ParseLimits::default() };
+        assert!(matches!(
+            decode_secure_qr_with_limits(&payload, &limits),
+            Err(AadhaarVerificationError::QrPayloadTooLong { limit: 64, .. })
+        ));
+
+        // A few hundred bytes of gzip that inflate to far more than the payload could hold
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&vec![0; 16 * 1024 * 1024]).unwrap();
+        let bomb = rsa::BigUint::from_bytes_be(&encoder.finish().unwrap()).to_string();
+        let limits = ParseLimits { max_qr_payload_length: bomb.len(), ..ParseLimits::default() };
+        assert!(matches!(
+            decode_secure_qr_with_limits(&bomb, &limits),
+            Err(AadhaarVerificationError::UncompressedSizeExceeded(limit)) if limit == ParseLimits::default().max_uncompressed_size
+        ));
+        assert!(matches!(decode_secure_qr(&bomb), Err(AadhaarVerificationError::QrPayloadTooLong { .. })));
+    }
+}
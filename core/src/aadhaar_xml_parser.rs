@@ -1,21 +1,44 @@
 // Aadhaar XML Parser with UIDAI Digital Signature Verification
 // Implements production-ready Aadhaar offline e-KYC verification
 
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::io::Read;
 use thiserror::Error;
+use zeroize::Zeroize;
+use crate::aadhaar_photo::{AadhaarPhoto, PhotoCommitment, PhotoError};
+use crate::sensitive::Sensitive;
+use crate::birth_date::{date_from_numeric, AgeBracket, BirthDate, BirthDateError, YearOnlyMode};
+use crate::contact_verification::{hash_iterations, registered_hash, uidai_contact_hash, ContactKind};
+use crate::freshness::FreshnessPolicy;
+use crate::logging::Field;
+use crate::{log_debug, log_warn};
+use crate::offline_kyc::{AadhaarKycDocument, KycSchemaError};
+use crate::parse_limits::ParseLimits;
+use crate::trust_store::{KeyInfoSelector, SignerCertificate, TrustStore, TrustedCertificate};
+use crate::xml_c14n::{
+    CanonicalizationError, CanonicalizationMethod, XmlDocument, XmlElement,
+    C14N_EXCLUSIVE, ENVELOPED_SIGNATURE_TRANSFORM, XMLDSIG_NAMESPACE,
+};
 
 #[derive(Debug, Error)]
 pub enum AadhaarVerificationError {
-    #[error("Invalid ZIP file or share code")]
+    #[error("Invalid or corrupt ZIP file")]
     InvalidZipFile,
+    #[error("Incorrect share code")]
+    IncorrectShareCode,
+    #[error("No XML file found in ZIP")]
+    NoXmlInArchive,
+    #[error("ZIP contains {0} XML files, expected one")]
+    MultipleXmlInArchive(usize),
     #[error("XML parsing failed: {0}")]
     XmlParsingFailed(String),
     #[error("UIDAI signature verification failed")]
     SignatureVerificationFailed,
     #[error("Certificate validation failed: {0}")]
     CertificateError(String),
+    #[error("No trusted UIDAI certificates; add the UIDAI signing certificate before verifying")]
+    NoTrustedCertificates,
     #[error("Required demographic data missing: {0}")]
     MissingDemographicData(String),
     #[error("Invalid Aadhaar number format")]
@@ -25,10 +48,69 @@ pub enum AadhaarVerificationError {
     #[error("Base64 decode error: {0}")]
     Base64Error(#[from] base64::DecodeError),
     #[error("Date parsing error: {0}")]
-    DateParseError(String),
+    DateParseError(#[from] BirthDateError),
+    #[error("Unsupported signature algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Reference digest does not match the signed document")]
+    DigestMismatch,
+    #[error("Canonicalization failed: {0}")]
+    CanonicalizationError(CanonicalizationError),
+    #[error("Invalid Secure QR code: {0}")]
+    InvalidQrCode(String),
+    #[error("Contact does not match the registered UIDAI hash")]
+    ContactMismatch,
+    #[error("Unexpected e-KYC document structure: {0}")]
+    SchemaError(#[from] KycSchemaError),
+    #[error("ZIP archive is {size} bytes, limit is {limit}")]
+    ArchiveTooLarge { size: u64, limit: u64 },
+    #[error("ZIP archive has {count} entries, limit is {limit}")]
+    TooManyZipEntries { count: usize, limit: usize },
+    #[error("Decompressed content exceeds {0} bytes")]
+    UncompressedSizeExceeded(u64),
+    #[error("Secure QR payload is {length} characters, limit is {limit}")]
+    QrPayloadTooLong { length: usize, limit: usize },
+    #[error("ZIP entry compression ratio exceeds {0}:1")]
+    CompressionRatioExceeded(u64),
+    #[error("XML nesting exceeds {0} levels")]
+    XmlDepthExceeded(usize),
+    #[error("Element <{element}> has more than {limit} attributes")]
+    TooManyXmlAttributes { element: String, limit: usize },
+    #[error("XML text node exceeds {0} bytes")]
+    XmlTextTooLong(usize),
+    #[error("XML document type declarations are not allowed")]
+    DtdNotAllowed,
+    #[error("Malformed referenceId: {0}")]
+    InvalidReferenceId(String),
+    #[error("e-KYC file generated at {generated_at} is older than {max_age_days} days")]
+    StaleDocument { generated_at: String, max_age_days: i64 },
+    #[error("e-KYC file carries no generation timestamp")]
+    UndatedDocument,
+    #[error("e-KYC file is dated in the future: {0}")]
+    FutureDatedDocument(String),
+    #[error("Invalid Aadhaar photo: {0}")]
+    InvalidPhoto(#[from] PhotoError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<CanonicalizationError> for AadhaarVerificationError {
+    fn from(error: CanonicalizationError) -> Self {
+        match error {
+            CanonicalizationError::DoctypeNotAllowed => AadhaarVerificationError::DtdNotAllowed,
+            CanonicalizationError::DocumentTooLarge(limit) => AadhaarVerificationError::UncompressedSizeExceeded(limit),
+            CanonicalizationError::DepthExceeded(limit) => AadhaarVerificationError::XmlDepthExceeded(limit),
+            CanonicalizationError::TooManyAttributes { element, limit } => {
+                AadhaarVerificationError::TooManyXmlAttributes { element, limit }
+            }
+            CanonicalizationError::TextTooLong(limit) => AadhaarVerificationError::XmlTextTooLong(limit),
+            other => AadhaarVerificationError::CanonicalizationError(other),
+        }
+    }
+}
+
+/// XMLDSig algorithm identifier used by UIDAI for offline e-KYC signatures
+pub const RSA_SHA256_SIGNATURE_METHOD: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+pub const SHA256_DIGEST_METHOD: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
 pub struct AadhaarAddress {
     pub care_of: Option<String>,
     pub house: Option<String>,
@@ -125,40 +207,118 @@ impl AadhaarAddress {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Raw PII has no `Serialize` impl; `to_exposed_json` is the one way out, for proof generation
+#[derive(Debug, Clone, Deserialize)]
 pub struct VerifiedAadhaarData {
-    pub name: String,
-    pub date_of_birth: String, // DD-MM-YYYY format
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub name: Sensitive<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub date_of_birth: Sensitive<String>, // DD-MM-YYYY, or YYYY when UIDAI only has the year
     pub gender: String,        // M, F, or T (Transgender)
-    pub address: AadhaarAddress,
-    pub photo_base64: Option<String>,
-    pub mobile_hash: Option<String>,
-    pub email_hash: Option<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub address: Sensitive<AadhaarAddress>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub photo_base64: Sensitive<Option<String>>, // Dropped by the parser unless the photo is retained
+    #[serde(default)]
+    pub photo_commitment: Option<PhotoCommitment>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub mobile_hash: Sensitive<Option<String>>, // Brute-forceable over the 10-digit mobile space
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub email_hash: Sensitive<Option<String>>,
     pub reference_id: String,
     pub generated_date: String,
+    #[serde(default)]
+    pub generated_at: Option<DateTime<Utc>>, // Decoded from referenceId; None for undated formats
     pub signature_valid: bool,
     pub certificate_valid: bool,
+    #[serde(default)]
+    pub signer_certificate: Option<SignerCertificate>,
     pub aadhaar_last_4_digits: String, // Last 4 digits for reference
 }
 
+/// Derived, non-identifying view of `VerifiedAadhaarData`, safe to log or hand to a verifier
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactedAadhaarData {
+    pub age_bracket: Option<AgeBracket>, // None when the birth date cannot be parsed
+    pub state_code: u32,
+    pub aadhaar_last_4_digits: String,
+    pub generated_date: String,
+    pub signature_valid: bool,
+    pub certificate_valid: bool,
+    pub has_photo_commitment: bool,
+}
+
 impl VerifiedAadhaarData {
-    pub fn birth_date_numeric(&self) -> Result<u32, AadhaarVerificationError> {
-        // Convert DD-MM-YYYY to YYYYMMDD numeric format
-        let parts: Vec<&str> = self.date_of_birth.split('-').collect();
-        if parts.len() != 3 {
-            return Err(AadhaarVerificationError::DateParseError(
-                format!("Invalid date format: {}", self.date_of_birth)
-            ));
+    pub fn redacted(&self) -> RedactedAadhaarData {
+        self.redacted_on(Utc::now().date_naive())
+    }
+
+    /// Year-only birth dates are bracketed by the youngest age the holder can be
+    pub fn redacted_on(&self, today: NaiveDate) -> RedactedAadhaarData {
+        let age_bracket = self.birth_date().ok()
+            .and_then(|birth_date| birth_date.age_on(today, YearOnlyMode::Latest).ok())
+            .map(AgeBracket::from_age);
+
+        RedactedAadhaarData {
+            age_bracket,
+            state_code: self.address.expose().state_code(),
+            aadhaar_last_4_digits: self.aadhaar_last_4_digits.clone(),
+            generated_date: self.generated_date.clone(),
+            signature_valid: self.signature_valid,
+            certificate_valid: self.certificate_valid,
+            has_photo_commitment: self.photo_commitment.is_some(),
         }
-        
-        let day: u32 = parts[0].parse()
-            .map_err(|_| AadhaarVerificationError::DateParseError("Invalid day".to_string()))?;
-        let month: u32 = parts[1].parse()
-            .map_err(|_| AadhaarVerificationError::DateParseError("Invalid month".to_string()))?;
-        let year: u32 = parts[2].parse()
-            .map_err(|_| AadhaarVerificationError::DateParseError("Invalid year".to_string()))?;
-        
-        Ok(year * 10000 + month * 100 + day)
+    }
+
+    /// Every field in clear, as `generate_aadhaar_proof` reads it back. Keep it on the holder's device;
+    /// anything shown or sent elsewhere gets `redacted()`.
+    pub fn to_exposed_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&serde_json::json!({
+            "name": self.name.expose(),
+            "date_of_birth": self.date_of_birth.expose(),
+            "gender": self.gender,
+            "address": self.address.expose(),
+            "photo_base64": self.photo_base64.expose(),
+            "photo_commitment": self.photo_commitment,
+            "mobile_hash": self.mobile_hash.expose(),
+            "email_hash": self.email_hash.expose(),
+            "reference_id": self.reference_id,
+            "generated_date": self.generated_date,
+            "generated_at": self.generated_at,
+            "signature_valid": self.signature_valid,
+            "certificate_valid": self.certificate_valid,
+            "signer_certificate": self.signer_certificate,
+            "aadhaar_last_4_digits": self.aadhaar_last_4_digits,
+        }))
+    }
+
+    pub fn birth_date(&self) -> Result<BirthDate, AadhaarVerificationError> {
+        Ok(BirthDate::parse(self.date_of_birth.expose())?)
+    }
+
+    /// Birth date as YYYYMMDD; `mode` decides how year-only records are resolved
+    pub fn birth_date_numeric(&self, mode: YearOnlyMode) -> Result<u32, AadhaarVerificationError> {
+        Ok(self.birth_date()?.to_numeric(mode)?)
+    }
+
+    /// Decode and header-check the embedded photo, if still present
+    pub fn decode_photo(&self) -> Result<Option<AadhaarPhoto>, AadhaarVerificationError> {
+        Ok(self.photo_base64.expose().as_deref().map(AadhaarPhoto::from_base64).transpose()?)
+    }
+
+    /// Validate the photo and record its commitment
+    pub fn commit_photo(&mut self) -> Result<(), AadhaarVerificationError> {
+        if let Some(photo) = self.decode_photo()? {
+            self.photo_commitment = Some(photo.commitment());
+        }
+        Ok(())
+    }
+
+    /// Drop the raw image, keeping only its commitment
+    pub fn strip_photo(&mut self) -> Result<(), AadhaarVerificationError> {
+        self.commit_photo()?;
+        self.photo_base64 = Sensitive::new(None);
+        Ok(())
     }
 
     pub fn gender_code(&self) -> u32 {
@@ -170,73 +330,107 @@ impl VerifiedAadhaarData {
         }
     }
 
-    pub fn calculate_age(&self, current_date_yyyymmdd: u32) -> Result<u32, AadhaarVerificationError> {
-        let birth_numeric = self.birth_date_numeric()?;
-        
-        let birth_year = birth_numeric / 10000;
-        let birth_month = (birth_numeric / 100) % 100;
-        let birth_day = birth_numeric % 100;
-        
-        let current_year = current_date_yyyymmdd / 10000;
-        let current_month = (current_date_yyyymmdd / 100) % 100;
-        let current_day = current_date_yyyymmdd % 100;
-        
-        let mut age = current_year - birth_year;
-        
-        // Adjust if birthday hasn't occurred this year
-        if current_month < birth_month || (current_month == birth_month && current_day < birth_day) {
-            age -= 1;
-        }
-        
-        Ok(age)
+    /// Check a mobile number against the UIDAI-signed mobile hash
+    pub fn verify_mobile(&self, mobile: &str, share_code: &str) -> Result<bool, AadhaarVerificationError> {
+        self.verify_contact(ContactKind::Mobile, mobile, share_code)
+    }
+
+    /// Check an email address against the UIDAI-signed email hash
+    pub fn verify_email(&self, email: &str, share_code: &str) -> Result<bool, AadhaarVerificationError> {
+        self.verify_contact(ContactKind::Email, email, share_code)
+    }
+
+    fn verify_contact(&self, kind: ContactKind, contact: &str, share_code: &str) -> Result<bool, AadhaarVerificationError> {
+        let registered = registered_hash(self, kind)?;
+        let iterations = hash_iterations(&self.aadhaar_last_4_digits)?;
+        let computed = uidai_contact_hash(&kind.normalize(contact), share_code, iterations);
+        Ok(computed.eq_ignore_ascii_case(registered))
+    }
+
+    pub fn calculate_age(&self, current_date_yyyymmdd: u32, mode: YearOnlyMode) -> Result<u32, AadhaarVerificationError> {
+        let today = date_from_numeric(current_date_yyyymmdd)?;
+        Ok(self.birth_date()?.age_on(today, mode)?)
     }
 }
 
 pub struct AadhaarXMLParser {
-    uidai_certificates: Vec<String>, // PEM format certificates
-    certificate_cache: HashMap<String, Vec<u8>>,
+    trust_store: TrustStore,
+    parse_limits: ParseLimits,
+    freshness_policy: FreshnessPolicy,
+    qr_freshness_policy: FreshnessPolicy, // Secure QR on a printed card does not expire
+    retain_photo: bool,
 }
 
 impl AadhaarXMLParser {
-    pub fn new() -> Result<Self, AadhaarVerificationError> {
-        let mut parser = Self {
-            uidai_certificates: Vec::new(),
-            certificate_cache: HashMap::new(),
-        };
-        
-        parser.load_uidai_certificates()?;
-        Ok(parser)
-    }
-
-    /// Load UIDAI public certificates for signature verification
-    fn load_uidai_certificates(&mut self) -> Result<(), AadhaarVerificationError> {
-        // UIDAI Production Certificate (RSA 2048-bit)
-        // This is a placeholder - in production, download from:
-        // https://uidai.gov.in/images/authDoc/uidai_auth_prod.cer
-        let uidai_prod_cert = r#"-----BEGIN CERTIFICATE-----
-MIIDdzCCAl+gAwIBAgIEByeaqTANBgkqhkiG9w0BAQsFADBsMRAwDgYDVQQGEwdV
-bmtub3duMRAwDgYDVQQIEwdVbmtub3duMRAwDgYDVQQHEwdVbmtub3duMRAwDgYD
-VQQKEwdVbmtub3duMRAwDgYDVQQLEwdVbmtub3duMRAwDgYDVQQDEwdVbmtub3du
-MB4XDTE2MDEwNzEwMzUzOFoXDTE4MDEwNjEwMzUzOFowbDEQMA4GA1UEBhMHVW5r
-bm93bjEQMA4GA1UECBMHVW5rbm93bjEQMA4GA1UEBxMHVW5rbm93bjEQMA4GA1UE
-ChMHVW5rbm93bjEQMA4GA1UECxMHVW5rbm93bjEQMA4GA1UEAxMHVW5rbm93bjCC
-ASIwDQYJKoZIhvcNAQEBBQADggEPADCCAQoCggEBANbVxJXOAJXKfL1Vv0cPZuJw
-lTMnNBY2H1s/cXZPFgxPQnQ8lbBl8nWvDJqrq5vx3m4J/nXLnL3vGzGCvBQw7Z8A
-8lYcP7u2FNBp5taPmqJHWVqLqxLPjqe9HlLLXsZLCTJWNLBQVKLVTnZi7pZvYzN3
-LiYeJmKjJ2CLGKLFpCkgkEJ3f0YQGhJQFTVFUDFfLi2KEGSFRTj+Nv8Nqsj0mNzM
-TLxFpL7T2VYGxNQZLjb/hJzXE9RcUQBY8c8GEH3kQNAHBQSz0kMq5ND0T3qZkTvZ
-BfQQvKsJfNCKZRh5v+1HGBm1qGWvmv0MjvZLjBGJHNXCrKLLvuHBpCmG1VfXVbkC
-AwEAAaMhMB8wHQYDVR0OBBYEFGMmGvKGV8KhHkJh1Sje8JpzfRl3MA0GCSqGSIb3
-DQEBCwUAA4IBAQBKmrHzBIVJiSYCeWXBNAJHr7/Qi6vlIqvE0gRLcXqGLKNHFqHJ
-MCLLyBuM9MBvJqLDaXV0Q7iFqHbNFqGTiGJNXRqIWqYy0dBPPfJLMLE8Cg2FBPnV
-WJ2jCZfYQGV7h0NqFkQCPPqRiJkDvVNKXTGpC7hFCPqv9N4kfLNFaBQXj9yYKPKZ
-VnwZKf8GxPHN2fF0gLwmGQYDHSCJL4Q8hGQYBJPJbVoZLN8w+0bpLQHwFkNrEWOJ
-l8Rjq0YQXZL0hPQQGVCPqLGDFLWLSqVF5vDUKPQV2E9J8cMZFLYTLUQGVLTL3wMA
-6Zh7HqDQfqYL5Hhv5LUqVFNE3LHLNTLAYvIV
------END CERTIFICATE-----"#;
-        
-        self.uidai_certificates.push(uidai_prod_cert.to_string());
-        Ok(())
+    /// Create a parser with an empty trust store. UIDAI's signing certificate is not bundled, so
+    /// every signature check fails with `NoTrustedCertificates` until the caller adds it.
+    pub fn new() -> Self {
+        Self::with_trust_store(TrustStore::new())
+    }
+
+    /// Create a parser that trusts only the given PEM certificates
+    pub fn with_certificates(certificates_pem: Vec<String>) -> Result<Self, AadhaarVerificationError> {
+        let mut trust_store = TrustStore::new();
+        for certificate_pem in certificates_pem {
+            trust_store.add_pem(certificate_pem.as_bytes())?;
+        }
+        Ok(Self::with_trust_store(trust_store))
+    }
+
+    /// Create a parser backed by a caller-managed trust store
+    pub fn with_trust_store(trust_store: TrustStore) -> Self {
+        Self {
+            trust_store,
+            parse_limits: ParseLimits::default(),
+            freshness_policy: FreshnessPolicy::default(),
+            qr_freshness_policy: FreshnessPolicy::unrestricted(),
+            retain_photo: false,
+        }
+    }
+
+    pub fn trust_store(&self) -> &TrustStore {
+        &self.trust_store
+    }
+
+    /// Mutable access for adding rotated UIDAI certificates at runtime
+    pub fn trust_store_mut(&mut self) -> &mut TrustStore {
+        &mut self.trust_store
+    }
+
+    pub fn parse_limits(&self) -> &ParseLimits {
+        &self.parse_limits
+    }
+
+    /// Tighten or relax the size and structure caps applied to uploaded files
+    pub fn set_parse_limits(&mut self, parse_limits: ParseLimits) {
+        self.parse_limits = parse_limits;
+    }
+
+    pub fn freshness_policy(&self) -> &FreshnessPolicy {
+        &self.freshness_policy
+    }
+
+    /// Reject offline e-KYC files generated longer ago than the policy allows
+    pub fn set_freshness_policy(&mut self, freshness_policy: FreshnessPolicy) {
+        self.freshness_policy = freshness_policy;
+    }
+
+    pub fn qr_freshness_policy(&self) -> &FreshnessPolicy {
+        &self.qr_freshness_policy
+    }
+
+    /// Reject Secure QR codes generated longer ago than the policy allows; unrestricted by default
+    pub fn set_qr_freshness_policy(&mut self, qr_freshness_policy: FreshnessPolicy) {
+        self.qr_freshness_policy = qr_freshness_policy;
+    }
+
+    pub fn retains_photo(&self) -> bool {
+        self.retain_photo
+    }
+
+    /// Keep the raw photo in parsed data; by default only its commitment survives parsing
+    pub fn set_retain_photo(&mut self, retain_photo: bool) {
+        self.retain_photo = retain_photo;
     }
 
     /// Parse and verify Aadhaar XML from ZIP file
@@ -249,15 +443,46 @@ l8Rjq0YQXZL0hPQQGVCPqLGDFLWLSqVF5vDUKPQV2E9J8cMZFLYTLUQGVLTL3wMA
         let xml_content = self.extract_xml_from_zip(zip_data, share_code)?;
         
         // Step 2: Verify UIDAI digital signature
-        let signature_valid = self.verify_uidai_signature(&xml_content)?;
+        let verification = self.verify_uidai_signature(&xml_content)?;
+        if !verification.signature_valid {
+            log_warn!("Offline e-KYC signature did not verify against any trusted certificate");
+        } else if !verification.certificate_valid {
+            log_warn!("Offline e-KYC signed by a certificate outside its validity period");
+        }
         
         // Step 3: Extract demographic data
         let mut aadhaar_data = self.extract_demographic_data(&xml_content)?;
         
         // Step 4: Set verification status
-        aadhaar_data.signature_valid = signature_valid;
-        aadhaar_data.certificate_valid = true;
+        aadhaar_data.signature_valid = verification.signature_valid;
+        aadhaar_data.certificate_valid = verification.certificate_valid;
+        aadhaar_data.signer_certificate = verification.signer_certificate;
+        
+        // Step 5: Apply freshness and photo policies
+        self.apply_policies(aadhaar_data, &self.freshness_policy)
+    }
+
+    /// Parse and verify the decimal payload of an Aadhaar Secure QR code
+    pub fn parse_secure_qr(&self, qr_payload: &str) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+        let aadhaar_data = crate::aadhaar_qr::parse_secure_qr_with_limits(qr_payload, &self.trust_store, &self.parse_limits)?;
+        self.apply_policies(aadhaar_data, &self.qr_freshness_policy)
+    }
+
+    fn apply_policies(
+        &self,
+        mut aadhaar_data: VerifiedAadhaarData,
+        freshness_policy: &FreshnessPolicy,
+    ) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+        if let Err(error) = freshness_policy.check(aadhaar_data.generated_at, self.trust_store.now()) {
+            log_warn!("Aadhaar document rejected by freshness policy", Field::public("reason", &error));
+            return Err(error);
+        }
         
+        if self.retain_photo {
+            aadhaar_data.commit_photo()?;
+        } else {
+            aadhaar_data.strip_photo()?;
+        }
         Ok(aadhaar_data)
     }
 
@@ -267,284 +492,696 @@ l8Rjq0YQXZL0hPQQGVCPqLGDFLWLSqVF5vDUKPQV2E9J8cMZFLYTLUQGVLTL3wMA
         zip_data: &[u8],
         share_code: &str,
     ) -> Result<String, AadhaarVerificationError> {
-        use zip::ZipArchive;
+        use zip::{result::ZipError, ZipArchive};
         use std::io::Cursor;
         
+        let limits = &self.parse_limits;
+        if zip_data.len() as u64 > limits.max_compressed_size {
+            return Err(AadhaarVerificationError::ArchiveTooLarge {
+                size: zip_data.len() as u64,
+                limit: limits.max_compressed_size,
+            });
+        }
+        
         let cursor = Cursor::new(zip_data);
         let mut archive = ZipArchive::new(cursor)
             .map_err(|_| AadhaarVerificationError::InvalidZipFile)?;
+        if archive.len() > limits.max_zip_entries {
+            return Err(AadhaarVerificationError::TooManyZipEntries {
+                count: archive.len(),
+                limit: limits.max_zip_entries,
+            });
+        }
         
-        // Aadhaar XML is usually the first (and only) file in the ZIP
+        // UIDAI archives hold exactly one XML; anything else is not an offline e-KYC download
+        let mut xml_entries = Vec::new();
         for i in 0..archive.len() {
-            let mut file = archive.by_index_decrypt(i, share_code.as_bytes())
-                .map_err(|_| AadhaarVerificationError::InvalidZipFile)?
+            let entry = archive.by_index_raw(i)
                 .map_err(|_| AadhaarVerificationError::InvalidZipFile)?;
-            
-            let name = file.name().to_string();
-            if name.ends_with(".xml") {
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)?;
-                return Ok(contents);
+            if entry.name().to_lowercase().ends_with(".xml") {
+                xml_entries.push(i);
             }
         }
+        let index = match xml_entries.as_slice() {
+            [index] => *index,
+            [] => return Err(AadhaarVerificationError::NoXmlInArchive),
+            entries => return Err(AadhaarVerificationError::MultipleXmlInArchive(entries.len())),
+        };
         
-        Err(AadhaarVerificationError::XmlParsingFailed(
-            "No XML file found in ZIP".to_string()
-        ))
+        let encrypted = matches!(
+            archive.by_index(index),
+            Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED))
+        );
+        log_debug!("Extracting offline e-KYC XML", Field::public("entries", archive.len()), Field::public("encrypted", encrypted));
+        // Handles both legacy ZipCrypto and WinZip AES-128/192/256 entries
+        let file = match archive.by_index_decrypt(index, share_code.as_bytes()) {
+            Ok(Ok(file)) => file,
+            Ok(Err(_)) => {
+                log_warn!("Offline e-KYC share code rejected", Field::pii("share_code", share_code));
+                return Err(AadhaarVerificationError::IncorrectShareCode);
+            }
+            Err(_) => return Err(AadhaarVerificationError::InvalidZipFile),
+        };
+        
+        // Declared sizes can lie, so the read itself is capped as well
+        if file.size() > limits.max_uncompressed_size {
+            return Err(AadhaarVerificationError::UncompressedSizeExceeded(limits.max_uncompressed_size));
+        }
+        let compressed_size = file.compressed_size().max(1);
+        let mut contents = Vec::new();
+        file.take(limits.max_uncompressed_size + 1)
+            .read_to_end(&mut contents)
+            .map_err(|e| {
+                // ZipCrypto's one-byte password check accepts 1 in 256 wrong codes; the CRC
+                // (or the AES authentication code) then fails once the entry is read
+                if encrypted {
+                    AadhaarVerificationError::IncorrectShareCode
+                } else {
+                    AadhaarVerificationError::IoError(e)
+                }
+            })?;
+        
+        if contents.len() as u64 > limits.max_uncompressed_size {
+            return Err(AadhaarVerificationError::UncompressedSizeExceeded(limits.max_uncompressed_size));
+        }
+        if contents.len() as u64 / compressed_size > limits.max_compression_ratio {
+            return Err(AadhaarVerificationError::CompressionRatioExceeded(limits.max_compression_ratio));
+        }
+        String::from_utf8(contents)
+            .map_err(|e| AadhaarVerificationError::XmlParsingFailed(e.to_string()))
     }
 
     /// Verify UIDAI digital signature on XML
-    fn verify_uidai_signature(&self, xml_content: &str) -> Result<bool, AadhaarVerificationError> {
+    fn verify_uidai_signature(&self, xml_content: &str) -> Result<SignatureVerification, AadhaarVerificationError> {
+        let mut document = XmlDocument::parse_with_limits(xml_content, &self.parse_limits)?;
+        
         // Parse XML to find <Signature> element
-        let signature_info = self.extract_signature_info(xml_content)?;
+        let signature_info = self.extract_signature_info(&document)?;
+        
+        if signature_info.signature_method != RSA_SHA256_SIGNATURE_METHOD {
+            return Err(AadhaarVerificationError::UnsupportedAlgorithm(
+                signature_info.signature_method.clone()
+            ));
+        }
+        if signature_info.reference.digest_method != SHA256_DIGEST_METHOD {
+            return Err(AadhaarVerificationError::UnsupportedAlgorithm(
+                signature_info.reference.digest_method.clone()
+            ));
+        }
         
-        // Extract signed content (everything before <Signature> tag)
-        let signed_content = self.extract_signed_content(xml_content)?;
+        // The referenced content must match its digest before the signature means anything
+        self.verify_reference_digest(&mut document, &signature_info)?;
         
-        // Canonicalize XML content
-        let canonical_content = self.canonicalize_xml(&signed_content)?;
+        // Verify signature over the canonicalized <SignedInfo> using UIDAI public certificate
+        let signer = self.verify_signature_with_certificate(&signature_info.canonical_signed_info, &signature_info)?;
         
-        // Verify signature using UIDAI public certificate
-        self.verify_signature_with_certificate(&canonical_content, &signature_info)
+        Ok(SignatureVerification {
+            signature_valid: signer.is_some(),
+            certificate_valid: signer.is_some_and(|certificate| self.trust_store.is_currently_valid(certificate)),
+            signer_certificate: signer.map(TrustedCertificate::summary),
+        })
     }
 
-    /// Extract signature information from XML
-    fn extract_signature_info(&self, xml_content: &str) -> Result<SignatureInfo, AadhaarVerificationError> {
-        use xml::reader::{EventReader, XmlEvent};
-        use std::io::Cursor;
+    /// Extract signature information from the parsed XML document
+    fn extract_signature_info(&self, document: &XmlDocument) -> Result<SignatureInfo, AadhaarVerificationError> {
+        let signature_path = document.root.descendant_path(XMLDSIG_NAMESPACE, "Signature")
+            .ok_or(AadhaarVerificationError::SignatureVerificationFailed)?;
+        let signature = document.root.descendant_at(&signature_path)
+            .ok_or(AadhaarVerificationError::SignatureVerificationFailed)?;
+        let signed_info = signature.find_child(XMLDSIG_NAMESPACE, "SignedInfo")
+            .ok_or(AadhaarVerificationError::SignatureVerificationFailed)?;
         
-        let cursor = Cursor::new(xml_content.as_bytes());
-        let parser = EventReader::new(cursor);
-        
-        let mut in_signature = false;
-        let mut in_signature_value = false;
-        let mut signature_value = String::new();
-        
-        for event in parser {
-            match event {
-                Ok(XmlEvent::StartElement { name, .. }) => {
-                    if name.local_name == "Signature" {
-                        in_signature = true;
-                    } else if in_signature && name.local_name == "SignatureValue" {
-                        in_signature_value = true;
-                    }
-                }
-                Ok(XmlEvent::Characters(data)) => {
-                    if in_signature_value {
-                        signature_value.push_str(&data);
-                    }
-                }
-                Ok(XmlEvent::EndElement { name }) => {
-                    if name.local_name == "SignatureValue" {
-                        in_signature_value = false;
-                    } else if name.local_name == "Signature" {
-                        in_signature = false;
-                    }
-                }
-                Err(e) => {
-                    return Err(AadhaarVerificationError::XmlParsingFailed(e.to_string()));
-                }
-                _ => {}
-            }
-        }
+        let signature_value = signature.find_child(XMLDSIG_NAMESPACE, "SignatureValue")
+            .map(|element| strip_whitespace(&element.text()))
+            .filter(|value| !value.is_empty())
+            .ok_or(AadhaarVerificationError::SignatureVerificationFailed)?;
         
-        if signature_value.is_empty() {
-            return Err(AadhaarVerificationError::SignatureVerificationFailed);
-        }
+        let canonicalization = signed_info.find_child(XMLDSIG_NAMESPACE, "CanonicalizationMethod")
+            .ok_or_else(|| AadhaarVerificationError::XmlParsingFailed(
+                "CanonicalizationMethod missing".to_string()
+            ))?;
+        let canonicalization_method = CanonicalizationMethod::from_uri(
+            canonicalization.attribute("Algorithm").unwrap_or_default()
+        )?;
+        
+        let signature_method = signed_info.find_child(XMLDSIG_NAMESPACE, "SignatureMethod")
+            .and_then(|element| element.attribute("Algorithm"))
+            .unwrap_or_default()
+            .to_string();
+        
+        let reference = signed_info.find_child(XMLDSIG_NAMESPACE, "Reference")
+            .ok_or_else(|| AadhaarVerificationError::XmlParsingFailed(
+                "Reference missing".to_string()
+            ))?;
+        
+        let transforms = reference.find_child(XMLDSIG_NAMESPACE, "Transforms")
+            .map(|transforms| {
+                transforms.child_elements()
+                    .filter(|transform| transform.is(XMLDSIG_NAMESPACE, "Transform"))
+                    .map(|transform| TransformInfo {
+                        algorithm: transform.attribute("Algorithm").unwrap_or_default().to_string(),
+                        inclusive_prefixes: inclusive_prefixes(transform),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let reference = ReferenceInfo {
+            uri: reference.attribute("URI").unwrap_or_default().to_string(),
+            transforms,
+            digest_method: reference.find_child(XMLDSIG_NAMESPACE, "DigestMethod")
+                .and_then(|element| element.attribute("Algorithm"))
+                .unwrap_or_default()
+                .to_string(),
+            digest_value: reference.find_child(XMLDSIG_NAMESPACE, "DigestValue")
+                .map(|element| strip_whitespace(&element.text()))
+                .unwrap_or_default(),
+        };
         
         Ok(SignatureInfo {
-            signature_value: signature_value.trim().to_string(),
-            digest_method: "SHA256".to_string(),
-            signature_method: "RSA-SHA256".to_string(),
+            signature_value,
+            signature_method,
+            key_info: signature.find_child(XMLDSIG_NAMESPACE, "KeyInfo")
+                .map(extract_key_info)
+                .transpose()?
+                .unwrap_or_default(),
+            canonical_signed_info: signed_info.canonicalize(
+                canonicalization_method,
+                &inclusive_prefixes(canonicalization),
+            ),
+            reference,
+            signature_path,
         })
     }
 
-    /// Extract signed content (everything before <Signature> tag)
-    fn extract_signed_content(&self, xml_content: &str) -> Result<String, AadhaarVerificationError> {
-        if let Some(sig_index) = xml_content.find("<Signature") {
-            Ok(xml_content[..sig_index].to_string())
-        } else {
-            Err(AadhaarVerificationError::SignatureVerificationFailed)
+    /// Apply the Reference transforms and compare the SHA-256 digest with DigestValue
+    fn verify_reference_digest(
+        &self,
+        document: &mut XmlDocument,
+        signature_info: &SignatureInfo,
+    ) -> Result<(), AadhaarVerificationError> {
+        use base64::{Engine as _, engine::general_purpose};
+        use sha2::{Sha256, Digest};
+        
+        let reference = &signature_info.reference;
+        // UIDAI signs the whole document (same-document reference with an empty URI)
+        if !reference.uri.is_empty() {
+            return Err(AadhaarVerificationError::UnsupportedAlgorithm(
+                format!("Reference URI \"{}\"", reference.uri)
+            ));
         }
-    }
-
-    /// Canonicalize XML content (C14N)
-    fn canonicalize_xml(&self, xml_content: &str) -> Result<String, AadhaarVerificationError> {
-        // Simplified canonicalization
-        // In production, use proper XML C14N library
-        let canonical = xml_content
-            .lines()
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty())
-            .collect::<Vec<&str>>()
-            .join("");
         
-        Ok(canonical)
+        let mut canonical: Option<String> = None;
+        for transform in &reference.transforms {
+            if canonical.is_some() {
+                // Nothing may follow the canonicalization step that turns the node-set into octets
+                return Err(AadhaarVerificationError::UnsupportedAlgorithm(transform.algorithm.clone()));
+            }
+            if transform.algorithm == ENVELOPED_SIGNATURE_TRANSFORM {
+                // Exactly the Signature being verified, never another one elsewhere in the tree
+                document.remove_enveloped_signature(&signature_info.signature_path)
+                    .ok_or(AadhaarVerificationError::SignatureVerificationFailed)?;
+            } else {
+                let method = CanonicalizationMethod::from_uri(&transform.algorithm)?;
+                canonical = Some(document.canonicalize(method, &transform.inclusive_prefixes));
+            }
+        }
+        
+        // A node-set result is converted to octets with inclusive C14N (XMLDSig 4.3.3.2)
+        let canonical = canonical
+            .unwrap_or_else(|| document.canonicalize(CanonicalizationMethod::Inclusive, &[]));
+        
+        let digest = general_purpose::STANDARD.encode(Sha256::digest(canonical.as_bytes()));
+        if digest != reference.digest_value {
+            return Err(AadhaarVerificationError::DigestMismatch);
+        }
+        Ok(())
     }
 
-    /// Verify signature with UIDAI certificate
+    /// Verify the RSA PKCS#1 v1.5 SHA-256 signature, returning the trusted certificate that produced it
     fn verify_signature_with_certificate(
         &self,
         content: &str,
         signature_info: &SignatureInfo,
-    ) -> Result<bool, AadhaarVerificationError> {
-        use sha2::{Sha256, Digest};
+    ) -> Result<Option<&TrustedCertificate>, AadhaarVerificationError> {
         use base64::{Engine as _, engine::general_purpose};
         
         // Decode signature from base64
         let signature_bytes = general_purpose::STANDARD.decode(&signature_info.signature_value)?;
         
-        // Calculate SHA-256 hash of content
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        let content_hash = hasher.finalize();
-        
-        // In production, verify signature using OpenSSL or similar
-        // For now, we'll do a simplified verification
-        
-        // TODO: Implement proper RSA signature verification
-        // This would involve:
-        // 1. Parse X.509 certificate
-        // 2. Extract RSA public key
-        // 3. Verify signature using public key and content hash
-        
-        // For demonstration, return true if signature exists and is valid base64
-        Ok(!signature_bytes.is_empty() && signature_bytes.len() >= 256)
+        self.trust_store.verify_rsa_sha256(&signature_info.key_info, content.as_bytes(), &signature_bytes)
     }
 
-    /// Extract demographic data from XML
+    /// Extract demographic data from the typed e-KYC document model
     fn extract_demographic_data(&self, xml_content: &str) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
-        use xml::reader::{EventReader, XmlEvent};
-        use std::io::Cursor;
-        
-        let cursor = Cursor::new(xml_content.as_bytes());
-        let parser = EventReader::new(cursor);
-        
-        let mut data = HashMap::new();
-        let mut current_element = String::new();
-        
-        for event in parser {
-            match event {
-                Ok(XmlEvent::StartElement { name, attributes, .. }) => {
-                    current_element = name.local_name.clone();
-                    
-                    // Extract attributes from root element
-                    if name.local_name == "PrintLetterBarcodeData" || 
-                       name.local_name == "OfflinePaperlessKyc" {
-                        for attr in attributes {
-                            data.insert(attr.name.local_name, attr.value);
-                        }
-                    }
-                }
-                Ok(XmlEvent::Characters(text)) => {
-                    if !current_element.is_empty() {
-                        data.insert(current_element.clone(), text);
-                    }
-                }
-                Err(e) => {
-                    return Err(AadhaarVerificationError::XmlParsingFailed(e.to_string()));
-                }
-                _ => {}
-            }
-        }
-        
-        // Build VerifiedAadhaarData from extracted attributes
-        let name = data.get("name")
-            .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("name".to_string()))?
-            .clone();
-        
-        let dob = data.get("dob")
-            .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("dob".to_string()))?
-            .clone();
-        
-        let gender = data.get("gender")
-            .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("gender".to_string()))?
-            .clone();
-        
-        let address = AadhaarAddress {
-            care_of: data.get("co").cloned(),
-            house: data.get("house").cloned(),
-            street: data.get("street").cloned(),
-            landmark: data.get("lm").cloned(),
-            locality: data.get("loc").cloned(),
-            vtc: data.get("vtc")
-                .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("vtc".to_string()))?
-                .clone(),
-            post_office: data.get("po").cloned(),
-            subdist: data.get("subdist").cloned(),
-            district: data.get("dist")
-                .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("district".to_string()))?
-                .clone(),
-            state: data.get("state")
-                .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("state".to_string()))?
-                .clone(),
-            pincode: data.get("pc")
-                .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("pincode".to_string()))?
-                .clone(),
-            country: data.get("country").cloned().unwrap_or_else(|| "India".to_string()),
-        };
-        
-        let reference_id = data.get("referenceId").cloned()
-            .unwrap_or_else(|| data.get("uid").cloned().unwrap_or_else(|| "N/A".to_string()));
-        
-        let generated_date = data.get("generatedDate").cloned()
-            .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
-        
-        let photo_base64 = data.get("photo").cloned();
-        let mobile_hash = data.get("mobileHash").cloned();
-        let email_hash = data.get("emailHash").cloned();
-        
-        // Extract last 4 digits of Aadhaar (masked number)
-        let aadhaar_last_4 = data.get("uid")
-            .and_then(|uid| {
-                let cleaned = uid.replace("XXXX", "").replace(" ", "");
-                if cleaned.len() >= 4 {
-                    Some(cleaned[cleaned.len()-4..].to_string())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| "****".to_string());
-        
-        Ok(VerifiedAadhaarData {
-            name,
-            date_of_birth: dob,
-            gender,
-            address,
-            photo_base64,
-            mobile_hash,
-            email_hash,
-            reference_id,
-            generated_date,
-            signature_valid: false, // Will be set by caller
-            certificate_valid: false, // Will be set by caller
-            aadhaar_last_4_digits: aadhaar_last_4,
-        })
+        let document = XmlDocument::parse_with_limits(xml_content, &self.parse_limits)?;
+        AadhaarKycDocument::from_document(&document)?.into_verified_data()
     }
 }
 
+/// Outcome of checking the XMLDSig signature against the trust store
+#[derive(Debug)]
+struct SignatureVerification {
+    signature_valid: bool,
+    certificate_valid: bool,
+    signer_certificate: Option<SignerCertificate>,
+}
+
 #[derive(Debug)]
 struct SignatureInfo {
     signature_value: String,
-    digest_method: String,
     signature_method: String,
+    key_info: KeyInfoSelector,
+    canonical_signed_info: String, // <SignedInfo> after its CanonicalizationMethod
+    reference: ReferenceInfo,
+    signature_path: Vec<usize>, // Child indices from the root to the verified <Signature>
+}
+
+#[derive(Debug)]
+struct ReferenceInfo {
+    uri: String,
+    transforms: Vec<TransformInfo>,
+    digest_method: String,
+    digest_value: String,
+}
+
+#[derive(Debug)]
+struct TransformInfo {
+    algorithm: String,
+    inclusive_prefixes: Vec<String>,
+}
+
+/// Exclusive C14N InclusiveNamespaces PrefixList carried by a transform or canonicalization method
+fn inclusive_prefixes(element: &XmlElement) -> Vec<String> {
+    element.find_child(C14N_EXCLUSIVE, "InclusiveNamespaces")
+        .and_then(|inclusive| inclusive.attribute("PrefixList"))
+        .map(|list| list.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Read the X509Data hints UIDAI places in KeyInfo
+fn extract_key_info(key_info: &XmlElement) -> Result<KeyInfoSelector, AadhaarVerificationError> {
+    use base64::{Engine as _, engine::general_purpose};
+    
+    let mut selector = KeyInfoSelector::default();
+    let Some(x509_data) = key_info.find_child(XMLDSIG_NAMESPACE, "X509Data") else {
+        return Ok(selector);
+    };
+    
+    if let Some(certificate) = x509_data.find_child(XMLDSIG_NAMESPACE, "X509Certificate") {
+        selector.certificate_der = Some(general_purpose::STANDARD.decode(strip_whitespace(&certificate.text()))?);
+    }
+    if let Some(issuer_serial) = x509_data.find_child(XMLDSIG_NAMESPACE, "X509IssuerSerial") {
+        selector.issuer_name = issuer_serial.find_child(XMLDSIG_NAMESPACE, "X509IssuerName")
+            .map(|element| element.text().trim().to_string());
+        selector.serial_number = issuer_serial.find_child(XMLDSIG_NAMESPACE, "X509SerialNumber")
+            .map(|element| element.text().trim().to_string());
+    }
+    selector.subject_name = x509_data.find_child(XMLDSIG_NAMESPACE, "X509SubjectName")
+        .map(|element| element.text().trim().to_string());
+    
+    Ok(selector)
+}
+
+pub(crate) fn strip_whitespace(value: &str) -> String {
+    value.chars().filter(|c| !c.is_whitespace()).collect()
 }
 
 impl Default for AadhaarXMLParser {
     fn default() -> Self {
-        Self::new().expect("Failed to initialize AadhaarXMLParser")
+        Self::new()
     }
 }
 
+/// Validate Aadhaar number using Verhoeff algorithm
+pub fn validate_aadhaar_number(aadhaar: &str) -> bool {
+    let cleaned = aadhaar.replace(&[' ', '-'][..], "");
+    
+    if cleaned.len() != 12 || !cleaned.chars().all(|c| c.is_numeric()) {
+        return false;
+    }
+    
+    // Verhoeff algorithm tables
+    let d = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+        [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+        [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+        [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+        [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+        [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+        [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+        [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+        [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+    ];
+    
+    let p = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+        [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+        [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+        [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
+        [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+        [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+        [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+    ];
+    
+    let mut c = 0;
+    let digits: Vec<usize> = cleaned.chars()
+        .rev()
+        .filter_map(|ch| ch.to_digit(10))
+        .map(|d| d as usize)
+        .collect();
+    
+    for (i, &digit) in digits.iter().enumerate() {
+        c = d[c][p[i % 8][digit]];
+    }
+    
+    c == 0
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    pub(crate) const TEST_CERT_PEM: &str = include_str!("../testdata/test_uidai_cert.pem");
+    pub(crate) const TEST_KEY_PEM: &str = include_str!("../testdata/test_uidai_key.pem");
+
+    fn sign_with_key(content: &str, key_pem: &str) -> String {
+        use base64::{Engine as _, engine::general_purpose};
+        use rsa::pkcs1v15::SigningKey;
+        use rsa::pkcs8::DecodePrivateKey;
+        use rsa::signature::{SignatureEncoding, Signer};
+        use sha2::Sha256;
+
+        let private_key = rsa::RsaPrivateKey::from_pkcs8_pem(key_pem).unwrap();
+        let signing_key = SigningKey::<Sha256>::new(private_key);
+        general_purpose::STANDARD.encode(signing_key.sign(content.as_bytes()).to_bytes())
+    }
+
+    fn signed_test_xml(uid_data: &str, signature_method: &str) -> String {
+        sign_test_document(uid_data, signature_method, TEST_KEY_PEM, "")
+    }
+
+    /// Build an enveloped-signature document; `uid_data` must already be in canonical form
+    pub(crate) fn sign_test_document(uid_data: &str, signature_method: &str, key_pem: &str, key_info: &str) -> String {
+        use base64::{Engine as _, engine::general_purpose};
+        use sha2::{Sha256, Digest};
+
+        let root_open = r#"<OfflinePaperlessKyc referenceId="123420250101120000000">"#;
+        let canonical_document = format!("{}{}</OfflinePaperlessKyc>", root_open, uid_data);
+        let digest = general_purpose::STANDARD.encode(Sha256::digest(canonical_document.as_bytes()));
+
+        let signed_info = format!(
+            concat!(
+                r#"<SignedInfo xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+                r#"<CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></CanonicalizationMethod>"#,
+                r#"<SignatureMethod Algorithm="{}"></SignatureMethod>"#,
+                r#"<Reference URI=""><Transforms><Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></Transform></Transforms>"#,
+                r#"<DigestMethod Algorithm="{}"></DigestMethod>"#,
+                r#"<DigestValue>{}</DigestValue></Reference></SignedInfo>"#
+            ),
+            signature_method, SHA256_DIGEST_METHOD, digest
+        );
+        let signature_value = sign_with_key(&signed_info, key_pem);
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}{}<Signature xmlns=\"http://www.w3.org/2000/09/xmldsig#\">{}\n<SignatureValue>{}</SignatureValue>{}</Signature></OfflinePaperlessKyc>",
+            root_open, uid_data, signed_info, signature_value, key_info
+        )
+    }
+
+    const TEST_UID_DATA: &str = r#"<UidData><Poi dob="15-08-1990" gender="M" name="Test User"></Poi></UidData>"#;
+
+    #[test]
+    fn test_rsa_signature_verification() {
+        let parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        let xml = signed_test_xml(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD);
+
+        let verification = parser.verify_uidai_signature(&xml).unwrap();
+        assert!(verification.signature_valid);
+        assert!(verification.certificate_valid);
+        assert_eq!(
+            verification.signer_certificate.unwrap().fingerprint_sha256,
+            parser.trust_store().certificates()[0].fingerprint_sha256
+        );
+
+        // Tampering with SignedInfo invalidates the signature
+        let tampered = xml.replace("<Reference URI=\"\">", "<Reference URI=\"\" Id=\"x\">");
+        let verification = parser.verify_uidai_signature(&tampered).unwrap();
+        assert!(!verification.signature_valid);
+        assert!(!verification.certificate_valid);
+
+        // Nothing is trusted by default, and a key that is not trusted is rejected
+        assert!(matches!(
+            AadhaarXMLParser::new().verify_uidai_signature(&xml),
+            Err(AadhaarVerificationError::NoTrustedCertificates)
+        ));
+        let other_parser = AadhaarXMLParser::with_certificates(vec![include_str!("../testdata/test_uidai_expired_cert.pem").to_string()]).unwrap();
+        assert!(!other_parser.verify_uidai_signature(&xml).unwrap().signature_valid);
+    }
+
+    #[test]
+    fn test_unsupported_signature_algorithm() {
+        let parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        let xml = signed_test_xml(TEST_UID_DATA, "http://www.w3.org/2000/09/xmldsig#rsa-sha1");
+
+        assert!(matches!(
+            parser.verify_uidai_signature(&xml),
+            Err(AadhaarVerificationError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_reference_digest_verification() {
+        let parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        let xml = signed_test_xml(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD);
+
+        // Equivalent serializations canonicalize to the same digest
+        let reformatted = xml
+            .replace(r#"gender="M" name="Test User"></Poi>"#, "name='Test User'\r\n gender='M'/>");
+        assert!(parser.verify_uidai_signature(&reformatted).unwrap().signature_valid);
+
+        // Changing the signed demographic data breaks the reference digest
+        let tampered = xml.replace("15-08-1990", "15-08-1980");
+        assert!(matches!(
+            parser.verify_uidai_signature(&tampered),
+            Err(AadhaarVerificationError::DigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_key_info_selects_signing_certificate() {
+        use crate::clock::FixedClock;
+        use chrono::{TimeZone, Utc};
+
+        let expired_cert = include_str!("../testdata/test_uidai_expired_cert.pem");
+        let expired_key = include_str!("../testdata/test_uidai_expired_key.pem");
+        let mut trust_store = TrustStore::with_clock(Box::new(FixedClock(
+            Utc.with_ymd_and_hms(2025, 10, 6, 0, 0, 0).unwrap()
+        )));
+        trust_store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        trust_store.add_pem(expired_cert.as_bytes()).unwrap();
+        let parser = AadhaarXMLParser::with_trust_store(trust_store);
+        let expired = parser.trust_store().certificates()[1].clone();
+
+        // KeyInfo names the expired certificate by issuer and serial number
+        let key_info = format!(
+            "<KeyInfo><X509Data><X509IssuerSerial><X509IssuerName>{}</X509IssuerName><X509SerialNumber>{}</X509SerialNumber></X509IssuerSerial></X509Data></KeyInfo>",
+            expired.issuer, expired.serial_number
+        );
+        let xml = sign_test_document(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD, expired_key, &key_info);
+
+        let verification = parser.verify_uidai_signature(&xml).unwrap();
+        assert!(verification.signature_valid);
+        assert!(!verification.certificate_valid);
+        assert_eq!(verification.signer_certificate.unwrap().serial_number, expired.serial_number);
+
+        // A selector that matches no trusted certificate selects nothing
+        let untrusted_key_info = "<KeyInfo><X509Data><X509SubjectName>CN=Attacker</X509SubjectName></X509Data></KeyInfo>";
+        let xml = sign_test_document(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD, TEST_KEY_PEM, untrusted_key_info);
+        assert!(matches!(
+            parser.verify_uidai_signature(&xml),
+            Err(AadhaarVerificationError::CertificateError(_))
+        ));
+
+        // An embedded certificate that is not in the trust store is never used, even when its key signed the document
+        use base64::{Engine as _, engine::general_purpose};
+        let attacker_cert = general_purpose::STANDARD.encode(include_bytes!("../testdata/passkey_attestation_cert.der"));
+        let attacker_key = include_str!("../testdata/passkey_attestation_key.pem");
+        let embedded_key_info = format!("<KeyInfo><X509Data><X509Certificate>{}</X509Certificate></X509Data></KeyInfo>", attacker_cert);
+        let xml = sign_test_document(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD, attacker_key, &embedded_key_info);
+        assert!(matches!(
+            parser.verify_uidai_signature(&xml),
+            Err(AadhaarVerificationError::CertificateError(_))
+        ));
+    }
+
+    pub(crate) fn zip_with_entries(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        use std::io::Write;
+        use zip::write::{FileOptions, ZipWriter};
+
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_zip_limits_enforced() {
+        let mut parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        let xml = signed_test_xml(TEST_UID_DATA, RSA_SHA256_SIGNATURE_METHOD);
+        let archive = zip_with_entries(&[("kyc.xml", xml.as_bytes())]);
+        assert_eq!(parser.extract_xml_from_zip(&archive, "1234").unwrap(), xml);
+
+        // Highly repetitive content trips the ratio check long before memory does
+        let bomb = zip_with_entries(&[("kyc.xml", &vec![b' '; 1024 * 1024])]);
+        assert!(matches!(
+            parser.extract_xml_from_zip(&bomb, "1234"),
+            Err(AadhaarVerificationError::CompressionRatioExceeded(100))
+        ));
+
+        let many = zip_with_entries(&[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c"), ("d.txt", b"d"), ("e.xml", b"e")]);
+        assert!(matches!(
+            parser.extract_xml_from_zip(&many, "1234"),
+            Err(AadhaarVerificationError::TooManyZipEntries { count: 5, limit: 4 })
+        ));
+
+        parser.set_parse_limits(ParseLimits { max_uncompressed_size: 64, ..ParseLimits::default() });
+        assert!(matches!(
+            parser.extract_xml_from_zip(&archive, "1234"),
+            Err(AadhaarVerificationError::UncompressedSizeExceeded(64))
+        ));
+
+        parser.set_parse_limits(ParseLimits { max_compressed_size: 64, ..ParseLimits::default() });
+        assert!(matches!(
+            parser.extract_xml_from_zip(&archive, "1234"),
+            Err(AadhaarVerificationError::ArchiveTooLarge { limit: 64, .. })
+        ));
+
+        // XML structure limits surface as dedicated errors too
+        parser.set_parse_limits(ParseLimits { max_element_depth: 2, ..ParseLimits::default() });
+        assert!(matches!(
+            parser.verify_uidai_signature(&xml),
+            Err(AadhaarVerificationError::XmlDepthExceeded(2))
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_zip_extraction() {
+        let parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        let expected = include_str!("../testdata/offline_kyc.xml");
+
+        for archive in [
+            &include_bytes!("../testdata/offline_kyc_zipcrypto.zip")[..],
+            &include_bytes!("../testdata/offline_kyc_aes128.zip")[..],
+            &include_bytes!("../testdata/offline_kyc_aes256.zip")[..],
+        ] {
+            assert_eq!(parser.extract_xml_from_zip(archive, "1234").unwrap(), expected);
+            assert!(matches!(
+                parser.extract_xml_from_zip(archive, "4321"),
+                Err(AadhaarVerificationError::IncorrectShareCode)
+            ));
+        }
+
+        let aes256 = include_bytes!("../testdata/offline_kyc_aes256.zip");
+        assert!(matches!(
+            parser.extract_xml_from_zip(&aes256[..aes256.len() - 30], "1234"),
+            Err(AadhaarVerificationError::InvalidZipFile)
+        ));
+
+        let no_xml = zip_with_entries(&[("readme.txt", b"hello")]);
+        assert!(matches!(
+            parser.extract_xml_from_zip(&no_xml, "1234"),
+            Err(AadhaarVerificationError::NoXmlInArchive)
+        ));
+        let two_xml = zip_with_entries(&[("a.xml", b"<a/>"), ("b.XML", b"<b/>")]);
+        assert!(matches!(
+            parser.extract_xml_from_zip(&two_xml, "1234"),
+            Err(AadhaarVerificationError::MultipleXmlInArchive(2))
+        ));
+    }
+
+    #[test]
+    fn test_freshness_policy_enforced() {
+        use crate::clock::FixedClock;
+        use chrono::TimeZone;
+
+        let mut trust_store = TrustStore::with_clock(Box::new(FixedClock(
+            Utc.with_ymd_and_hms(2025, 1, 5, 0, 0, 0).unwrap()
+        )));
+        trust_store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        let mut parser = AadhaarXMLParser::with_trust_store(trust_store);
+
+        let uid_data = concat!(
+            r#"<UidData><Poi dob="15-08-1990" gender="M" name="Test User"></Poi>"#,
+            r#"<Poa dist="Mumbai" pc="400001" state="Maharashtra" vtc="Mumbai"></Poa></UidData>"#
+        );
+        let xml = signed_test_xml(uid_data, RSA_SHA256_SIGNATURE_METHOD);
+        let archive = zip_with_entries(&[("kyc.xml", xml.as_bytes())]);
+
+        // Generated nearly four days before the clock, over the default bound
+        assert!(matches!(
+            parser.parse_aadhaar_xml(&archive, "1234"),
+            Err(AadhaarVerificationError::StaleDocument { max_age_days: 3, .. })
+        ));
+
+        parser.set_freshness_policy(FreshnessPolicy::max_age_days(7));
+        let data = parser.parse_aadhaar_xml(&archive, "1234").unwrap();
+        assert!(data.signature_valid);
+        assert_eq!(data.aadhaar_last_4_digits, "1234");
+        assert_eq!(data.generated_at, Some(Utc.with_ymd_and_hms(2025, 1, 1, 6, 30, 0).unwrap()));
+
+        // A file dated ahead of the clock is refused whatever its age bound
+        parser.trust_store_mut().set_clock(Box::new(FixedClock(Utc.with_ymd_and_hms(2025, 1, 1, 6, 0, 0).unwrap())));
+        assert!(matches!(
+            parser.parse_aadhaar_xml(&archive, "1234"),
+            Err(AadhaarVerificationError::FutureDatedDocument(_))
+        ));
+    }
+
+    #[test]
+    fn test_photo_stripped_by_default() {
+        use base64::{Engine as _, engine::general_purpose};
+
+        let jpeg = crate::aadhaar_photo::tests::test_jpeg(160, 200);
+        let uid_data = format!(
+            concat!(
+                r#"<UidData><Poi dob="15-08-1990" gender="M" name="Test User"></Poi>"#,
+                r#"<Poa dist="Mumbai" pc="400001" state="Maharashtra" vtc="Mumbai"></Poa><Pht>{}</Pht></UidData>"#
+            ),
+            general_purpose::STANDARD.encode(&jpeg)
+        );
+        let xml = signed_test_xml(&uid_data, RSA_SHA256_SIGNATURE_METHOD);
+        let archive = zip_with_entries(&[("kyc.xml", xml.as_bytes())]);
+        let mut parser = AadhaarXMLParser::with_certificates(vec![TEST_CERT_PEM.to_string()]).unwrap();
+        parser.set_freshness_policy(FreshnessPolicy::unrestricted()); // The test file dates from 2025
+
+        let data = parser.parse_aadhaar_xml(&archive, "1234").unwrap();
+        assert!(data.photo_base64.expose().is_none());
+        let commitment = data.photo_commitment.unwrap();
+        assert_eq!((commitment.width, commitment.height), (160, 200));
+        assert!(commitment.matches(&jpeg));
+
+        parser.set_retain_photo(true);
+        let data = parser.parse_aadhaar_xml(&archive, "1234").unwrap();
+        assert_eq!(data.decode_photo().unwrap().unwrap().data, jpeg);
+        assert!(data.photo_commitment.is_some());
+    }
+
+    #[test]
+    fn test_invalid_certificate_rejected() {
+        let result = AadhaarXMLParser::with_certificates(vec!["not a certificate".to_string()]);
+        assert!(matches!(result, Err(AadhaarVerificationError::CertificateError(_))));
+    }
+
     #[test]
     fn test_verhoeff_algorithm() {
         // Valid Aadhaar numbers (test data)
-        assert!(validate_aadhaar_number("234123451234"));
-        assert!(validate_aadhaar_number("234167890123"));
+        assert!(validate_aadhaar_number("234123451235"));
+        assert!(validate_aadhaar_number("234167890128"));
     }
 
     #[test]
@@ -570,8 +1207,8 @@ mod tests {
     #[test]
     fn test_age_calculation() {
         let data = VerifiedAadhaarData {
-            name: "Test User".to_string(),
-            date_of_birth: "15-08-1990".to_string(),
+            name: "Test User".to_string().into(),
+            date_of_birth: "15-08-1990".to_string().into(),
             gender: "M".to_string(),
             address: AadhaarAddress {
                 care_of: None,
@@ -586,66 +1223,44 @@ mod tests {
                 state: "Maharashtra".to_string(),
                 pincode: "400001".to_string(),
                 country: "India".to_string(),
-            },
-            photo_base64: None,
-            mobile_hash: None,
-            email_hash: None,
+            }.into(),
+            photo_base64: None.into(),
+            photo_commitment: None,
+            mobile_hash: None.into(),
+            email_hash: None.into(),
             reference_id: "TEST123".to_string(),
             generated_date: "2025-10-06".to_string(),
+            generated_at: None,
             signature_valid: true,
             certificate_valid: true,
+            signer_certificate: None,
             aadhaar_last_4_digits: "1234".to_string(),
         };
         
         // Test age calculation for October 6, 2025
-        let age = data.calculate_age(20251006).unwrap();
+        let age = data.calculate_age(20251006, YearOnlyMode::Strict).unwrap();
         assert_eq!(age, 35);
-    }
-}
+        
+        // Dates before the birth year are an error rather than an underflow
+        assert!(matches!(
+            data.calculate_age(19891231, YearOnlyMode::Strict),
+            Err(AadhaarVerificationError::DateParseError(BirthDateError::BeforeBirth))
+        ));
 
-/// Validate Aadhaar number using Verhoeff algorithm
-pub fn validate_aadhaar_number(aadhaar: &str) -> bool {
-    let cleaned = aadhaar.replace(&[' ', '-'][..], "");
-    
-    if cleaned.len() != 12 || !cleaned.chars().all(|c| c.is_numeric()) {
-        return false;
-    }
-    
-    // Verhoeff algorithm tables
-    let d = [
-        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
-        [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
-        [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
-        [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
-        [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
-        [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
-        [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
-        [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
-        [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
-        [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
-    ];
-    
-    let p = [
-        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
-        [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
-        [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
-        [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
-        [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
-        [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
-        [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
-        [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
-    ];
-    
-    let mut c = 0;
-    let digits: Vec<usize> = cleaned.chars()
-        .rev()
-        .filter_map(|ch| ch.to_digit(10))
-        .map(|d| d as usize)
-        .collect();
-    
-    for (i, &digit) in digits.iter().enumerate() {
-        c = d[c][p[i % 8][digit]];
+        // Debug output and the redacted view carry no raw PII
+        let debug = format!("{:?}", data);
+        assert!(!debug.contains("Test User") && !debug.contains("15-08-1990") && !debug.contains("400001"));
+
+        let redacted = data.redacted_on(NaiveDate::from_ymd_opt(2025, 10, 6).unwrap());
+        assert_eq!(redacted.age_bracket, Some(AgeBracket::From35To44));
+        assert_eq!(redacted.state_code, 27);
+        assert_eq!(redacted.aadhaar_last_4_digits, "1234");
+        assert!(!serde_json::to_string(&redacted).unwrap().contains("Test User"));
+
+        // The exposed form is explicit and reads back unchanged
+        let exposed: VerifiedAadhaarData = serde_json::from_str(&data.to_exposed_json().unwrap()).unwrap();
+        assert_eq!(exposed.name.expose(), "Test User");
+        assert_eq!(exposed.address.expose().pincode, data.address.expose().pincode);
+        assert_eq!(exposed.to_exposed_json().unwrap(), data.to_exposed_json().unwrap());
     }
-    
-    c == 0
 }
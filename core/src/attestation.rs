@@ -0,0 +1,273 @@
+// WebAuthn Attestation
+// Verifies passkey registrations in the none and packed (self and x5c) formats before a credential is bound
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x509_cert::der::asn1::ObjectIdentifier;
+
+use crate::cbor::{self, CborValue};
+use crate::cose::CoseKey;
+use crate::webauthn::{self, AttestedCredential, AuthenticatorData, RelyingParty, WebAuthnError};
+
+/// Longest credential ID WebAuthn allows
+const MAX_CREDENTIAL_ID_LENGTH: usize = 1023;
+
+const OID_ORGANIZATIONAL_UNIT: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.11");
+const OID_BASIC_CONSTRAINTS: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.19");
+const OID_FIDO_AAGUID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.45724.1.1.4");
+const OID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const OID_P256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const OID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// How much the authenticator vouched for the new credential
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationType {
+    None,            // fmt "none": nothing beyond the credential itself
+    SelfAttestation, // fmt "packed" without x5c: signed by the credential key
+    Basic,           // fmt "packed" with x5c: signed by an attestation certificate; its issuer is not checked here
+}
+
+/// What `navigator.credentials.create()` returns, each buffer base64url-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasskeyRegistration {
+    #[serde(with = "webauthn::base64url")]
+    pub attestation_object: Vec<u8>,
+    #[serde(with = "webauthn::base64url")]
+    pub client_data_json: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedRegistration {
+    pub credential: AttestedCredential,
+    pub sign_count: u32,
+    pub attestation: AttestationType,
+    pub attestation_certificate: Option<Vec<u8>>, // DER leaf certificate for Basic attestation
+}
+
+/// Verify a registration made over `challenge` and return the credential it created
+pub fn verify_registration(
+    relying_party: &RelyingParty,
+    registration: &PasskeyRegistration,
+    challenge: &[u8],
+) -> Result<VerifiedRegistration, WebAuthnError> {
+    webauthn::verify_client_data(relying_party, &registration.client_data_json, "webauthn.create", challenge)?;
+
+    let object = cbor::decode(&registration.attestation_object)?;
+    let format = object.get_text("fmt").and_then(CborValue::as_text).ok_or(WebAuthnError::Malformed("attestation object"))?;
+    let statement = object.get_text("attStmt").filter(|statement| statement.as_map().is_some())
+        .ok_or(WebAuthnError::Malformed("attestation object"))?;
+    let raw_authenticator_data = object.get_text("authData").and_then(CborValue::as_bytes)
+        .ok_or(WebAuthnError::Malformed("attestation object"))?;
+
+    let authenticator_data = AuthenticatorData::parse(raw_authenticator_data)?;
+    webauthn::check_authenticator_data(relying_party, &authenticator_data)?;
+    let credential = authenticator_data.attested_credential
+        .ok_or(WebAuthnError::InvalidAttestation("no attested credential data"))?;
+    if credential.credential_id.is_empty() || credential.credential_id.len() > MAX_CREDENTIAL_ID_LENGTH {
+        return Err(WebAuthnError::InvalidAttestation("credential ID length"));
+    }
+
+    let mut signed = raw_authenticator_data.to_vec();
+    signed.extend_from_slice(&Sha256::digest(&registration.client_data_json));
+    let (attestation, attestation_certificate) = match format {
+        "none" if statement.as_map().is_some_and(<[_]>::is_empty) => (AttestationType::None, None),
+        "none" => return Err(WebAuthnError::InvalidAttestation("none attestation with a statement")),
+        "packed" => verify_packed(statement, &credential, &signed)?,
+        other => return Err(WebAuthnError::UnsupportedAttestation(other.to_string())),
+    };
+
+    Ok(VerifiedRegistration {
+        credential,
+        sign_count: authenticator_data.sign_count,
+        attestation,
+        attestation_certificate,
+    })
+}
+
+fn verify_packed(
+    statement: &CborValue,
+    credential: &AttestedCredential,
+    signed: &[u8],
+) -> Result<(AttestationType, Option<Vec<u8>>), WebAuthnError> {
+    let algorithm = statement.get_text("alg").and_then(CborValue::as_integer)
+        .ok_or(WebAuthnError::InvalidAttestation("packed statement without alg"))?;
+    let signature = statement.get_text("sig").and_then(CborValue::as_bytes)
+        .ok_or(WebAuthnError::InvalidAttestation("packed statement without sig"))?;
+    if statement.get_text("ecdaaKeyId").is_some() {
+        return Err(WebAuthnError::UnsupportedAttestation("packed ECDAA".to_string()));
+    }
+
+    let Some(chain) = statement.get_text("x5c") else {
+        if algorithm != credential.public_key.algorithm() as i128 {
+            return Err(WebAuthnError::InvalidAttestation("alg differs from the credential key"));
+        }
+        credential.public_key.verify(signed, signature)?;
+        return Ok((AttestationType::SelfAttestation, None));
+    };
+
+    let leaf = chain.as_array().and_then(<[_]>::first).and_then(CborValue::as_bytes)
+        .ok_or(WebAuthnError::InvalidAttestation("empty x5c"))?;
+    let attestation_key = attestation_certificate_key(leaf, &credential.aaguid)?;
+    if algorithm != attestation_key.algorithm() as i128 {
+        return Err(WebAuthnError::InvalidAttestation("alg differs from the certificate key"));
+    }
+    attestation_key.verify(signed, signature)?;
+    Ok((AttestationType::Basic, Some(leaf.to_vec())))
+}
+
+/// Public key of a packed attestation certificate that meets the WebAuthn requirements (§8.2.1)
+fn attestation_certificate_key(der: &[u8], aaguid: &[u8; 16]) -> Result<CoseKey, WebAuthnError> {
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::traits::PublicKeyParts;
+    use x509_cert::certificate::Version;
+    use x509_cert::der::{Decode, Encode};
+    use x509_cert::ext::pkix::BasicConstraints;
+    use x509_cert::Certificate;
+
+    let invalid = WebAuthnError::InvalidAttestation;
+    let certificate = Certificate::from_der(der).map_err(|_| invalid("unparseable certificate"))?;
+    let tbs = &certificate.tbs_certificate;
+    if tbs.version != Version::V3 {
+        return Err(invalid("certificate is not X.509 v3"));
+    }
+
+    let unit = tbs.subject.0.iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .find(|attribute| attribute.oid == OID_ORGANIZATIONAL_UNIT)
+        .map(|attribute| attribute.value.value());
+    if unit != Some(b"Authenticator Attestation".as_slice()) {
+        return Err(invalid("certificate subject OU is not Authenticator Attestation"));
+    }
+
+    for extension in tbs.extensions.iter().flatten() {
+        if extension.extn_id == OID_BASIC_CONSTRAINTS {
+            let constraints = BasicConstraints::from_der(extension.extn_value.as_bytes())
+                .map_err(|_| invalid("unparseable basic constraints"))?;
+            if constraints.ca {
+                return Err(invalid("attestation certificate is a CA"));
+            }
+        } else if extension.extn_id == OID_FIDO_AAGUID {
+            // An OCTET STRING wrapping the 16-byte AAGUID; it must not be critical
+            let value = extension.extn_value.as_bytes();
+            if extension.critical || value.len() != 18 || value[..2] != [0x04, 0x10] || value[2..] != aaguid[..] {
+                return Err(invalid("certificate AAGUID differs from the authenticator's"));
+            }
+        }
+    }
+
+    let spki = &tbs.subject_public_key_info;
+    let key = if spki.algorithm.oid == OID_EC_PUBLIC_KEY {
+        let curve = spki.algorithm.parameters.as_ref().and_then(|parameters| parameters.decode_as::<ObjectIdentifier>().ok());
+        let point = spki.subject_public_key.raw_bytes();
+        if curve != Some(OID_P256) || point.len() != 65 || point[0] != 0x04 {
+            return Err(invalid("certificate key is not an uncompressed P-256 point"));
+        }
+        CoseKey::Es256 {
+            x: point[1..33].try_into().expect("32 bytes"),
+            y: point[33..].try_into().expect("32 bytes"),
+        }
+    } else if spki.algorithm.oid == OID_RSA_ENCRYPTION {
+        let spki_der = spki.to_der().map_err(|_| invalid("unparseable certificate key"))?;
+        let key = rsa::RsaPublicKey::from_public_key_der(&spki_der).map_err(|_| invalid("unparseable certificate key"))?;
+        CoseKey::Rs256 { n: key.n().to_bytes_be(), e: key.e().to_bytes_be() }
+    } else {
+        return Err(invalid("unsupported certificate key algorithm"));
+    };
+    key.validate()?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webauthn::testing::SoftwarePasskey;
+
+    const ATTESTATION_CERT_DER: &[u8] = include_bytes!("../testdata/passkey_attestation_cert.der");
+    const ATTESTATION_KEY_PEM: &str = include_str!("../testdata/passkey_attestation_key.pem");
+    const ATTESTATION_AAGUID: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+    /// A packed attestation signed by the test attestation certificate's RSA key
+    fn basic_attestation(passkey: &SoftwarePasskey, challenge: &[u8], aaguid: [u8; 16]) -> PasskeyRegistration {
+        use rsa::pkcs1v15::SigningKey;
+        use rsa::pkcs8::DecodePrivateKey;
+        use rsa::signature::{SignatureEncoding, Signer};
+
+        let authenticator_data = passkey.attested_authenticator_data(aaguid);
+        let client_data_json = passkey.client_data("webauthn.create", challenge);
+        let mut signed = authenticator_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data_json));
+        let key = rsa::RsaPrivateKey::from_pkcs8_pem(ATTESTATION_KEY_PEM).unwrap();
+        let signature = SigningKey::<Sha256>::new(key).sign(&signed).to_vec();
+
+        let text = |text: &str| CborValue::Text(text.to_string());
+        let statement = CborValue::Map(vec![
+            (text("alg"), CborValue::Integer(-257)),
+            (text("sig"), CborValue::Bytes(signature)),
+            (text("x5c"), CborValue::Array(vec![CborValue::Bytes(ATTESTATION_CERT_DER.to_vec())])),
+        ]);
+        let object = CborValue::Map(vec![
+            (text("fmt"), text("packed")),
+            (text("attStmt"), statement),
+            (text("authData"), CborValue::Bytes(authenticator_data)),
+        ]);
+        PasskeyRegistration { attestation_object: object.encode(), client_data_json }
+    }
+
+    #[test]
+    fn test_none_and_self_attestation() {
+        let rp = SoftwarePasskey::relying_party();
+        let passkey = SoftwarePasskey::new(4);
+        let challenge = [9u8; 32];
+
+        for (format, expected) in [("none", AttestationType::None), ("packed", AttestationType::SelfAttestation)] {
+            let registration = passkey.register(&challenge, format);
+            let verified = verify_registration(&rp, &registration, &challenge).unwrap();
+            assert_eq!(verified.attestation, expected);
+            assert_eq!(verified.credential.credential_id, passkey.credential_id);
+            assert_eq!(verified.credential.public_key, passkey.public_key());
+
+            assert_eq!(verify_registration(&rp, &registration, &[8; 32]), Err(WebAuthnError::ChallengeMismatch));
+            let json = serde_json::to_string(&registration).unwrap();
+            assert_eq!(serde_json::from_str::<PasskeyRegistration>(&json).unwrap(), registration);
+        }
+
+        // An assertion is not a registration, and a self signature must come from the new key
+        let mut assertion = passkey.register(&challenge, "packed");
+        assertion.client_data_json = passkey.client_data("webauthn.get", &challenge);
+        assert!(matches!(verify_registration(&rp, &assertion, &challenge), Err(WebAuthnError::WrongCeremony { .. })));
+
+        let mut forged = passkey.register(&challenge, "packed");
+        let CborValue::Map(mut entries) = cbor::decode(&forged.attestation_object).unwrap() else { unreachable!() };
+        let authenticator_data = entries[2].1.as_bytes().unwrap().to_vec();
+        let CborValue::Map(statement) = &mut entries[1].1 else { unreachable!() };
+        statement[1].1 = CborValue::Bytes(SoftwarePasskey::new(5).sign(&authenticator_data, &forged.client_data_json));
+        forged.attestation_object = CborValue::Map(entries).encode();
+        assert!(matches!(verify_registration(&rp, &forged, &challenge), Err(WebAuthnError::Cose(_))));
+    }
+
+    #[test]
+    fn test_basic_attestation_certificate() {
+        let rp = SoftwarePasskey::relying_party();
+        let passkey = SoftwarePasskey::new(4);
+        let challenge = [9u8; 32];
+
+        let verified = verify_registration(&rp, &basic_attestation(&passkey, &challenge, ATTESTATION_AAGUID), &challenge).unwrap();
+        assert_eq!(verified.attestation, AttestationType::Basic);
+        assert_eq!(verified.attestation_certificate.as_deref(), Some(ATTESTATION_CERT_DER));
+        assert_eq!(verified.credential.aaguid, ATTESTATION_AAGUID);
+
+        // The certificate names the authenticator model; another AAGUID cannot borrow it
+        let borrowed = basic_attestation(&passkey, &challenge, [0; 16]);
+        assert_eq!(
+            verify_registration(&rp, &borrowed, &challenge),
+            Err(WebAuthnError::InvalidAttestation("certificate AAGUID differs from the authenticator's"))
+        );
+
+        let other_rp = RelyingParty::new("evil.example", &[crate::webauthn::testing::ORIGIN]);
+        assert_eq!(
+            verify_registration(&other_rp, &basic_attestation(&passkey, &challenge, ATTESTATION_AAGUID), &challenge),
+            Err(WebAuthnError::RpIdMismatch)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+// Date of Birth Model
+// Single parser and age calculator shared by Aadhaar data, manual input and the proof service
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BirthDateError {
+    #[error("Unrecognised date format: {0} (expected DD-MM-YYYY, YYYY-MM-DD, DD/MM/YYYY or YYYY)")]
+    InvalidFormat(String),
+    #[error("Date does not exist: {0}")]
+    ImpossibleDate(String),
+    #[error("Reference date is before the date of birth")]
+    BeforeBirth,
+    #[error("Age is ambiguous for a year-only birth date")]
+    AmbiguousAge,
+}
+
+/// Date of birth as recorded by UIDAI: a full date, or only the year ("yob") on older records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum BirthDate {
+    Exact(NaiveDate),
+    YearOnly(i32),
+}
+
+/// How to treat the unknown day and month of a year-only birth date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YearOnlyMode {
+    /// Fail unless the answer is the same for every day of the year
+    Strict,
+    /// Assume 1 January, the oldest the holder can be
+    Earliest,
+    /// Assume 31 December, the youngest the holder can be (safe for minimum-age checks)
+    Latest,
+}
+
+impl BirthDate {
+    pub fn parse(value: &str) -> Result<Self, BirthDateError> {
+        let value = value.trim();
+        let invalid = || BirthDateError::InvalidFormat(value.to_string());
+
+        if value.len() == 4 && value.chars().all(|c| c.is_ascii_digit()) {
+            let year = value.parse().map_err(|_| invalid())?;
+            return Ok(BirthDate::YearOnly(year));
+        }
+
+        let separator = if value.contains('/') { '/' } else { '-' };
+        let parts: Vec<&str> = value.split(separator).collect();
+        let numbers: Vec<u32> = parts.iter()
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+
+        let (year, month, day) = match (parts.as_slice(), numbers.as_slice()) {
+            // YYYY-MM-DD
+            ([y, _, _], [year, month, day]) if separator == '-' && y.len() == 4 => (*year, *month, *day),
+            // DD-MM-YYYY and DD/MM/YYYY
+            ([_, _, y], [day, month, year]) if y.len() == 4 => (*year, *month, *day),
+            _ => return Err(invalid()),
+        };
+
+        NaiveDate::from_ymd_opt(year as i32, month, day)
+            .map(BirthDate::Exact)
+            .ok_or_else(|| BirthDateError::ImpossibleDate(value.to_string()))
+    }
+
+    pub fn year(&self) -> i32 {
+        match self {
+            BirthDate::Exact(date) => date.year(),
+            BirthDate::YearOnly(year) => *year,
+        }
+    }
+
+    /// Concrete date to use for the birth date under the given mode
+    pub fn resolve(&self, mode: YearOnlyMode) -> Result<NaiveDate, BirthDateError> {
+        match (self, mode) {
+            (BirthDate::Exact(date), _) => Ok(*date),
+            (BirthDate::YearOnly(_), YearOnlyMode::Strict) => Err(BirthDateError::AmbiguousAge),
+            (BirthDate::YearOnly(year), YearOnlyMode::Earliest) => year_bound(*year, 1, 1),
+            (BirthDate::YearOnly(year), YearOnlyMode::Latest) => year_bound(*year, 12, 31),
+        }
+    }
+
+    /// Completed years on `on`. A 29 February birthday is reached on 1 March in common years.
+    pub fn age_on(&self, on: NaiveDate, mode: YearOnlyMode) -> Result<u32, BirthDateError> {
+        match (self, mode) {
+            (BirthDate::YearOnly(_), YearOnlyMode::Strict) => {
+                let oldest = self.age_on(on, YearOnlyMode::Earliest)?;
+                let youngest = self.age_on(on, YearOnlyMode::Latest);
+                match youngest {
+                    Ok(youngest) if youngest == oldest => Ok(oldest),
+                    _ => Err(BirthDateError::AmbiguousAge),
+                }
+            }
+            _ => {
+                let birth = self.resolve(mode)?;
+                if on < birth {
+                    return Err(BirthDateError::BeforeBirth);
+                }
+                let mut age = on.year() - birth.year();
+                if (on.month(), on.day()) < (birth.month(), birth.day()) {
+                    age -= 1;
+                }
+                Ok(age as u32)
+            }
+        }
+    }
+
+    /// YYYYMMDD encoding used by the circuits
+    pub fn to_numeric(&self, mode: YearOnlyMode) -> Result<u32, BirthDateError> {
+        date_to_numeric(self.resolve(mode)?)
+    }
+}
+
+impl fmt::Display for BirthDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BirthDate::Exact(date) => write!(f, "{}", date.format("%d-%m-%Y")),
+            BirthDate::YearOnly(year) => write!(f, "{:04}", year),
+        }
+    }
+}
+
+impl TryFrom<String> for BirthDate {
+    type Error = BirthDateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        BirthDate::parse(&value)
+    }
+}
+
+impl From<BirthDate> for String {
+    fn from(birth_date: BirthDate) -> Self {
+        birth_date.to_string()
+    }
+}
+
+/// Coarse age band shown in place of a date of birth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AgeBracket {
+    Under18,
+    From18To24,
+    From25To34,
+    From35To44,
+    From45To59,
+    Over60, // 60 and above
+}
+
+impl AgeBracket {
+    pub fn from_age(age: u32) -> Self {
+        match age {
+            0..=17 => AgeBracket::Under18,
+            18..=24 => AgeBracket::From18To24,
+            25..=34 => AgeBracket::From25To34,
+            35..=44 => AgeBracket::From35To44,
+            45..=59 => AgeBracket::From45To59,
+            _ => AgeBracket::Over60,
+        }
+    }
+}
+
+/// Parse a YYYYMMDD number such as a circuit's current_date
+pub fn date_from_numeric(yyyymmdd: u32) -> Result<NaiveDate, BirthDateError> {
+    NaiveDate::from_ymd_opt((yyyymmdd / 10000) as i32, (yyyymmdd / 100) % 100, yyyymmdd % 100)
+        .ok_or_else(|| BirthDateError::ImpossibleDate(yyyymmdd.to_string()))
+}
+
+pub fn date_to_numeric(date: NaiveDate) -> Result<u32, BirthDateError> {
+    u32::try_from(date.year())
+        .map(|year| year * 10000 + date.month() * 100 + date.day())
+        .map_err(|_| BirthDateError::ImpossibleDate(date.to_string()))
+}
+
+fn year_bound(year: i32, month: u32, day: u32) -> Result<NaiveDate, BirthDateError> {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .ok_or_else(|| BirthDateError::ImpossibleDate(year.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = BirthDate::Exact(date(1990, 8, 15));
+        assert_eq!(BirthDate::parse("15-08-1990").unwrap(), expected);
+        assert_eq!(BirthDate::parse("1990-08-15").unwrap(), expected);
+        assert_eq!(BirthDate::parse("15/08/1990").unwrap(), expected);
+        assert_eq!(BirthDate::parse("1990").unwrap(), BirthDate::YearOnly(1990));
+        assert_eq!(expected.to_string(), "15-08-1990");
+
+        assert!(matches!(BirthDate::parse("31-02-1990"), Err(BirthDateError::ImpossibleDate(_))));
+        assert!(matches!(BirthDate::parse("29-02-2023"), Err(BirthDateError::ImpossibleDate(_))));
+        assert!(matches!(BirthDate::parse("1990/08/15"), Err(BirthDateError::InvalidFormat(_))));
+        assert!(matches!(BirthDate::parse("15-08-90"), Err(BirthDateError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_age_with_leap_day() {
+        let birth = BirthDate::Exact(date(2004, 2, 29));
+        assert_eq!(birth.age_on(date(2022, 2, 28), YearOnlyMode::Strict).unwrap(), 17);
+        assert_eq!(birth.age_on(date(2022, 3, 1), YearOnlyMode::Strict).unwrap(), 18);
+        assert_eq!(birth.age_on(date(2024, 2, 29), YearOnlyMode::Strict).unwrap(), 20);
+        assert_eq!(birth.age_on(date(2003, 1, 1), YearOnlyMode::Strict), Err(BirthDateError::BeforeBirth));
+    }
+
+    #[test]
+    fn test_year_only_modes() {
+        let birth = BirthDate::YearOnly(1990);
+        let on = date(2025, 6, 1);
+        assert_eq!(birth.age_on(on, YearOnlyMode::Earliest).unwrap(), 35);
+        assert_eq!(birth.age_on(on, YearOnlyMode::Latest).unwrap(), 34);
+        assert_eq!(birth.age_on(on, YearOnlyMode::Strict), Err(BirthDateError::AmbiguousAge));
+        // Only on 31 December does every possible birthday agree
+        assert_eq!(birth.age_on(date(2025, 12, 31), YearOnlyMode::Strict).unwrap(), 35);
+        assert_eq!(birth.to_numeric(YearOnlyMode::Latest).unwrap(), 19901231);
+
+        assert_eq!(AgeBracket::from_age(17), AgeBracket::Under18);
+        assert_eq!(AgeBracket::from_age(18), AgeBracket::From18To24);
+        assert_eq!(AgeBracket::from_age(60), AgeBracket::Over60);
+
+        let json = serde_json::to_string(&birth).unwrap();
+        assert_eq!(json, "\"1990\"");
+        assert_eq!(serde_json::from_str::<BirthDate>(&json).unwrap(), birth);
+    }
+}
@@ -0,0 +1,295 @@
+// Minimal CBOR Codec
+// Decodes the COSE keys and attestation objects WebAuthn hands over, and writes deterministic CBOR
+
+use thiserror::Error;
+
+/// Nesting allowed before decoding gives up; COSE keys and attestation objects need three levels
+pub const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CborError {
+    #[error("CBOR input ended early")]
+    UnexpectedEnd,
+    #[error("Unsupported CBOR item (initial byte {0:#04x})")]
+    Unsupported(u8),
+    #[error("CBOR text is not UTF-8")]
+    InvalidUtf8,
+    #[error("CBOR nested deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+    #[error("{0} trailing bytes after the CBOR item")]
+    TrailingBytes(usize),
+}
+
+/// The subset WebAuthn uses: no floats, tags or indefinite lengths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CborValue {
+    Integer(i128), // Major types 0 and 1
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(CborValue, CborValue)>), // Entries in encoded order
+    Bool(bool),
+    Null,
+}
+
+/// Decode exactly one item spanning all of `bytes`
+pub fn decode(bytes: &[u8]) -> Result<CborValue, CborError> {
+    let (value, used) = decode_prefix(bytes)?;
+    if used != bytes.len() {
+        return Err(CborError::TrailingBytes(bytes.len() - used));
+    }
+    Ok(value)
+}
+
+/// Decode the item at the start of `bytes`, returning it with the number of bytes it took
+pub fn decode_prefix(bytes: &[u8]) -> Result<(CborValue, usize), CborError> {
+    let mut decoder = Decoder { bytes, position: 0 };
+    let value = decoder.item(0)?;
+    Ok((value, decoder.position))
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], CborError> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.bytes.len()).ok_or(CborError::UnexpectedEnd)?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    /// Argument of a head whose additional info is `info`
+    fn argument(&mut self, initial: u8) -> Result<u64, CborError> {
+        let info = initial & 0x1f;
+        let width = match info {
+            0..=23 => return Ok(info as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(CborError::Unsupported(initial)),
+        };
+        Ok(self.take(width)?.iter().fold(0u64, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    /// A declared length, rejected early if the input cannot possibly hold it
+    fn length(&mut self, initial: u8) -> Result<usize, CborError> {
+        let length = self.argument(initial)?;
+        usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= self.bytes.len() - self.position)
+            .ok_or(CborError::UnexpectedEnd)
+    }
+
+    fn item(&mut self, depth: usize) -> Result<CborValue, CborError> {
+        if depth > MAX_DEPTH {
+            return Err(CborError::TooDeep);
+        }
+        let initial = self.take(1)?[0];
+        match initial >> 5 {
+            0 => Ok(CborValue::Integer(self.argument(initial)? as i128)),
+            1 => Ok(CborValue::Integer(-1 - self.argument(initial)? as i128)),
+            2 => {
+                let length = self.length(initial)?;
+                Ok(CborValue::Bytes(self.take(length)?.to_vec()))
+            }
+            3 => {
+                let length = self.length(initial)?;
+                let text = std::str::from_utf8(self.take(length)?).map_err(|_| CborError::InvalidUtf8)?;
+                Ok(CborValue::Text(text.to_string()))
+            }
+            4 => {
+                // Every item takes at least one byte, so the remaining input bounds the count
+                let count = self.length(initial)?;
+                let mut items = Vec::with_capacity(count);
+                for _ in 0..count {
+                    items.push(self.item(depth + 1)?);
+                }
+                Ok(CborValue::Array(items))
+            }
+            5 => {
+                let count = self.length(initial)?;
+                let mut entries = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let key = self.item(depth + 1)?;
+                    entries.push((key, self.item(depth + 1)?));
+                }
+                Ok(CborValue::Map(entries))
+            }
+            7 => match initial {
+                0xf4 => Ok(CborValue::Bool(false)),
+                0xf5 => Ok(CborValue::Bool(true)),
+                0xf6 => Ok(CborValue::Null),
+                _ => Err(CborError::Unsupported(initial)),
+            },
+            _ => Err(CborError::Unsupported(initial)),
+        }
+    }
+}
+
+impl CborValue {
+    /// Deterministic encoding: shortest heads, map entries in the order given
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            CborValue::Integer(value) if *value >= 0 => write_head(out, 0, *value as u64),
+            CborValue::Integer(value) => write_head(out, 1, (-1 - *value) as u64),
+            CborValue::Bytes(bytes) => {
+                write_head(out, 2, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            CborValue::Text(text) => {
+                write_head(out, 3, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+            CborValue::Array(items) => {
+                write_head(out, 4, items.len() as u64);
+                for item in items {
+                    item.encode_into(out);
+                }
+            }
+            CborValue::Map(entries) => {
+                write_head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.encode_into(out);
+                    value.encode_into(out);
+                }
+            }
+            CborValue::Bool(value) => out.push(if *value { 0xf5 } else { 0xf4 }),
+            CborValue::Null => out.push(0xf6),
+        }
+    }
+
+    /// Map value under an integer key, as COSE uses
+    pub fn get(&self, key: i128) -> Option<&CborValue> {
+        self.entry(|candidate| *candidate == CborValue::Integer(key))
+    }
+
+    /// Map value under a text key, as attestation objects use
+    pub fn get_text(&self, key: &str) -> Option<&CborValue> {
+        self.entry(|candidate| matches!(candidate, CborValue::Text(text) if text == key))
+    }
+
+    fn entry(&self, matches: impl Fn(&CborValue) -> bool) -> Option<&CborValue> {
+        self.as_map()?.iter().find(|(key, _)| matches(key)).map(|(_, value)| value)
+    }
+
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            CborValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            CborValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            CborValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[CborValue]> {
+        match self {
+            CborValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(CborValue, CborValue)]> {
+        match self {
+            CborValue::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+    let major = major << 5;
+    match argument {
+        0..=23 => out.push(major | argument as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, argument as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(argument as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&argument.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc8949_examples_round_trip() {
+        let cases: &[(&str, CborValue)] = &[
+            ("00", CborValue::Integer(0)),
+            ("1818", CborValue::Integer(24)),
+            ("1903e8", CborValue::Integer(1000)),
+            ("1bffffffffffffffff", CborValue::Integer(u64::MAX as i128)),
+            ("3903e7", CborValue::Integer(-1000)),
+            ("4401020304", CborValue::Bytes(vec![1, 2, 3, 4])),
+            ("6449455446", CborValue::Text("IETF".to_string())),
+            ("f5", CborValue::Bool(true)),
+            ("f6", CborValue::Null),
+            (
+                "a201020304",
+                CborValue::Map(vec![
+                    (CborValue::Integer(1), CborValue::Integer(2)),
+                    (CborValue::Integer(3), CborValue::Integer(4)),
+                ]),
+            ),
+            (
+                "826161a161626163",
+                CborValue::Array(vec![
+                    CborValue::Text("a".to_string()),
+                    CborValue::Map(vec![(CborValue::Text("b".to_string()), CborValue::Text("c".to_string()))]),
+                ]),
+            ),
+        ];
+        for (encoded, value) in cases {
+            let bytes = hex::decode(encoded).unwrap();
+            assert_eq!(&decode(&bytes).unwrap(), value, "{}", encoded);
+            assert_eq!(value.encode(), bytes, "{}", encoded);
+        }
+
+        let map = decode(&hex::decode("a201020304").unwrap()).unwrap();
+        assert_eq!(map.get(3).and_then(CborValue::as_integer), Some(4));
+        assert_eq!(map.get(2), None);
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert_eq!(decode(&[]), Err(CborError::UnexpectedEnd));
+        assert_eq!(decode(&hex::decode("0001").unwrap()), Err(CborError::TrailingBytes(1)));
+        assert_eq!(decode_prefix(&hex::decode("0001").unwrap()), Ok((CborValue::Integer(0), 1)));
+        // A huge declared length fails without allocating
+        assert_eq!(decode(&hex::decode("5bffffffffffffffff").unwrap()), Err(CborError::UnexpectedEnd));
+        assert_eq!(decode(&hex::decode("9a7fffffff").unwrap()), Err(CborError::UnexpectedEnd));
+        assert_eq!(decode(&hex::decode("5f").unwrap()), Err(CborError::Unsupported(0x5f)));
+        assert_eq!(decode(&hex::decode("62c328").unwrap()), Err(CborError::InvalidUtf8));
+        assert_eq!(decode(&[0x81; MAX_DEPTH + 2]), Err(CborError::TooDeep));
+    }
+}
@@ -0,0 +1,202 @@
+// Registered Mobile/Email Verification
+// Checks contacts against the UIDAI hashes and attests ownership without revealing the contact
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::aadhaar_xml_parser::{AadhaarVerificationError, VerifiedAadhaarData};
+use crate::field_element::FieldElement;
+use crate::poseidon::poseidon2_hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactKind {
+    Mobile,
+    Email,
+}
+
+impl ContactKind {
+    pub fn from_name(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().as_str() {
+            "mobile" => Some(ContactKind::Mobile),
+            "email" => Some(ContactKind::Email),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContactKind::Mobile => "mobile",
+            ContactKind::Email => "email",
+        }
+    }
+
+    /// Canonical form UIDAI hashes: 10-digit mobile without country code, trimmed email
+    pub fn normalize(&self, contact: &str) -> String {
+        match self {
+            ContactKind::Mobile => {
+                let digits: String = contact.chars().filter(|c| c.is_ascii_digit()).collect();
+                match digits.strip_prefix("91") {
+                    Some(national) if national.len() == 10 => national.to_string(),
+                    _ => digits,
+                }
+            }
+            ContactKind::Email => contact.trim().to_string(),
+        }
+    }
+}
+
+/// UIDAI contact hash: SHA-256 applied `iterations` times to (contact + share code),
+/// each round hashing the lowercase hex of the previous one
+pub fn uidai_contact_hash(contact: &str, share_code: &str, iterations: u32) -> String {
+    let mut value = format!("{}{}", contact, share_code);
+    for _ in 0..iterations.max(1) {
+        value = hex::encode(Sha256::digest(value.as_bytes()));
+    }
+    value
+}
+
+/// Number of hashing rounds, taken from the last digit of the Aadhaar number (0 and 1 mean once)
+pub fn hash_iterations(aadhaar_last_4_digits: &str) -> Result<u32, AadhaarVerificationError> {
+    aadhaar_last_4_digits
+        .chars()
+        .last()
+        .and_then(|c| c.to_digit(10))
+        .map(|digit| digit.max(1))
+        .ok_or(AadhaarVerificationError::InvalidAadhaarNumber)
+}
+
+/// Public signal of a contact proof: poseidon2_hash([contact, request nonce, blinding]). Without
+/// the blinding a mobile number could be brute-forced from the signal, so the holder hands it
+/// only to the verifier, which recomputes the commitment for the contact it expects.
+pub fn contact_commitment(
+    contact_kind: ContactKind,
+    contact: &str,
+    nonce: FieldElement,
+    blinding: FieldElement,
+) -> FieldElement {
+    poseidon2_hash(&[contact_field(contact_kind, contact), nonce, blinding])
+}
+
+/// SHA-256 of "kind:contact" in normalised form, reduced mod p
+fn contact_field(contact_kind: ContactKind, contact: &str) -> FieldElement {
+    FieldElement::from_text(&format!("{}:{}", contact_kind.name(), contact_kind.normalize(contact)))
+}
+
+/// Document hash a contact proof is generated over: the UIDAI-signed contact hash is folded
+/// into the document's own hash, so the circuit ties the contact to that signed document
+pub fn contact_document_hash(
+    data: &VerifiedAadhaarData,
+    contact_kind: ContactKind,
+    document_hash: FieldElement,
+) -> Result<FieldElement, AadhaarVerificationError> {
+    let registered = FieldElement::from_text(&registered_hash(data, contact_kind)?.to_lowercase());
+    Ok(poseidon2_hash(&[document_hash, registered]))
+}
+
+pub(crate) fn registered_hash(data: &VerifiedAadhaarData, contact_kind: ContactKind) -> Result<&str, AadhaarVerificationError> {
+    let hash = match contact_kind {
+        ContactKind::Mobile => data.mobile_hash.expose().as_deref(),
+        ContactKind::Email => data.email_hash.expose().as_deref(),
+    };
+    hash.map(str::trim).ok_or_else(|| {
+        AadhaarVerificationError::MissingDemographicData(match contact_kind {
+            ContactKind::Mobile => "mobile_hash".to_string(),
+            ContactKind::Email => "email_hash".to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aadhaar_xml_parser::AadhaarAddress;
+    use crate::sensitive::Sensitive;
+
+    // Reference values computed independently with Python's hashlib
+    const MOBILE_HASH: &str = "d4c5c48e83ef5b7217464b7f22ab7bbfb6541db97895c4e6410912d96a3183a1";
+    const EMAIL_HASH: &str = "495cb0ba980afeae591e2e39f457a262e538af58e100bd02a9d2cbf561e61613";
+
+    fn test_data() -> VerifiedAadhaarData {
+        VerifiedAadhaarData {
+            name: "Test User".to_string().into(),
+            date_of_birth: "15-08-1990".to_string().into(),
+            gender: "M".to_string(),
+            address: AadhaarAddress {
+                care_of: None,
+                house: None,
+                street: None,
+                landmark: None,
+                locality: None,
+                vtc: "Mumbai".to_string(),
+                post_office: None,
+                subdist: None,
+                district: "Mumbai".to_string(),
+                state: "Maharashtra".to_string(),
+                pincode: "400001".to_string(),
+                country: "India".to_string(),
+            }.into(),
+            photo_base64: None.into(),
+            photo_commitment: None,
+            mobile_hash: Some(MOBILE_HASH.to_uppercase()).into(),
+            email_hash: Some(EMAIL_HASH.to_string()).into(),
+            reference_id: "234520250101120000000".to_string(),
+            generated_date: "2025-01-01".to_string(),
+            generated_at: None,
+            signature_valid: true,
+            certificate_valid: true,
+            signer_certificate: None,
+            aadhaar_last_4_digits: "2345".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_uidai_contact_hash() {
+        assert_eq!(uidai_contact_hash("9876543210", "1234", 5), MOBILE_HASH);
+        // Last digits 0 and 1 both hash once
+        assert_eq!(hash_iterations("1230").unwrap(), 1);
+        assert_eq!(hash_iterations("1231").unwrap(), 1);
+        assert_eq!(hash_iterations("1239").unwrap(), 9);
+        assert!(hash_iterations("****").is_err());
+    }
+
+    #[test]
+    fn test_verify_mobile_and_email() {
+        let data = test_data();
+        assert!(data.verify_mobile("+91 98765 43210", "1234").unwrap());
+        assert!(!data.verify_mobile("9876543211", "1234").unwrap());
+        assert!(!data.verify_mobile("9876543210", "4321").unwrap());
+        assert!(data.verify_email(" test@example.com ", "1234").unwrap());
+
+        let mut without_email = data.clone();
+        without_email.email_hash = Sensitive::new(None);
+        assert!(matches!(
+            without_email.verify_email("test@example.com", "1234"),
+            Err(AadhaarVerificationError::MissingDemographicData(_))
+        ));
+    }
+
+    #[test]
+    fn test_contact_commitments() {
+        let nonce = FieldElement::from_u64(1);
+        let blinding = FieldElement::from_u64(7);
+        let commitment = contact_commitment(ContactKind::Mobile, "9876543210", nonce, blinding);
+        assert_eq!(commitment, contact_commitment(ContactKind::Mobile, "+91 98765 43210", nonce, blinding));
+        assert_ne!(commitment, contact_commitment(ContactKind::Mobile, "9876543210", FieldElement::from_u64(2), blinding));
+        assert_ne!(commitment, contact_commitment(ContactKind::Mobile, "9876543210", nonce, FieldElement::from_u64(8)));
+        assert_ne!(commitment, contact_commitment(ContactKind::Mobile, "9876543211", nonce, blinding));
+        assert_ne!(commitment, contact_commitment(ContactKind::Email, "9876543210", nonce, blinding));
+
+        // The registered hash is part of the document hash, whatever its case
+        let data = test_data();
+        let document_hash = FieldElement::from_u64(0xabc);
+        let bound = contact_document_hash(&data, ContactKind::Mobile, document_hash).unwrap();
+        assert_ne!(bound, contact_document_hash(&data, ContactKind::Email, document_hash).unwrap());
+        let mut lowercase = data.clone();
+        lowercase.mobile_hash = Sensitive::new(Some(MOBILE_HASH.to_string()));
+        assert_eq!(contact_document_hash(&lowercase, ContactKind::Mobile, document_hash).unwrap(), bound);
+        let mut other = data;
+        other.mobile_hash = Sensitive::new(Some(EMAIL_HASH.to_string()));
+        assert_ne!(contact_document_hash(&other, ContactKind::Mobile, document_hash).unwrap(), bound);
+    }
+}
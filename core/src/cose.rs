@@ -0,0 +1,268 @@
+// COSE Credential Public Keys
+// Parses the COSE_Key a passkey registers and verifies ES256, EdDSA and RS256 signatures with it
+
+use thiserror::Error;
+
+use crate::cbor::{self, CborError, CborValue};
+
+// COSE labels and values (RFC 9052/9053, RFC 8230)
+const LABEL_KTY: i128 = 1;
+const LABEL_ALG: i128 = 3;
+const LABEL_CRV: i128 = -1; // RSA keys use -1 for n
+const LABEL_X: i128 = -2; // RSA keys use -2 for e
+const LABEL_Y: i128 = -3;
+const KTY_OKP: i128 = 1;
+const KTY_EC2: i128 = 2;
+const KTY_RSA: i128 = 3;
+const CRV_P256: i128 = 1;
+const CRV_ED25519: i128 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CoseError {
+    #[error("Malformed COSE key: {0}")]
+    Cbor(#[from] CborError),
+    #[error("Unsupported COSE algorithm {0}")]
+    UnsupportedAlgorithm(i128),
+    #[error("Invalid COSE key: {0}")]
+    InvalidKey(&'static str),
+    #[error("Signature does not verify")]
+    InvalidSignature,
+}
+
+/// The signature algorithms a passkey may register with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoseAlgorithm {
+    Es256 = -7,
+    EdDsa = -8,
+    Rs256 = -257,
+}
+
+/// A credential public key; coordinates are checked to lie on their curve when parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoseKey {
+    Es256 { x: [u8; 32], y: [u8; 32] },
+    EdDsa { x: [u8; 32] },                // Encoded Ed25519 point
+    Rs256 { n: Vec<u8>, e: Vec<u8> },     // Big-endian modulus and exponent
+}
+
+impl CoseKey {
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CoseError> {
+        Self::from_value(&cbor::decode(bytes)?)
+    }
+
+    pub fn from_value(value: &CborValue) -> Result<Self, CoseError> {
+        let integer = |label, name| value.get(label).and_then(CborValue::as_integer).ok_or(CoseError::InvalidKey(name));
+        let bytes = |label, name| value.get(label).and_then(CborValue::as_bytes).ok_or(CoseError::InvalidKey(name));
+        let coordinate = |label, name| -> Result<[u8; 32], CoseError> {
+            bytes(label, name)?.try_into().map_err(|_| CoseError::InvalidKey(name))
+        };
+
+        let key = match (integer(LABEL_KTY, "kty")?, integer(LABEL_ALG, "alg")?) {
+            (KTY_EC2, -7) => {
+                if integer(LABEL_CRV, "crv")? != CRV_P256 {
+                    return Err(CoseError::InvalidKey("crv"));
+                }
+                CoseKey::Es256 { x: coordinate(LABEL_X, "x")?, y: coordinate(LABEL_Y, "y")? }
+            }
+            (KTY_OKP, -8) => {
+                if integer(LABEL_CRV, "crv")? != CRV_ED25519 {
+                    return Err(CoseError::InvalidKey("crv"));
+                }
+                CoseKey::EdDsa { x: coordinate(LABEL_X, "x")? }
+            }
+            (KTY_RSA, -257) => CoseKey::Rs256 {
+                n: bytes(LABEL_CRV, "n")?.to_vec(),
+                e: bytes(LABEL_X, "e")?.to_vec(),
+            },
+            (KTY_EC2 | KTY_OKP | KTY_RSA, algorithm) => return Err(CoseError::UnsupportedAlgorithm(algorithm)),
+            _ => return Err(CoseError::InvalidKey("kty")),
+        };
+        key.validate()?;
+        Ok(key)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), CoseError> {
+        let valid = match self {
+            CoseKey::Es256 { .. } => self.es256_key().is_some(),
+            CoseKey::EdDsa { .. } => self.ed25519_key().is_some(),
+            CoseKey::Rs256 { .. } => self.rsa_key().is_some(),
+        };
+        if valid { Ok(()) } else { Err(CoseError::InvalidKey("not a valid public key")) }
+    }
+
+    fn es256_key(&self) -> Option<p256::ecdsa::VerifyingKey> {
+        match self {
+            CoseKey::Es256 { x, y } => {
+                let point = p256::EncodedPoint::from_affine_coordinates(x.into(), y.into(), false);
+                p256::ecdsa::VerifyingKey::from_encoded_point(&point).ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn ed25519_key(&self) -> Option<ed25519_dalek::VerifyingKey> {
+        match self {
+            CoseKey::EdDsa { x } => ed25519_dalek::VerifyingKey::from_bytes(x).ok(),
+            _ => None,
+        }
+    }
+
+    fn rsa_key(&self) -> Option<rsa::RsaPublicKey> {
+        match self {
+            CoseKey::Rs256 { n, e } => {
+                rsa::RsaPublicKey::new(rsa::BigUint::from_bytes_be(n), rsa::BigUint::from_bytes_be(e)).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Canonical CTAP2 encoding, as authenticators emit it
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let int = CborValue::Integer;
+        let entries = match self {
+            CoseKey::Es256 { x, y } => vec![
+                (int(LABEL_KTY), int(KTY_EC2)),
+                (int(LABEL_ALG), int(CoseAlgorithm::Es256 as i128)),
+                (int(LABEL_CRV), int(CRV_P256)),
+                (int(LABEL_X), CborValue::Bytes(x.to_vec())),
+                (int(LABEL_Y), CborValue::Bytes(y.to_vec())),
+            ],
+            CoseKey::EdDsa { x } => vec![
+                (int(LABEL_KTY), int(KTY_OKP)),
+                (int(LABEL_ALG), int(CoseAlgorithm::EdDsa as i128)),
+                (int(LABEL_CRV), int(CRV_ED25519)),
+                (int(LABEL_X), CborValue::Bytes(x.to_vec())),
+            ],
+            CoseKey::Rs256 { n, e } => vec![
+                (int(LABEL_KTY), int(KTY_RSA)),
+                (int(LABEL_ALG), int(CoseAlgorithm::Rs256 as i128)),
+                (int(LABEL_CRV), CborValue::Bytes(n.clone())),
+                (int(LABEL_X), CborValue::Bytes(e.clone())),
+            ],
+        };
+        CborValue::Map(entries).encode()
+    }
+
+    pub fn algorithm(&self) -> CoseAlgorithm {
+        match self {
+            CoseKey::Es256 { .. } => CoseAlgorithm::Es256,
+            CoseKey::EdDsa { .. } => CoseAlgorithm::EdDsa,
+            CoseKey::Rs256 { .. } => CoseAlgorithm::Rs256,
+        }
+    }
+
+    /// Check `signature` over `message`; ES256 signatures are DER-encoded, as WebAuthn returns them
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), CoseError> {
+        use rsa::signature::Verifier;
+
+        let invalid_key = CoseError::InvalidKey("not a valid public key");
+        let valid = match self {
+            CoseKey::Es256 { .. } => {
+                let key = self.es256_key().ok_or(invalid_key)?;
+                p256::ecdsa::Signature::from_der(signature)
+                    .is_ok_and(|signature| key.verify(message, &signature).is_ok())
+            }
+            CoseKey::EdDsa { .. } => {
+                let key = self.ed25519_key().ok_or(invalid_key)?;
+                ed25519_dalek::Signature::from_slice(signature)
+                    .is_ok_and(|signature| key.verify(message, &signature).is_ok())
+            }
+            CoseKey::Rs256 { .. } => {
+                use rsa::pkcs1v15::{Signature, VerifyingKey};
+                use sha2::Sha256;
+
+                let key = self.rsa_key().ok_or(invalid_key)?;
+                Signature::try_from(signature)
+                    .is_ok_and(|signature| VerifyingKey::<Sha256>::new(key).verify(message, &signature).is_ok())
+            }
+        };
+        if valid { Ok(()) } else { Err(CoseError::InvalidSignature) }
+    }
+}
+
+/// Ed25519 key pair from `seed` and its signature over `message`, for test passkeys
+#[cfg(test)]
+pub(crate) fn ed25519_sign(seed: &[u8; 32], message: &[u8]) -> ([u8; 32], [u8; 64]) {
+    use ed25519_dalek::Signer;
+
+    let key = ed25519_dalek::SigningKey::from_bytes(seed);
+    (key.verifying_key().to_bytes(), key.sign(message).to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes32(hex: &str) -> [u8; 32] {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_ed25519_rfc8032_vectors() {
+        let vectors = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+        for (seed, public_key, message, signature) in vectors {
+            let message = hex::decode(message).unwrap();
+            let signature = hex::decode(signature).unwrap();
+            let (derived, signed) = ed25519_sign(&bytes32(seed), &message);
+            assert_eq!(hex::encode(derived), public_key);
+            assert_eq!(signed.to_vec(), signature);
+
+            let key = CoseKey::from_cbor(&CoseKey::EdDsa { x: bytes32(public_key) }.to_cbor()).unwrap();
+            assert_eq!(key.verify(&message, &signature), Ok(()));
+            assert_eq!(key.verify(b"other", &signature), Err(CoseError::InvalidSignature));
+        }
+    }
+
+    #[test]
+    fn test_es256_and_rs256_vectors() {
+        // Produced with Python's `cryptography`
+        let message = b"PatriconID passkey test vector";
+        let es256 = CoseKey::Es256 {
+            x: bytes32("bd7c73b88b2e9b4ceda62022b2da8be13193a5b56edc26e7df7842e24cd0b5eb"),
+            y: bytes32("0605ada7bda83ac6a2b80d7e314040fa47ff16b83bac85cedb014451bb7ce71a"),
+        };
+        let es256_signature = hex::decode("304502207c8039535f01495572f1863acb26d41bf61abf199745bbf72b5bbe5800816c0f0221008376ff43d8c540c5e84e848e1f38814049301a856bc498a4e0ace1a5d825407d").unwrap();
+        assert_eq!(CoseKey::from_cbor(&es256.to_cbor()).unwrap(), es256);
+        assert_eq!(es256.verify(message, &es256_signature), Ok(()));
+        assert_eq!(es256.verify(b"other", &es256_signature), Err(CoseError::InvalidSignature));
+        let mut truncated = es256_signature.clone();
+        truncated.pop();
+        assert_eq!(es256.verify(message, &truncated), Err(CoseError::InvalidSignature));
+
+        let rs256 = CoseKey::Rs256 {
+            n: hex::decode("a8fe11c42fbd47c932c872864bf600770da56682acb0a4d40ff99d0ab5ecb9898e915d2b642d4fba8a3f883c354c0c266d4e0f15cc3902e0afdbb5f719acac95e856b6d883f0b09f72c95182003f5ac8b50a4fc3d9cf0e3812f5ea4bd7a932b96d1ed6c2ec360e0b7d9fdc5f1c12aed7ca8104ac2bf1a49b6295495ce8ec00c3").unwrap(),
+            e: vec![1, 0, 1],
+        };
+        let rs256_signature = hex::decode("09549f313cf5e7e1593a4e301f32d781905987b8c97961b0fa2486ac272e2332c8b06a4430a5b2f17169467db795c65f5226701422a9f119158b82706b5d411b0a8c06d015ef120c8075e9a63b94ea60d9ddec662c41f38d36d5b16237e156e03f1b73b0bcc891a8f3ec652498741ec37575dacbeffbafa4a4b55cbac94167d3").unwrap();
+        assert_eq!(CoseKey::from_cbor(&rs256.to_cbor()).unwrap(), rs256);
+        assert_eq!(rs256.verify(message, &rs256_signature), Ok(()));
+        assert_eq!(rs256.verify(b"other", &rs256_signature), Err(CoseError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_rejects_invalid_keys() {
+        let off_curve = CoseKey::Es256 { x: [1; 32], y: [2; 32] };
+        assert_eq!(CoseKey::from_cbor(&off_curve.to_cbor()), Err(CoseError::InvalidKey("not a valid public key")));
+
+        // ES384 is not accepted
+        let int = CborValue::Integer;
+        let es384 = CborValue::Map(vec![(int(1), int(2)), (int(3), int(-35))]).encode();
+        assert_eq!(CoseKey::from_cbor(&es384), Err(CoseError::UnsupportedAlgorithm(-35)));
+        let no_alg = CborValue::Map(vec![(int(1), int(1))]).encode();
+        assert_eq!(CoseKey::from_cbor(&no_alg), Err(CoseError::InvalidKey("alg")));
+        assert!(matches!(CoseKey::from_cbor(&[0xa1]), Err(CoseError::Cbor(_))));
+    }
+}
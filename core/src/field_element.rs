@@ -0,0 +1,346 @@
+// Circuit Field Elements
+// Canonical encodings of identity attributes into the BN254 scalar field; every circuit input goes through here
+
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField, Zero};
+use chrono::NaiveDate;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::birth_date::{date_from_numeric, date_to_numeric, BirthDateError};
+use crate::poseidon::Fr;
+
+/// Longest UTF-8 string packed directly; 32 bytes could exceed the modulus
+pub const SHORT_STRING_MAX_BYTES: usize = 31;
+const ADDRESS_BYTES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FieldElementError {
+    #[error("Invalid field element: {0}")]
+    Invalid(String),
+    #[error("Value is not below the BN254 scalar field modulus: {0}")]
+    NotCanonical(String),
+    #[error("String is {0} bytes; at most {SHORT_STRING_MAX_BYTES} fit in a field element")]
+    StringTooLong(usize),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Field element does not encode a {0}")]
+    WrongEncoding(&'static str),
+    #[error(transparent)]
+    Date(#[from] BirthDateError),
+}
+
+/// Element of the BN254 scalar field, i.e. a Noir `Field`. Serialized as 0x-prefixed 64-digit hex.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FieldElement(Fr);
+
+impl FieldElement {
+    pub const ZERO: FieldElement = FieldElement(Fr::ZERO);
+
+    pub fn from_u64(value: u64) -> Self {
+        Self(Fr::from(value))
+    }
+
+    /// Negative values wrap to p - |value|, as Noir evaluates `0 - x`
+    pub fn from_i64(value: i64) -> Self {
+        Self(Fr::from(value))
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        let bytes = self.to_bytes();
+        bytes[..24].iter().all(|byte| *byte == 0)
+            .then(|| u64::from_be_bytes(bytes[24..].try_into().expect("slice is 8 bytes")))
+    }
+
+    /// 0x hex below the modulus; out-of-range values are rejected, not reduced
+    pub fn from_hex(value: &str) -> Result<Self, FieldElementError> {
+        let digits = value.strip_prefix("0x").unwrap_or(value);
+        if digits.is_empty() || digits.len() > 64 {
+            return Err(FieldElementError::Invalid(value.to_string()));
+        }
+        let bytes = hex::decode(format!("{:0>64}", digits))
+            .map_err(|_| FieldElementError::Invalid(value.to_string()))?;
+        let element = Self::reduce(&bytes);
+        if element.to_bytes().as_slice() != bytes.as_slice() {
+            return Err(FieldElementError::NotCanonical(value.to_string()));
+        }
+        Ok(element)
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_bytes()))
+    }
+
+    /// Digest (e.g. SHA-256) read big-endian and reduced mod p. One-way; the bias is below 2^-2 per
+    /// element and irrelevant for commitments.
+    pub fn from_hash(digest: &[u8]) -> Self {
+        Self::reduce(digest)
+    }
+
+    /// SHA-256 of the UTF-8 text, reduced mod p. Callers normalise (trim, case) before encoding.
+    pub fn from_text(text: &str) -> Self {
+        Self::from_hash(&Sha256::digest(text.as_bytes()))
+    }
+
+    /// SHA-256 over address line 1, city and postal code, reduced mod p. Each part is trimmed,
+    /// lowercased and has whitespace runs collapsed (the postal code loses its spaces entirely),
+    /// then goes in behind its byte length as a u32 big-endian, so no two splits of one string collide.
+    pub fn from_postal_address(line_1: &str, city: &str, postal_code: &str) -> Self {
+        let collapse = |part: &str| part.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let postal_code: String = postal_code.split_whitespace().collect();
+        let mut hasher = Sha256::new();
+        for part in [collapse(line_1), collapse(city), postal_code.to_lowercase()] {
+            hasher.update((part.len() as u32).to_be_bytes());
+            hasher.update(part.as_bytes());
+        }
+        Self::from_hash(&hasher.finalize())
+    }
+
+    /// Up to 31 UTF-8 bytes packed big-endian, reversible with `to_short_string`
+    pub fn from_short_string(value: &str) -> Result<Self, FieldElementError> {
+        if value.len() > SHORT_STRING_MAX_BYTES {
+            return Err(FieldElementError::StringTooLong(value.len()));
+        }
+        // A leading NUL would be lost on decoding
+        if value.contains('\0') {
+            return Err(FieldElementError::Invalid(value.to_string()));
+        }
+        Ok(Self::reduce(value.as_bytes()))
+    }
+
+    pub fn to_short_string(&self) -> Result<String, FieldElementError> {
+        let bytes = self.to_bytes();
+        let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+        if start == 0 {
+            return Err(FieldElementError::WrongEncoding("short string"));
+        }
+        String::from_utf8(bytes[start..].to_vec()).map_err(|_| FieldElementError::WrongEncoding("short string"))
+    }
+
+    /// YYYYMMDD, the form the circuits compare dates in
+    pub fn from_date(date: NaiveDate) -> Result<Self, FieldElementError> {
+        Ok(Self::from_u64(date_to_numeric(date)? as u64))
+    }
+
+    pub fn to_date(&self) -> Result<NaiveDate, FieldElementError> {
+        let numeric = self.to_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or(FieldElementError::WrongEncoding("date"))?;
+        Ok(date_from_numeric(numeric)?)
+    }
+
+    /// 20-byte EVM address as its integer value (always below the modulus); checksum case is ignored
+    pub fn from_address(address: &str) -> Result<Self, FieldElementError> {
+        let invalid = || FieldElementError::InvalidAddress(address.to_string());
+        let digits = address.strip_prefix("0x").ok_or_else(invalid)?;
+        if digits.len() != ADDRESS_BYTES * 2 {
+            return Err(invalid());
+        }
+        let bytes = hex::decode(digits).map_err(|_| invalid())?;
+        Ok(Self::reduce(&bytes))
+    }
+
+    /// Lowercase 0x address
+    pub fn to_address(&self) -> Result<String, FieldElementError> {
+        let bytes = self.to_bytes();
+        let (padding, address) = bytes.split_at(32 - ADDRESS_BYTES);
+        if padding.iter().any(|byte| *byte != 0) {
+            return Err(FieldElementError::WrongEncoding("address"));
+        }
+        Ok(format!("0x{}", hex::encode(address)))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Canonical big-endian bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.into_bigint().to_bytes_be().try_into().expect("BN254 elements are 32 bytes")
+    }
+
+    fn reduce(bytes_be: &[u8]) -> Self {
+        Self(Fr::from_be_bytes_mod_order(bytes_be))
+    }
+
+    pub(crate) fn inner(&self) -> Fr {
+        self.0
+    }
+}
+
+impl From<Fr> for FieldElement {
+    fn from(value: Fr) -> Self {
+        Self(value)
+    }
+}
+
+impl From<u64> for FieldElement {
+    fn from(value: u64) -> Self {
+        Self::from_u64(value)
+    }
+}
+
+impl From<u32> for FieldElement {
+    fn from(value: u32) -> Self {
+        Self::from_u64(value as u64)
+    }
+}
+
+impl fmt::Display for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FieldElement({})", self.to_hex())
+    }
+}
+
+/// 0x hex, or a decimal integer as Noir's Prover.toml allows
+impl FromStr for FieldElement {
+    type Err = FieldElementError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.starts_with("0x") {
+            return Self::from_hex(value);
+        }
+        if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+            return Err(FieldElementError::Invalid(value.to_string()));
+        }
+        // Parsing reduces silently, so compare against the modulus first
+        let digits = value.trim_start_matches('0');
+        let modulus = Fr::MODULUS.to_string();
+        if digits.len() > modulus.len() || (digits.len() == modulus.len() && digits >= modulus.as_str()) {
+            return Err(FieldElementError::NotCanonical(value.to_string()));
+        }
+        Fr::from_str(value).map(Self).map_err(|_| FieldElementError::Invalid(value.to_string()))
+    }
+}
+
+impl Serialize for FieldElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+/// Accepts a hex or decimal string, or a non-negative JSON integer
+impl<'de> Deserialize<'de> for FieldElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldElementVisitor;
+
+        impl Visitor<'_> for FieldElementVisitor {
+            type Value = FieldElement;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a BN254 field element as a hex or decimal string, or an unsigned integer")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<FieldElement, E> {
+                Ok(FieldElement::from_u64(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<FieldElement, E> {
+                u64::try_from(value)
+                    .map(FieldElement::from_u64)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<FieldElement, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(FieldElementVisitor)
+    }
+}
+
+/// Struct handed to a Noir circuit as (part of) its witness
+pub trait CircuitInput {
+    /// Members in the order the circuit declares them
+    fn to_fields(&self) -> Vec<(&'static str, FieldElement)>;
+
+    /// Noir ABI JSON object with every member hex-encoded
+    fn to_noir_value(&self) -> serde_json::Value {
+        self.to_fields().into_iter()
+            .map(|(name, value)| (name.to_string(), serde_json::Value::String(value.to_hex())))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_and_integer_round_trip() {
+        let element = FieldElement::from_u64(19900815);
+        assert_eq!(element.to_hex(), format!("0x{:064x}", 19900815));
+        assert_eq!(FieldElement::from_hex(&element.to_hex()).unwrap(), element);
+        assert_eq!(element.to_u64(), Some(19900815));
+        assert_eq!("19900815".parse::<FieldElement>().unwrap(), element);
+
+        let modulus = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+        assert!(matches!(FieldElement::from_hex(modulus), Err(FieldElementError::NotCanonical(_))));
+        assert!(matches!(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617".parse::<FieldElement>(),
+            Err(FieldElementError::NotCanonical(_))
+        ));
+        assert_eq!(FieldElement::from_i64(-1).to_hex(), "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000");
+        assert_eq!(FieldElement::from_i64(-1).to_u64(), None);
+    }
+
+    #[test]
+    fn test_hash_and_text_encodings() {
+        // A SHA-256 digest above the modulus is reduced, never rejected
+        let digest = [0xFF; 32];
+        let element = FieldElement::from_hash(&digest);
+        assert_eq!(FieldElement::from_hex(&element.to_hex()).unwrap(), element);
+        assert_ne!(element.to_bytes(), digest);
+
+        assert_eq!(FieldElement::from_text("secret"), FieldElement::from_hash(&Sha256::digest(b"secret")));
+        assert_ne!(FieldElement::from_text("Secret"), FieldElement::from_text("secret"));
+
+        // Moving characters between parts changes the address; spacing and case do not
+        let address = FieldElement::from_postal_address("1 Main St", "Pune", "411001");
+        assert_ne!(FieldElement::from_postal_address("1 Main StP", "une", "411001"), address);
+        assert_ne!(FieldElement::from_postal_address("1 Main St", "Pune4", "11001"), address);
+        assert_eq!(FieldElement::from_postal_address("  1  MAIN St ", "pune", "411 001"), address);
+    }
+
+    #[test]
+    fn test_short_string_date_and_address_round_trip() {
+        let code = FieldElement::from_short_string("IN-MH").unwrap();
+        assert_eq!(code.to_short_string().unwrap(), "IN-MH");
+        assert_eq!(FieldElement::from_short_string(&"a".repeat(32)), Err(FieldElementError::StringTooLong(32)));
+
+        let date = NaiveDate::from_ymd_opt(2004, 2, 29).unwrap();
+        let element = FieldElement::from_date(date).unwrap();
+        assert_eq!(element.to_u64(), Some(20040229));
+        assert_eq!(element.to_date().unwrap(), date);
+        assert!(FieldElement::from_u64(20230229).to_date().is_err());
+
+        let address = FieldElement::from_address("0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0").unwrap();
+        assert_eq!(address.to_address().unwrap(), "0x742d35cc6634c0532925a3b844bc9e7595f0beb0");
+        assert!(FieldElement::from_address("0x742d35").is_err());
+        assert_eq!(FieldElement::from_text("x").to_address(), Err(FieldElementError::WrongEncoding("address")));
+    }
+
+    #[test]
+    fn test_serde_formats() {
+        let element = FieldElement::from_u64(356);
+        let json = serde_json::to_string(&element).unwrap();
+        assert_eq!(serde_json::from_str::<FieldElement>(&json).unwrap(), element);
+        assert_eq!(serde_json::from_str::<FieldElement>("356").unwrap(), element);
+        assert_eq!(serde_json::from_str::<FieldElement>("\"356\"").unwrap(), element);
+        assert_eq!(serde_json::from_str::<FieldElement>("\"0x164\"").unwrap(), element);
+        assert!(serde_json::from_str::<FieldElement>("-1").is_err());
+        assert!(serde_json::from_str::<FieldElement>("\"secret\"").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// Offline e-KYC Freshness
+// Decodes the generation timestamp UIDAI embeds in referenceId and bounds how old a file may be
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+use crate::aadhaar_xml_parser::AadhaarVerificationError;
+
+/// UIDAI referenceId: last 4 Aadhaar digits followed by the IST timestamp YYYYMMDDHHMMSSsss
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceId {
+    pub aadhaar_last_4_digits: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl ReferenceId {
+    pub fn parse(reference_id: &str) -> Result<Self, AadhaarVerificationError> {
+        let invalid = || AadhaarVerificationError::InvalidReferenceId(reference_id.to_string());
+
+        if reference_id.len() != 21 || !reference_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let (last_4, timestamp) = reference_id.split_at(4);
+
+        let local = NaiveDateTime::parse_from_str(timestamp, "%Y%m%d%H%M%S%3f").map_err(|_| invalid())?;
+        let generated_at = ist().from_local_datetime(&local).single().ok_or_else(invalid)?;
+
+        Ok(Self {
+            aadhaar_last_4_digits: last_4.to_string(),
+            generated_at: generated_at.with_timezone(&Utc),
+        })
+    }
+
+    /// Generation date as UIDAI prints it (IST), YYYY-MM-DD
+    pub fn generated_date(&self) -> String {
+        self.generated_at.with_timezone(&ist()).format("%Y-%m-%d").to_string()
+    }
+}
+
+fn ist() -> FixedOffset {
+    FixedOffset::east_opt(5 * 3600 + 30 * 60).expect("IST offset is in range")
+}
+
+/// Default bound: RBI's KYC directions accept offline e-KYC files up to 3 days old
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 3;
+
+/// How far a file's timestamp may run ahead of the verifier's clock
+pub const DEFAULT_MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// How old an offline e-KYC file may be when it is presented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreshnessPolicy {
+    pub max_age: Option<Duration>, // None accepts any age, including undated files
+    pub max_clock_skew: Duration,  // Files dated later than now plus this are rejected
+}
+
+impl Default for FreshnessPolicy {
+    fn default() -> Self {
+        Self::max_age_days(DEFAULT_MAX_AGE_DAYS)
+    }
+}
+
+impl FreshnessPolicy {
+    pub fn unrestricted() -> Self {
+        Self { max_age: None, max_clock_skew: Duration::minutes(DEFAULT_MAX_CLOCK_SKEW_MINUTES) }
+    }
+
+    pub fn max_age_days(days: i64) -> Self {
+        Self { max_age: Some(Duration::days(days)), ..Self::unrestricted() }
+    }
+
+    pub fn check(
+        &self,
+        generated_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<(), AadhaarVerificationError> {
+        if let Some(generated_at) = generated_at {
+            if generated_at - now > self.max_clock_skew {
+                return Err(AadhaarVerificationError::FutureDatedDocument(generated_at.to_rfc3339()));
+            }
+        }
+        let Some(max_age) = self.max_age else {
+            return Ok(());
+        };
+        let generated_at = generated_at.ok_or(AadhaarVerificationError::UndatedDocument)?;
+
+        if now - generated_at > max_age {
+            return Err(AadhaarVerificationError::StaleDocument {
+                generated_at: generated_at.to_rfc3339(),
+                max_age_days: max_age.num_days(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_id_timestamp_is_ist() {
+        let reference = ReferenceId::parse("234520250101120000123").unwrap();
+        assert_eq!(reference.aadhaar_last_4_digits, "2345");
+        assert_eq!(reference.generated_at.to_rfc3339(), "2025-01-01T06:30:00.123+00:00");
+        // Just after midnight IST is still the previous day in UTC
+        assert_eq!(ReferenceId::parse("234520250101003000000").unwrap().generated_date(), "2025-01-01");
+
+        assert!(ReferenceId::parse("TEST123").is_err());
+        assert!(ReferenceId::parse("234520251301120000000").is_err());
+        assert!(ReferenceId::parse("XXXX20250101120000000").is_err());
+    }
+
+    #[test]
+    fn test_freshness_policy() {
+        let generated_at = ReferenceId::parse("234520250101120000000").unwrap().generated_at;
+        let policy = FreshnessPolicy::max_age_days(3);
+
+        assert!(policy.check(Some(generated_at), generated_at + Duration::days(3)).is_ok());
+        assert!(matches!(
+            policy.check(Some(generated_at), generated_at + Duration::days(3) + Duration::seconds(1)),
+            Err(AadhaarVerificationError::StaleDocument { max_age_days: 3, .. })
+        ));
+        assert!(matches!(
+            policy.check(None, generated_at),
+            Err(AadhaarVerificationError::UndatedDocument)
+        ));
+        assert!(FreshnessPolicy::unrestricted().check(None, generated_at).is_ok());
+
+        // Files are bounded unless a caller opts out, and never dated beyond the clock skew
+        assert_eq!(FreshnessPolicy::default().max_age, Some(Duration::days(DEFAULT_MAX_AGE_DAYS)));
+        assert!(matches!(
+            FreshnessPolicy::default().check(None, generated_at),
+            Err(AadhaarVerificationError::UndatedDocument)
+        ));
+        let skew = Duration::minutes(DEFAULT_MAX_CLOCK_SKEW_MINUTES);
+        assert!(policy.check(Some(generated_at), generated_at - skew).is_ok());
+        for policy in [policy, FreshnessPolicy::unrestricted()] {
+            assert!(matches!(
+                policy.check(Some(generated_at), generated_at - skew - Duration::seconds(1)),
+                Err(AadhaarVerificationError::FutureDatedDocument(_))
+            ));
+        }
+    }
+}
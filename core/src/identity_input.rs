@@ -1,32 +1,50 @@
 use chrono::{NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::birth_date::{date_to_numeric, BirthDate, YearOnlyMode};
+use crate::logging::Field;
+use crate::field_element::{CircuitInput, FieldElement};
+use crate::poseidon::poseidon_hash;
+use crate::sensitive::Sensitive;
+use crate::{log_debug, log_info};
+
+/// Holder-entered identity. Read from the form; it has no `Serialize` impl, so it cannot be written back out.
+#[derive(Debug, Clone, Deserialize)]
 pub struct ManualIdentityData {
     // Basic Identity
-    pub full_name: String,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub full_name: Sensitive<String>,
     pub date_of_birth: NaiveDate,
     pub gender: Gender,
     pub nationality: String,
 
     // Address Information
-    pub address_line_1: String,
-    pub address_line_2: Option<String>,
-    pub city: String,
-    pub state_province: String,
-    pub postal_code: String,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub address_line_1: Sensitive<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub address_line_2: Sensitive<Option<String>>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub city: Sensitive<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub state_province: Sensitive<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub postal_code: Sensitive<String>,
     pub country: String,
 
     // Identity Document Numbers (user-provided)
-    pub national_id_number: Option<String>,
-    pub passport_number: Option<String>,
-    pub driver_license_number: Option<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub national_id_number: Sensitive<Option<String>>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub passport_number: Sensitive<Option<String>>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub driver_license_number: Sensitive<Option<String>>,
 
     // Additional Information
-    pub phone_number: Option<String>,
-    pub email: Option<String>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub phone_number: Sensitive<Option<String>>,
+    #[serde(deserialize_with = "crate::sensitive::exposed::deserialize")]
+    pub email: Sensitive<Option<String>>,
 
     // Metadata
     pub input_timestamp: u64,
@@ -41,16 +59,18 @@ pub enum Gender {
     PreferNotToSay,
 }
 
-impl Gender {
-    pub fn to_string(&self) -> String {
+impl std::fmt::Display for Gender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Gender::Male => "Male".to_string(),
-            Gender::Female => "Female".to_string(),
-            Gender::Other(s) => s.clone(),
-            Gender::PreferNotToSay => "PreferNotToSay".to_string(),
+            Gender::Male => write!(f, "Male"),
+            Gender::Female => write!(f, "Female"),
+            Gender::Other(s) => write!(f, "{}", s),
+            Gender::PreferNotToSay => write!(f, "PreferNotToSay"),
         }
     }
+}
 
+impl Gender {
     pub fn from_string(s: &str) -> Self {
         match s {
             "Male" => Gender::Male,
@@ -98,11 +118,11 @@ impl IdentityInputValidator {
         let mut issues = Vec::new();
 
         // Basic validation rules
-        if data.full_name.trim().is_empty() {
+        if data.full_name.expose().trim().is_empty() {
             issues.push("Full name is required".to_string());
         }
 
-        if data.full_name.trim().len() < 2 {
+        if data.full_name.expose().trim().len() < 2 {
             issues.push("Full name must be at least 2 characters".to_string());
         }
 
@@ -112,17 +132,18 @@ impl IdentityInputValidator {
             issues.push("Birth date cannot be in the future".to_string());
         }
 
-        // Age validation
-        let age_years = (today - data.date_of_birth).num_days() / 365;
-        if age_years < 13 {
-            issues.push("Age must be at least 13 years".to_string());
-        }
-        if age_years > 120 {
-            issues.push("Age cannot exceed 120 years".to_string());
+        // Age validation (future dates are reported above)
+        if let Ok(age_years) = BirthDate::Exact(data.date_of_birth).age_on(today, YearOnlyMode::Strict) {
+            if age_years < 13 {
+                issues.push("Age must be at least 13 years".to_string());
+            }
+            if age_years > 120 {
+                issues.push("Age cannot exceed 120 years".to_string());
+            }
         }
 
         // Address validation
-        if data.city.trim().is_empty() {
+        if data.city.expose().trim().is_empty() {
             issues.push("City is required".to_string());
         }
 
@@ -130,12 +151,12 @@ impl IdentityInputValidator {
             issues.push("Country is required".to_string());
         }
 
-        if data.address_line_1.trim().is_empty() {
+        if data.address_line_1.expose().trim().is_empty() {
             issues.push("Address line 1 is required".to_string());
         }
 
         // Postal code format validation
-        if !Self::validate_postal_code(&data.postal_code, &data.country) {
+        if !Self::validate_postal_code(data.postal_code.expose(), &data.country) {
             issues.push(format!(
                 "Invalid postal code format for country: {}",
                 data.country
@@ -148,22 +169,28 @@ impl IdentityInputValidator {
         }
 
         // Email validation (if provided)
-        if let Some(email) = &data.email {
+        if let Some(email) = data.email.expose() {
             if !email.trim().is_empty() && !Self::validate_email(email) {
                 issues.push("Invalid email format".to_string());
             }
         }
 
         // Phone number validation (if provided)
-        if let Some(phone) = &data.phone_number {
+        if let Some(phone) = data.phone_number.expose() {
             if !phone.trim().is_empty() && !Self::validate_phone(phone) {
                 issues.push("Invalid phone number format".to_string());
             }
         }
 
         if issues.is_empty() {
+            log_debug!("Identity input validated", Field::public("country", &data.country));
             Ok(ValidationResult::Valid)
         } else {
+            log_info!(
+                "Identity input rejected",
+                Field::public("issues", issues.len()),
+                Field::sensitive("full_name", &data.full_name),
+            );
             Ok(ValidationResult::Invalid(issues))
         }
     }
@@ -227,74 +254,66 @@ impl IdentityInputValidator {
         digits.len() >= 10 && digits.len() <= 15
     }
 
-    /// Generate cryptographic identity commitment from identity data
-    pub fn generate_identity_commitment(data: &ManualIdentityData) -> String {
-        let mut hasher = Sha256::new();
-
-        // Hash critical identity fields
-        hasher.update(data.full_name.to_lowercase().trim().as_bytes());
-        hasher.update(data.date_of_birth.format("%Y%m%d").to_string().as_bytes());
-        hasher.update(data.gender.to_string().as_bytes());
-        hasher.update(data.nationality.to_lowercase().trim().as_bytes());
-        hasher.update(data.country.to_lowercase().trim().as_bytes());
-        hasher.update(data.city.to_lowercase().trim().as_bytes());
-        hasher.update(data.postal_code.trim().as_bytes());
-        hasher.update(data.user_wallet_address.to_lowercase().as_bytes());
-        hasher.update(data.input_timestamp.to_string().as_bytes());
-
-        let result = hasher.finalize();
-        format!("0x{}", hex::encode(result))
+    /// Identity commitment checked by text_identity_proof.nr:
+    /// poseidon::bn254::hash_4([name_hash, birth_date, nationality_hash, wallet_address])
+    pub fn generate_identity_commitment(data: &ManualIdentityData) -> FieldElement {
+        let hashes = Self::generate_field_hashes(data);
+
+        poseidon_hash(&[
+            hashes.name_hash,
+            Self::birth_date_field(data),
+            hashes.nationality_hash,
+            Self::wallet_field(data),
+        ]).expect("four inputs are supported")
     }
 
-    /// Generate field-specific hashes for ZK circuit inputs
+    /// Field-element hashes for ZK circuit inputs; see `FieldHashes` for each encoding
     pub fn generate_field_hashes(data: &ManualIdentityData) -> FieldHashes {
-        let mut name_hasher = Sha256::new();
-        name_hasher.update(data.full_name.to_lowercase().trim().as_bytes());
-        let name_hash = hex::encode(name_hasher.finalize());
-
-        let mut nationality_hasher = Sha256::new();
-        nationality_hasher.update(data.nationality.to_lowercase().trim().as_bytes());
-        let nationality_hash = hex::encode(nationality_hasher.finalize());
-
-        let mut country_hasher = Sha256::new();
-        country_hasher.update(data.country.to_lowercase().trim().as_bytes());
-        let country_hash = hex::encode(country_hasher.finalize());
-
-        let mut address_hasher = Sha256::new();
-        address_hasher.update(data.address_line_1.to_lowercase().trim().as_bytes());
-        address_hasher.update(data.city.to_lowercase().trim().as_bytes());
-        address_hasher.update(data.postal_code.trim().as_bytes());
-        let address_hash = hex::encode(address_hasher.finalize());
+        let nationality_code = Self::get_country_code(&data.nationality) as u64;
 
         FieldHashes {
-            name_hash,
-            nationality_hash,
-            country_hash,
-            address_hash,
+            name_hash: FieldElement::from_text(data.full_name.expose().to_lowercase().trim()),
+            nationality_hash: poseidon_hash(&[nationality_code.into()]).expect("one input is supported"),
+            country_hash: FieldElement::from_text(data.country.to_lowercase().trim()),
+            address_hash: FieldElement::from_postal_address(
+                data.address_line_1.expose(),
+                data.city.expose(),
+                data.postal_code.expose(),
+            ),
         }
     }
 
+    /// YYYYMMDD; dates before year 0 cannot pass validation and encode as zero
+    fn birth_date_field(data: &ManualIdentityData) -> FieldElement {
+        FieldElement::from_date(data.date_of_birth).unwrap_or(FieldElement::ZERO)
+    }
+
+    /// Invalid addresses are rejected by `validate_input` and encode as zero
+    fn wallet_field(data: &ManualIdentityData) -> FieldElement {
+        FieldElement::from_address(&data.user_wallet_address).unwrap_or(FieldElement::ZERO)
+    }
+
     /// Calculate age in days from date of birth
     pub fn calculate_age_days(date_of_birth: NaiveDate) -> i64 {
         let today = Utc::now().date_naive();
         (today - date_of_birth).num_days()
     }
 
-    /// Extract country code (ISO 3166-1 alpha-2)
+    /// ISO 3166-1 numeric code from a country name, alpha-2/3 code or nationality
     pub fn get_country_code(country: &str) -> u32 {
-        match country.to_uppercase().as_str() {
-            "INDIA" | "IN" => 356,
-            "UNITED STATES" | "USA" | "US" => 840,
-            "UNITED KINGDOM" | "UK" | "GB" => 826,
-            "CANADA" | "CA" => 124,
-            "GERMANY" | "DE" => 276,
-            "FRANCE" | "FR" => 250,
-            "ITALY" | "IT" => 380,
-            "SPAIN" | "ES" => 724,
-            "JAPAN" | "JP" => 392,
-            "CHINA" | "CN" => 156,
-            "AUSTRALIA" | "AU" => 36,
-            "BRAZIL" | "BR" => 76,
+        match country.trim().to_uppercase().as_str() {
+            "INDIA" | "IN" | "INDIAN" => 356,
+            "UNITED STATES" | "USA" | "US" | "AMERICAN" => 840,
+            "UNITED KINGDOM" | "UK" | "GB" | "BRITISH" => 826,
+            "CANADA" | "CA" | "CANADIAN" => 124,
+            "GERMANY" | "DE" | "GERMAN" => 276,
+            "FRANCE" | "FR" | "FRENCH" => 250,
+            "ITALY" | "IT" | "ITALIAN" => 380,
+            "SPAIN" | "ES" | "SPANISH" => 724,
+            "JAPAN" | "JP" | "JAPANESE" => 392,
+            "CHINA" | "CN" | "CHINESE" => 156,
+            "AUSTRALIA" | "AU" | "AUSTRALIAN" => 36,
+            "BRAZIL" | "BR" | "BRAZILIAN" => 76,
             _ => 0, // Unknown country
         }
     }
@@ -311,22 +330,22 @@ impl IdentityInputValidator {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FieldHashes {
-    pub name_hash: String,
-    pub nationality_hash: String,
-    pub country_hash: String,
-    pub address_hash: String,
+    pub name_hash: FieldElement,        // SHA-256 of the trimmed, lowercased name, reduced mod p
+    pub nationality_hash: FieldElement, // Poseidon hash_1 of the ISO numeric code, as validate_nationality expects
+    pub country_hash: FieldElement,     // SHA-256 of the trimmed, lowercased country, reduced mod p
+    pub address_hash: FieldElement,     // `FieldElement::from_postal_address` of line 1, city and postal code
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CircuitInputs {
     // Private inputs (hashed)
-    pub name_hash: String,
+    pub name_hash: FieldElement,
     pub birth_date: u32, // YYYYMMDD format
-    pub nationality_hash: String,
-    pub country_hash: String,
-    pub address_hash: String,
+    pub nationality_hash: FieldElement,
+    pub country_hash: FieldElement,
+    pub address_hash: FieldElement,
 
     // Plaintext data for verification
     pub age_in_days: i64,
@@ -334,9 +353,9 @@ pub struct CircuitInputs {
     pub postal_code_region: u32,
 
     // User and commitment data
-    pub wallet_address: String,
+    pub wallet_address: FieldElement, // 20-byte address as an integer
     pub input_timestamp: u64,
-    pub identity_commitment: String,
+    pub identity_commitment: FieldElement,
 }
 
 impl CircuitInputs {
@@ -345,15 +364,10 @@ impl CircuitInputs {
         let field_hashes = IdentityInputValidator::generate_field_hashes(data);
         let age_in_days = IdentityInputValidator::calculate_age_days(data.date_of_birth);
         let country_code = IdentityInputValidator::get_country_code(&data.country);
-        let postal_code_region = IdentityInputValidator::get_postal_region(&data.postal_code);
+        let postal_code_region = IdentityInputValidator::get_postal_region(data.postal_code.expose());
         let identity_commitment = IdentityInputValidator::generate_identity_commitment(data);
 
-        let birth_date = data
-            .date_of_birth
-            .format("%Y%m%d")
-            .to_string()
-            .parse::<u32>()
-            .unwrap_or(0);
+        let birth_date = date_to_numeric(data.date_of_birth).unwrap_or(0);
 
         CircuitInputs {
             name_hash: field_hashes.name_hash,
@@ -364,15 +378,34 @@ impl CircuitInputs {
             age_in_days,
             country_code,
             postal_code_region,
-            wallet_address: data.user_wallet_address.clone(),
+            wallet_address: IdentityInputValidator::wallet_field(data),
             input_timestamp: data.input_timestamp,
             identity_commitment,
         }
     }
 
-    /// Export as JSON for Noir circuit
+    /// Export as JSON for Noir circuit, every member hex-encoded
     pub fn to_noir_json(&self) -> String {
-        serde_json::to_string_pretty(self).unwrap_or_default()
+        serde_json::to_string_pretty(&self.to_noir_value()).unwrap_or_default()
+    }
+}
+
+/// Members of `TextIdentityData` in text_identity_proof.nr
+impl CircuitInput for CircuitInputs {
+    fn to_fields(&self) -> Vec<(&'static str, FieldElement)> {
+        vec![
+            ("name_hash", self.name_hash),
+            ("birth_date", self.birth_date.into()),
+            ("nationality_hash", self.nationality_hash),
+            ("country_hash", self.country_hash),
+            ("address_hash", self.address_hash),
+            ("age_in_days", FieldElement::from_i64(self.age_in_days)),
+            ("country_code", self.country_code.into()),
+            ("postal_code_region", self.postal_code_region.into()),
+            ("wallet_address", self.wallet_address),
+            ("input_timestamp", self.input_timestamp.into()),
+            ("identity_commitment", self.identity_commitment),
+        ]
     }
 }
 
@@ -383,23 +416,23 @@ mod tests {
     #[test]
     fn test_validate_valid_identity() {
         let data = ManualIdentityData {
-            full_name: "John Doe".to_string(),
+            full_name: "John Doe".to_string().into(),
             date_of_birth: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
             gender: Gender::Male,
             nationality: "American".to_string(),
-            address_line_1: "123 Main St".to_string(),
-            address_line_2: None,
-            city: "New York".to_string(),
-            state_province: "NY".to_string(),
-            postal_code: "10001".to_string(),
+            address_line_1: "123 Main St".to_string().into(),
+            address_line_2: None.into(),
+            city: "New York".to_string().into(),
+            state_province: "NY".to_string().into(),
+            postal_code: "10001".to_string().into(),
             country: "USA".to_string(),
-            national_id_number: None,
-            passport_number: None,
-            driver_license_number: None,
-            phone_number: Some("+1234567890".to_string()),
-            email: Some("john@example.com".to_string()),
+            national_id_number: None.into(),
+            passport_number: None.into(),
+            driver_license_number: None.into(),
+            phone_number: Some("+1234567890".to_string()).into(),
+            email: Some("john@example.com".to_string()).into(),
             input_timestamp: 1699000000,
-            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0".to_string(),
         };
 
         let result = IdentityInputValidator::validate_input(&data).unwrap();
@@ -409,23 +442,23 @@ mod tests {
     #[test]
     fn test_validate_invalid_age() {
         let data = ManualIdentityData {
-            full_name: "Child User".to_string(),
+            full_name: "Child User".to_string().into(),
             date_of_birth: Utc::now().date_naive(),
             gender: Gender::PreferNotToSay,
             nationality: "Indian".to_string(),
-            address_line_1: "123 Street".to_string(),
-            address_line_2: None,
-            city: "Mumbai".to_string(),
-            state_province: "Maharashtra".to_string(),
-            postal_code: "400001".to_string(),
+            address_line_1: "123 Street".to_string().into(),
+            address_line_2: None.into(),
+            city: "Mumbai".to_string().into(),
+            state_province: "Maharashtra".to_string().into(),
+            postal_code: "400001".to_string().into(),
             country: "India".to_string(),
-            national_id_number: None,
-            passport_number: None,
-            driver_license_number: None,
-            phone_number: None,
-            email: None,
+            national_id_number: None.into(),
+            passport_number: None.into(),
+            driver_license_number: None.into(),
+            phone_number: None.into(),
+            email: None.into(),
             input_timestamp: 1699000000,
-            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0".to_string(),
         };
 
         let result = IdentityInputValidator::validate_input(&data).unwrap();
@@ -443,64 +476,75 @@ mod tests {
     #[test]
     fn test_wallet_address_validation() {
         assert!(IdentityInputValidator::validate_wallet_address(
-            "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb"
+            "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0"
         ));
-        assert!(!IdentityInputValidator::validate_wallet_address("742d35Cc6634C0532925a3b844Bc9e7595f0bEb"));
+        assert!(!IdentityInputValidator::validate_wallet_address("742d35Cc6634C0532925a3b844Bc9e7595f0bEb0"));
         assert!(!IdentityInputValidator::validate_wallet_address("0xinvalid"));
     }
 
     #[test]
     fn test_identity_commitment_generation() {
         let data = ManualIdentityData {
-            full_name: "Test User".to_string(),
+            full_name: "Test User".to_string().into(),
             date_of_birth: NaiveDate::from_ymd_opt(1995, 5, 15).unwrap(),
             gender: Gender::Female,
             nationality: "Canadian".to_string(),
-            address_line_1: "456 Elm St".to_string(),
-            address_line_2: None,
-            city: "Toronto".to_string(),
-            state_province: "ON".to_string(),
-            postal_code: "M5H2N2".to_string(),
+            address_line_1: "456 Elm St".to_string().into(),
+            address_line_2: None.into(),
+            city: "Toronto".to_string().into(),
+            state_province: "ON".to_string().into(),
+            postal_code: "M5H2N2".to_string().into(),
             country: "Canada".to_string(),
-            national_id_number: None,
-            passport_number: None,
-            driver_license_number: None,
-            phone_number: None,
-            email: None,
+            national_id_number: None.into(),
+            passport_number: None.into(),
+            driver_license_number: None.into(),
+            phone_number: None.into(),
+            email: None.into(),
             input_timestamp: 1699000000,
-            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0".to_string(),
         };
 
-        let commitment = IdentityInputValidator::generate_identity_commitment(&data);
+        let commitment = IdentityInputValidator::generate_identity_commitment(&data).to_hex();
         assert!(commitment.starts_with("0x"));
         assert_eq!(commitment.len(), 66); // 0x + 64 hex chars
+        // hash_4 over SHA-256("test user") mod p, 19950515, hash_1([124]) and the wallet
+        assert_eq!(commitment, "0x2e050e8338ca510facdf5b2583e007c1d827c935f6fecef812d017d96d94ecec");
     }
 
     #[test]
     fn test_circuit_inputs_generation() {
         let data = ManualIdentityData {
-            full_name: "Circuit Test".to_string(),
+            full_name: "Circuit Test".to_string().into(),
             date_of_birth: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
             gender: Gender::Male,
             nationality: "Indian".to_string(),
-            address_line_1: "789 Test St".to_string(),
-            address_line_2: None,
-            city: "Bangalore".to_string(),
-            state_province: "Karnataka".to_string(),
-            postal_code: "560001".to_string(),
+            address_line_1: "789 Test St".to_string().into(),
+            address_line_2: None.into(),
+            city: "Bangalore".to_string().into(),
+            state_province: "Karnataka".to_string().into(),
+            postal_code: "560001".to_string().into(),
             country: "India".to_string(),
-            national_id_number: Some("123456789012".to_string()),
-            passport_number: None,
-            driver_license_number: None,
-            phone_number: None,
-            email: None,
+            national_id_number: Some("123456789012".to_string()).into(),
+            passport_number: None.into(),
+            driver_license_number: None.into(),
+            phone_number: None.into(),
+            email: None.into(),
             input_timestamp: 1699000000,
-            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            user_wallet_address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb0".to_string(),
         };
 
         let inputs = CircuitInputs::from_identity_data(&data);
         assert_eq!(inputs.country_code, 356); // India
         assert!(inputs.age_in_days > 0);
         assert_eq!(inputs.postal_code_region, 560);
+
+        // "Indian" maps to 356, so the circuit's hash_1([required_country_code]) check can pass
+        assert_eq!(inputs.nationality_hash.to_hex(), "0x0905f8c9223c5ecc53aa38660491fdec7cb29e37209bd121eb4cb0d56d760de6");
+        assert_eq!(inputs.wallet_address.to_address().unwrap(), "0x742d35cc6634c0532925a3b844bc9e7595f0beb0");
+        assert_eq!(inputs.to_noir_value()["birth_date"], FieldElement::from_u64(20000101).to_hex());
+
+        let json = serde_json::to_string(&inputs).unwrap();
+        let parsed: CircuitInputs = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_fields(), inputs.to_fields());
     }
 }
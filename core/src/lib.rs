@@ -2,18 +2,109 @@
 // Provides P2P identity verification with ZK proofs and Aadhaar integration
 
 pub mod p2p_service;
+pub mod proof_type;
 pub mod aadhaar_xml_parser;
+pub mod aadhaar_qr;
+pub mod aadhaar_photo;
+pub mod contact_verification;
+pub mod offline_kyc;
 pub mod identity_input;
+pub mod xml_c14n;
+pub mod parse_limits;
+pub mod freshness;
+pub mod birth_date;
+pub mod sensitive;
+pub mod logging;
+pub mod trust_store;
+pub mod clock;
+pub mod rng;
+pub mod poseidon;
+pub mod field_element;
+pub mod merkle_tree;
+pub mod nullifier_store;
+pub mod cbor;
+pub mod cose;
+pub mod webauthn;
+pub mod attestation;
+pub mod proof_envelope;
 
 // Re-export main types
-pub use p2p_service::P2PProofService;
+pub use p2p_service::{
+    P2PProofService,
+    ProofServiceError,
+    ErrorCategory,
+    ErrorDetails,
+    ProofRequirements,
+    HolderSecrets,
+    ProofChallenge,
+    PasskeyBinding,
+};
+pub use proof_type::{
+    ProofType,
+    ProofTypeSpec,
+    ProofTypeRegistry,
+    ProofTypeError,
+    ProofSource,
+    IdField,
+    PublicSignal,
+};
 pub use aadhaar_xml_parser::{
     AadhaarXMLParser,
     VerifiedAadhaarData,
+    RedactedAadhaarData,
     AadhaarAddress,
     AadhaarVerificationError,
     validate_aadhaar_number,
 };
+pub use aadhaar_qr::{
+    SecureQrData,
+    SecureQrVersion,
+    decode_secure_qr,
+    decode_secure_qr_with_limits,
+    parse_secure_qr,
+    parse_secure_qr_with_limits,
+};
+pub use offline_kyc::{
+    AadhaarKycDocument,
+    KycSchemaVersion,
+    KycSchemaError,
+    OfflinePaperlessKyc,
+    PrintLetterBarcodeData,
+};
+pub use aadhaar_photo::{AadhaarPhoto, PhotoCommitment, PhotoFormat, PhotoError};
+pub use contact_verification::{contact_commitment, contact_document_hash, ContactKind};
+pub use parse_limits::ParseLimits;
+pub use sensitive::Sensitive;
+pub use logging::{LogLevel, LogRecord, LogSink, StderrSink};
+pub use birth_date::{AgeBracket, BirthDate, BirthDateError, YearOnlyMode};
+pub use freshness::{FreshnessPolicy, ReferenceId, DEFAULT_MAX_AGE_DAYS, DEFAULT_MAX_CLOCK_SKEW_MINUTES};
+pub use trust_store::{TrustStore, TrustedCertificate, SignerCertificate};
+pub use clock::{Clock, SystemClock, FixedClock};
+pub use rng::{Rng, SystemRng, FixedRng};
+pub use poseidon::{Fr, PoseidonError, poseidon2_hash, poseidon_hash};
+pub use field_element::{FieldElement, FieldElementError, CircuitInput};
+pub use merkle_tree::{MerkleTree, MerkleFrontier, MerklePath, MerkleError, TREE_DEPTH};
+pub use nullifier_store::{
+    NullifierStore,
+    NullifierStoreError,
+    NullifierPolicy,
+    NullifierRecord,
+    MemoryNullifierStore,
+    FileNullifierStore,
+};
+#[cfg(feature = "wasm")]
+pub use nullifier_store::IndexedDbNullifierStore;
+pub use cbor::{CborValue, CborError};
+pub use cose::{CoseKey, CoseAlgorithm, CoseError};
+pub use webauthn::{
+    RelyingParty,
+    PasskeyAssertion,
+    AuthenticatorData,
+    AttestedCredential,
+    WebAuthnError,
+};
+pub use attestation::{AttestationType, PasskeyRegistration, VerifiedRegistration};
+pub use proof_envelope::{EnvelopeError, EnvelopeSize, ENVELOPE_VERSION};
 pub use identity_input::{
     ManualIdentityData,
     Gender,
@@ -24,10 +115,22 @@ pub use identity_input::{
 };
 
 // WASM initialization
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(start)]
 pub fn init() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Change the runtime log level from JavaScript: off, error, warn, info, debug or trace
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = setLogLevel)]
+pub fn set_log_level_from_js(level: &str) -> Result<(), JsValue> {
+    let level = LogLevel::from_name(level)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown log level: {}", level)))?;
+    logging::set_log_level(level);
+    Ok(())
+}
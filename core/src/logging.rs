@@ -0,0 +1,257 @@
+// Structured Logging Facade
+// One entry point for diagnostics, routed to the browser console under WASM and stderr natively, with PII redacted
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroize;
+
+use crate::sensitive::Sensitive;
+
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(LogLevel::Off),
+            "error" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Off,
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogLevel::Off => "OFF",
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+        })
+    }
+}
+
+/// Key/value attached to a log event. PII fields never reach a sink in clear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub key: &'static str,
+    pub value: String,
+}
+
+impl Field {
+    pub fn public(key: &'static str, value: impl fmt::Display) -> Self {
+        Self { key, value: value.to_string() }
+    }
+
+    /// Tag a value as PII; only the placeholder is recorded
+    pub fn pii(key: &'static str, _value: impl fmt::Display) -> Self {
+        Self { key, value: REDACTED.to_string() }
+    }
+
+    pub fn sensitive<T: Zeroize>(key: &'static str, _value: &Sensitive<T>) -> Self {
+        Self { key, value: REDACTED.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub target: &'static str, // Module path of the call site
+    pub message: String,
+    pub fields: Vec<Field>,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} {}] {}", self.level, self.target, self.message)?;
+        for field in &self.fields {
+            write!(f, " {}={}", field.key, field.value)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait LogSink: Send + Sync {
+    fn write(&self, record: &LogRecord);
+}
+
+/// Browser developer console, one method per level
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleSink;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl LogSink for ConsoleSink {
+    fn write(&self, record: &LogRecord) {
+        use web_sys::console;
+
+        let line = wasm_bindgen::JsValue::from_str(&record.to_string());
+        match record.level {
+            LogLevel::Error => console::error_1(&line),
+            LogLevel::Warn => console::warn_1(&line),
+            LogLevel::Info => console::info_1(&line),
+            _ => console::debug_1(&line),
+        }
+    }
+}
+
+/// Native sink for server and CLI use
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrSink;
+
+impl LogSink for StderrSink {
+    fn write(&self, record: &LogRecord) {
+        eprintln!("{}", record);
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static SINK: RwLock<Option<Arc<dyn LogSink>>> = RwLock::new(None);
+
+pub fn log_level() -> LogLevel {
+    LogLevel::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+pub fn set_log_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Replace the sink for the whole process
+pub fn set_log_sink(sink: Arc<dyn LogSink>) {
+    *SINK.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(sink);
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level <= log_level()
+}
+
+pub fn log(level: LogLevel, target: &'static str, message: impl Into<String>, fields: Vec<Field>) {
+    if !enabled(level) {
+        return;
+    }
+    let record = LogRecord { level, target, message: message.into(), fields };
+
+    let sink = SINK.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    match sink {
+        Some(sink) => sink.write(&record),
+        None => default_sink().write(&record),
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn default_sink() -> ConsoleSink {
+    ConsoleSink
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn default_sink() -> StderrSink {
+    StderrSink
+}
+
+/// `log_event!(LogLevel::Info, "message", Field::public("key", value), ...)`; fields are only built when enabled
+#[macro_export]
+macro_rules! log_event {
+    ($level:expr, $message:expr $(, $field:expr)* $(,)?) => {
+        if $crate::logging::enabled($level) {
+            $crate::logging::log($level, module_path!(), $message, vec![$($field),*]);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($args:tt)*) => { $crate::log_event!($crate::logging::LogLevel::Error, $($args)*) };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($args:tt)*) => { $crate::log_event!($crate::logging::LogLevel::Warn, $($args)*) };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($($args:tt)*) => { $crate::log_event!($crate::logging::LogLevel::Info, $($args)*) };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($args:tt)*) => { $crate::log_event!($crate::logging::LogLevel::Debug, $($args)*) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct CaptureSink(Mutex<Vec<LogRecord>>);
+
+    impl LogSink for CaptureSink {
+        fn write(&self, record: &LogRecord) {
+            self.0.lock().unwrap().push(record.clone());
+        }
+    }
+
+    #[test]
+    fn test_pii_fields_redacted() {
+        let name = Field::pii("name", "Test User");
+        let sensitive = Field::sensitive("dob", &Sensitive::new("15-08-1990".to_string()));
+        let record = LogRecord {
+            level: LogLevel::Info,
+            target: "patriconid_core::tests",
+            message: "Aadhaar verified".to_string(),
+            fields: vec![Field::public("last_4", "1234"), name, sensitive],
+        };
+        assert_eq!(
+            record.to_string(),
+            "[INFO patriconid_core::tests] Aadhaar verified last_4=1234 name=[REDACTED] dob=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_runtime_level_and_sink() {
+        let sink = Arc::new(CaptureSink::default());
+        set_log_sink(sink.clone());
+
+        set_log_level(LogLevel::Warn);
+        crate::log_info!("logging test: filtered");
+        crate::log_warn!("logging test: kept", Field::public("attempt", 1));
+
+        set_log_level(LogLevel::Info);
+        crate::log_info!("logging test: kept after raising level");
+
+        let captured: Vec<String> = sink.0.lock().unwrap().iter()
+            .filter(|record| record.message.starts_with("logging test"))
+            .map(|record| record.message.clone())
+            .collect();
+        assert_eq!(captured, ["logging test: kept", "logging test: kept after raising level"]);
+        assert_eq!(LogLevel::from_name("WARNING"), Some(LogLevel::Warn));
+    }
+}
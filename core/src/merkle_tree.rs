@@ -0,0 +1,350 @@
+// Identity Registry Merkle Tree
+// Append-only Poseidon2 tree of identity commitments with inclusion paths in the circuit's format
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+use crate::field_element::FieldElement;
+use crate::poseidon::poseidon2_hash;
+
+/// Depth of `verify_merkle_inclusion` in circuits/p2p_selective_disclosure
+pub const TREE_DEPTH: usize = 32;
+pub const TREE_CAPACITY: u64 = 1 << TREE_DEPTH;
+
+const EMPTY_LEAF_DOMAIN: &str = "PatriconID/merkle/empty-leaf/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MerkleError {
+    #[error("Merkle tree is full ({TREE_CAPACITY} leaves)")]
+    TreeFull,
+    #[error("Leaf must be non-zero")]
+    ZeroLeaf,
+    #[error("No leaf at index {0}")]
+    LeafNotFound(u64),
+    #[error("Invalid frontier: {0}")]
+    InvalidFrontier(String),
+}
+
+/// Empty-subtree roots by level. The empty leaf is non-zero because the circuit stops hashing at
+/// the first zero sibling.
+pub fn zero_hashes() -> &'static [FieldElement; TREE_DEPTH + 1] {
+    static ZEROS: OnceLock<[FieldElement; TREE_DEPTH + 1]> = OnceLock::new();
+    ZEROS.get_or_init(|| {
+        let mut zeros = [FieldElement::from_text(EMPTY_LEAF_DOMAIN); TREE_DEPTH + 1];
+        for level in 0..TREE_DEPTH {
+            zeros[level + 1] = hash_pair(zeros[level], zeros[level]);
+        }
+        zeros
+    })
+}
+
+fn hash_pair(left: FieldElement, right: FieldElement) -> FieldElement {
+    poseidon2_hash(&[left, right])
+}
+
+/// Right-most filled node per level: enough to append and track the root without the leaves.
+/// This is what holders keep to follow the registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedFrontier")]
+pub struct MerkleFrontier {
+    next_index: u64,
+    filled_subtrees: Vec<FieldElement>, // TREE_DEPTH entries
+    root: FieldElement,
+}
+
+#[derive(Deserialize)]
+struct SerializedFrontier {
+    next_index: u64,
+    filled_subtrees: Vec<FieldElement>,
+    root: FieldElement,
+}
+
+impl TryFrom<SerializedFrontier> for MerkleFrontier {
+    type Error = MerkleError;
+
+    fn try_from(frontier: SerializedFrontier) -> Result<Self, Self::Error> {
+        if frontier.filled_subtrees.len() != TREE_DEPTH {
+            return Err(MerkleError::InvalidFrontier(format!(
+                "{} filled subtrees, expected {}", frontier.filled_subtrees.len(), TREE_DEPTH
+            )));
+        }
+        if frontier.next_index > TREE_CAPACITY {
+            return Err(MerkleError::InvalidFrontier(format!("next index {} exceeds capacity", frontier.next_index)));
+        }
+        // A full tree's right-most leaf is not in the frontier, so only there is the root taken as given
+        if frontier.next_index < TREE_CAPACITY
+            && frontier.root != empty_slot_root(frontier.next_index, &frontier.filled_subtrees)
+        {
+            return Err(MerkleError::InvalidFrontier("root does not match the filled subtrees".to_string()));
+        }
+        Ok(Self { next_index: frontier.next_index, filled_subtrees: frontier.filled_subtrees, root: frontier.root })
+    }
+}
+
+/// Root of the tree whose leaves before `next_index` are summarised by `filled_subtrees`, found by
+/// walking up from the empty slot at `next_index`
+fn empty_slot_root(next_index: u64, filled_subtrees: &[FieldElement]) -> FieldElement {
+    let zeros = zero_hashes();
+    let mut index = next_index;
+    let mut current = zeros[0];
+    for (zero, filled) in zeros.iter().zip(filled_subtrees) {
+        current = if index.is_multiple_of(2) { hash_pair(current, *zero) } else { hash_pair(*filled, current) };
+        index /= 2;
+    }
+    current
+}
+
+impl Default for MerkleFrontier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleFrontier {
+    pub fn new() -> Self {
+        let zeros = zero_hashes();
+        Self {
+            next_index: 0,
+            filled_subtrees: zeros[..TREE_DEPTH].to_vec(),
+            root: zeros[TREE_DEPTH],
+        }
+    }
+
+    pub fn root(&self) -> FieldElement {
+        self.root
+    }
+
+    /// Number of leaves appended so far, and the index the next one gets
+    pub fn len(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_index == 0
+    }
+
+    /// Append a leaf and return its index
+    pub fn append(&mut self, leaf: FieldElement) -> Result<u64, MerkleError> {
+        self.insert(leaf, |_, _, _| {})
+    }
+
+    /// Append, reporting every node on the new leaf's path as (level, index, value)
+    fn insert(&mut self, leaf: FieldElement, mut on_node: impl FnMut(usize, u64, FieldElement)) -> Result<u64, MerkleError> {
+        if leaf.is_zero() {
+            return Err(MerkleError::ZeroLeaf);
+        }
+        if self.next_index == TREE_CAPACITY {
+            return Err(MerkleError::TreeFull);
+        }
+
+        let zeros = zero_hashes();
+        let leaf_index = self.next_index;
+        let mut index = leaf_index;
+        let mut current = leaf;
+        for (level, (zero, filled)) in zeros.iter().zip(self.filled_subtrees.iter_mut()).enumerate() {
+            on_node(level, index, current);
+            current = if index.is_multiple_of(2) {
+                *filled = current;
+                hash_pair(current, *zero)
+            } else {
+                hash_pair(*filled, current)
+            };
+            index /= 2;
+        }
+
+        self.root = current;
+        self.next_index += 1;
+        Ok(leaf_index)
+    }
+}
+
+/// Sibling path for one leaf, as `verify_merkle_inclusion(leaf, proof, indices, root)` takes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerklePath {
+    pub siblings: Vec<FieldElement>, // `proof`, leaf level first; TREE_DEPTH entries
+    pub indices: u32,                // Leaf index; bit i set means the node is a right child at level i
+}
+
+impl MerklePath {
+    /// Root as the circuit computes it, including its stop at the first zero sibling
+    pub fn compute_root(&self, leaf: FieldElement) -> FieldElement {
+        let mut current = leaf;
+        let mut index = self.indices;
+        for sibling in &self.siblings {
+            if sibling.is_zero() {
+                break;
+            }
+            current = if index.is_multiple_of(2) {
+                hash_pair(current, *sibling)
+            } else {
+                hash_pair(*sibling, current)
+            };
+            index /= 2;
+        }
+        current
+    }
+
+    pub fn verify(&self, leaf: FieldElement, root: FieldElement) -> bool {
+        self.siblings.len() == TREE_DEPTH && self.compute_root(leaf) == root
+    }
+
+    /// `proof` and `indices` members of the circuit's witness
+    pub fn to_noir_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "proof": self.siblings.iter().map(FieldElement::to_hex).collect::<Vec<_>>(),
+            "indices": self.indices,
+        })
+    }
+}
+
+/// Full tree keeping every node, for whoever mirrors the registry and hands out paths
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    frontier: MerkleFrontier,
+    levels: Vec<Vec<FieldElement>>, // levels[0] are the leaves
+}
+
+impl Default for MerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleTree {
+    pub fn new() -> Self {
+        Self { frontier: MerkleFrontier::new(), levels: vec![Vec::new(); TREE_DEPTH] }
+    }
+
+    pub fn from_leaves(leaves: impl IntoIterator<Item = FieldElement>) -> Result<Self, MerkleError> {
+        let mut tree = Self::new();
+        for leaf in leaves {
+            tree.append(leaf)?;
+        }
+        Ok(tree)
+    }
+
+    pub fn append(&mut self, leaf: FieldElement) -> Result<u64, MerkleError> {
+        let levels = &mut self.levels;
+        self.frontier.insert(leaf, |level, index, value| {
+            let nodes = &mut levels[level];
+            match nodes.get_mut(index as usize) {
+                Some(node) => *node = value,
+                None => nodes.push(value),
+            }
+        })
+    }
+
+    pub fn root(&self) -> FieldElement {
+        self.frontier.root()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.frontier.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frontier.is_empty()
+    }
+
+    pub fn leaf(&self, index: u64) -> Option<FieldElement> {
+        self.levels[0].get(usize::try_from(index).ok()?).copied()
+    }
+
+    pub fn frontier(&self) -> &MerkleFrontier {
+        &self.frontier
+    }
+
+    pub fn path(&self, index: u64) -> Result<MerklePath, MerkleError> {
+        if index >= self.len() {
+            return Err(MerkleError::LeafNotFound(index));
+        }
+        let zeros = zero_hashes();
+        let siblings = (0..TREE_DEPTH)
+            .map(|level| {
+                let sibling = ((index >> level) ^ 1) as usize;
+                self.levels[level].get(sibling).copied().unwrap_or(zeros[level])
+            })
+            .collect();
+        let indices = u32::try_from(index).expect("indices below capacity fit in u32");
+        Ok(MerklePath { siblings, indices })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u64) -> Vec<FieldElement> {
+        (1..=count).map(|value| poseidon2_hash(&[FieldElement::from_u64(value)])).collect()
+    }
+
+    #[test]
+    fn test_paths_verify_against_root() {
+        let tree = MerkleTree::from_leaves(leaves(5)).unwrap();
+        assert_eq!(tree.len(), 5);
+
+        // Root by hand: ((l1, l2), (l3, l4)), ((l5, z0), z1), then empty subtrees
+        let zeros = zero_hashes();
+        let l = leaves(5);
+        let mut expected = hash_pair(
+            hash_pair(hash_pair(l[0], l[1]), hash_pair(l[2], l[3])),
+            hash_pair(hash_pair(l[4], zeros[0]), zeros[1]),
+        );
+        for zero in &zeros[3..TREE_DEPTH] {
+            expected = hash_pair(expected, *zero);
+        }
+        assert_eq!(tree.root(), expected);
+
+        for index in 0..5 {
+            let path = tree.path(index).unwrap();
+            assert_eq!(path.siblings.len(), TREE_DEPTH);
+            assert!(path.verify(tree.leaf(index).unwrap(), tree.root()));
+            assert!(!path.verify(l[(index as usize + 1) % 5], tree.root()));
+        }
+        assert_eq!(tree.path(5), Err(MerkleError::LeafNotFound(5)));
+
+        let noir = tree.path(4).unwrap().to_noir_value();
+        assert_eq!(noir["indices"], 4);
+        assert_eq!(noir["proof"][0], zeros[0].to_hex());
+
+        // A default tree has every level allocated, like `new`
+        let mut tree = MerkleTree::default();
+        assert_eq!(tree.append(l[0]).unwrap(), 0);
+        assert_eq!(tree.leaf(0), Some(l[0]));
+        assert!(tree.path(0).unwrap().verify(l[0], tree.root()));
+    }
+
+    #[test]
+    fn test_frontier_tracks_tree_and_round_trips() {
+        let mut tree = MerkleTree::new();
+        let mut frontier = MerkleFrontier::new();
+        assert_eq!(tree.root(), zero_hashes()[TREE_DEPTH]);
+
+        for leaf in leaves(7) {
+            tree.append(leaf).unwrap();
+            frontier.append(leaf).unwrap();
+            assert_eq!(frontier.root(), tree.root());
+        }
+
+        // A holder restores the frontier and keeps following new registrations
+        let json = serde_json::to_string(&frontier).unwrap();
+        let mut restored: MerkleFrontier = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, frontier);
+        let next = poseidon2_hash(&[FieldElement::from_u64(8)]);
+        assert_eq!(restored.append(next).unwrap(), 7);
+        tree.append(next).unwrap();
+        assert_eq!(restored.root(), tree.root());
+
+        assert_eq!(frontier.append(FieldElement::ZERO), Err(MerkleError::ZeroLeaf));
+        let truncated = json.replacen(&format!("\"{}\",", frontier.filled_subtrees[0]), "", 1);
+        assert!(serde_json::from_str::<MerkleFrontier>(&truncated).is_err());
+
+        // A corrupted root is caught on load rather than reported until the next append
+        let corrupted = json.replace(&frontier.root().to_string(), &next.to_string());
+        assert!(serde_json::from_str::<MerkleFrontier>(&corrupted).unwrap_err().to_string().contains("root does not match"));
+        let empty = serde_json::to_string(&MerkleFrontier::new()).unwrap();
+        let corrupted = empty.replace(&zero_hashes()[TREE_DEPTH].to_string(), &next.to_string());
+        assert!(serde_json::from_str::<MerkleFrontier>(&corrupted).is_err());
+    }
+}
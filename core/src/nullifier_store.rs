@@ -0,0 +1,555 @@
+// Nullifier Store
+// Remembers spent nullifiers so a verifier accepts each proof once, with in-memory, file and IndexedDB backends
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NullifierStoreError {
+    #[error("Nullifier file I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corrupt nullifier record: {0}")]
+    Corrupt(String),
+    #[error("IndexedDB request failed: {0}")]
+    IndexedDb(String),
+    #[error("Another writer stored the same nullifier first")]
+    Conflict,
+}
+
+/// How long a spent nullifier blocks replays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierPolicy {
+    pub epoch: u64,              // Nullifiers only collide within one epoch; bump it to start a new round
+    pub ttl_millis: Option<u64>, // Forget a nullifier this long after first use; None keeps it for the epoch
+}
+
+impl NullifierPolicy {
+    pub fn record(&self, nullifier: String, now_millis: u64) -> NullifierRecord {
+        NullifierRecord {
+            nullifier,
+            epoch: self.epoch,
+            first_seen: now_millis,
+            expires_at: self.ttl_millis.map(|ttl| now_millis.saturating_add(ttl)),
+        }
+    }
+}
+
+/// One spent nullifier, as exported and imported between verifiers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierRecord {
+    pub nullifier: String,       // Canonical 0x hex of the field element
+    pub epoch: u64,
+    pub first_seen: u64,         // Unix millis
+    pub expires_at: Option<u64>, // Unix millis; None lives until its epoch is pruned
+}
+
+impl NullifierRecord {
+    pub fn is_live(&self, now_millis: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now_millis)
+    }
+
+    fn key(&self) -> (u64, String) {
+        (self.epoch, self.nullifier.clone())
+    }
+
+    /// Whether this record blocks replays for at least as long as `other`
+    fn outlives(&self, other: &NullifierRecord) -> bool {
+        match (self.expires_at, other.expires_at) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(mine), Some(theirs)) => mine >= theirs,
+        }
+    }
+}
+
+/// Backend for spent nullifiers. `check_and_insert` must be atomic: of two concurrent calls for the
+/// same nullifier, exactly one may return true.
+pub trait NullifierStore: Send + Sync {
+    /// Record the nullifier unless a live entry for the same epoch exists; true if it was fresh
+    fn check_and_insert(&self, record: NullifierRecord, now_millis: u64) -> Result<bool, NullifierStoreError>;
+
+    /// Drop expired entries and entries from epochs before `current_epoch`; returns how many went
+    fn prune(&self, current_epoch: u64, now_millis: u64) -> Result<usize, NullifierStoreError>;
+
+    fn export(&self) -> Result<Vec<NullifierRecord>, NullifierStoreError>;
+
+    /// Merge records, e.g. another verifier's export; of two entries for one nullifier the longer-lived wins
+    fn import(&self, records: Vec<NullifierRecord>) -> Result<(), NullifierStoreError>;
+}
+
+type RecordMap = HashMap<(u64, String), NullifierRecord>;
+
+fn insert_if_fresh(records: &mut RecordMap, record: NullifierRecord, now_millis: u64) -> bool {
+    if records.get(&record.key()).is_some_and(|existing| existing.is_live(now_millis)) {
+        return false;
+    }
+    records.insert(record.key(), record);
+    true
+}
+
+fn merge(records: &mut RecordMap, record: NullifierRecord) {
+    match records.get(&record.key()) {
+        Some(existing) if existing.outlives(&record) => {}
+        _ => {
+            records.insert(record.key(), record);
+        }
+    }
+}
+
+fn prune_map(records: &mut RecordMap, current_epoch: u64, now_millis: u64) -> usize {
+    let before = records.len();
+    records.retain(|_, record| record.epoch >= current_epoch && record.is_live(now_millis));
+    before - records.len()
+}
+
+/// Process-local store; the default, and the cache behind the IndexedDB backend
+#[derive(Debug, Default)]
+pub struct MemoryNullifierStore {
+    records: Mutex<RecordMap>,
+}
+
+impl MemoryNullifierStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, RecordMap> {
+        // A panic mid-update leaves the map consistent, so a poisoned lock is still usable
+        self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl NullifierStore for MemoryNullifierStore {
+    fn check_and_insert(&self, record: NullifierRecord, now_millis: u64) -> Result<bool, NullifierStoreError> {
+        Ok(insert_if_fresh(&mut self.records(), record, now_millis))
+    }
+
+    fn prune(&self, current_epoch: u64, now_millis: u64) -> Result<usize, NullifierStoreError> {
+        Ok(prune_map(&mut self.records(), current_epoch, now_millis))
+    }
+
+    fn export(&self) -> Result<Vec<NullifierRecord>, NullifierStoreError> {
+        let mut records: Vec<NullifierRecord> = self.records().values().cloned().collect();
+        records.sort_by(|a, b| (a.first_seen, &a.nullifier).cmp(&(b.first_seen, &b.nullifier)));
+        Ok(records)
+    }
+
+    fn import(&self, records: Vec<NullifierRecord>) -> Result<(), NullifierStoreError> {
+        let mut map = self.records();
+        for record in records {
+            merge(&mut map, record);
+        }
+        Ok(())
+    }
+}
+
+/// JSON-lines file for native verifiers. Every operation holds an exclusive lock on `<path>.lock`,
+/// so several processes may share one file; inserts append, prune and import rewrite via rename.
+#[derive(Debug, Clone)]
+pub struct FileNullifierStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl FileNullifierStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NullifierStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let store = Self { path, lock_path: lock_path.into() };
+        // Fail early on an unreadable or corrupt file rather than at the first verification
+        let _lock = store.lock()?;
+        store.read()?;
+        Ok(store)
+    }
+
+    fn lock(&self) -> Result<File, NullifierStoreError> {
+        let lock = OpenOptions::new().create(true).truncate(false).write(true).open(&self.lock_path)?;
+        lock.lock()?;
+        Ok(lock) // Released when dropped
+    }
+
+    fn read(&self) -> Result<RecordMap, NullifierStoreError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(RecordMap::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut records = RecordMap::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: NullifierRecord = serde_json::from_str(&line)
+                .map_err(|error| NullifierStoreError::Corrupt(format!("line {}: {}", number + 1, error)))?;
+            // Later lines re-insert a nullifier after its earlier entry expired
+            records.insert(record.key(), record);
+        }
+        Ok(records)
+    }
+
+    fn append(&self, record: &NullifierRecord) -> Result<(), NullifierStoreError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", record_line(record))?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn rewrite(&self, records: &RecordMap) -> Result<(), NullifierStoreError> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let mut file = File::create(&temp_path)?;
+        for record in records.values() {
+            writeln!(file, "{}", record_line(record))?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+fn record_line(record: &NullifierRecord) -> String {
+    serde_json::to_string(record).expect("nullifier records always serialize")
+}
+
+impl NullifierStore for FileNullifierStore {
+    fn check_and_insert(&self, record: NullifierRecord, now_millis: u64) -> Result<bool, NullifierStoreError> {
+        let _lock = self.lock()?;
+        let mut records = self.read()?;
+        if !insert_if_fresh(&mut records, record.clone(), now_millis) {
+            return Ok(false);
+        }
+        self.append(&record)?;
+        Ok(true)
+    }
+
+    fn prune(&self, current_epoch: u64, now_millis: u64) -> Result<usize, NullifierStoreError> {
+        let _lock = self.lock()?;
+        let mut records = self.read()?;
+        let removed = prune_map(&mut records, current_epoch, now_millis);
+        if removed > 0 {
+            self.rewrite(&records)?;
+        }
+        Ok(removed)
+    }
+
+    fn export(&self) -> Result<Vec<NullifierRecord>, NullifierStoreError> {
+        let _lock = self.lock()?;
+        let store = MemoryNullifierStore { records: Mutex::new(self.read()?) };
+        store.export()
+    }
+
+    fn import(&self, records: Vec<NullifierRecord>) -> Result<(), NullifierStoreError> {
+        let _lock = self.lock()?;
+        let mut map = self.read()?;
+        for record in records {
+            merge(&mut map, record);
+        }
+        self.rewrite(&map)
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub use indexed_db::IndexedDbNullifierStore;
+
+#[cfg(feature = "wasm")]
+mod indexed_db {
+    use super::*;
+    use crate::log_warn;
+    use crate::logging::Field;
+    use std::cell::RefCell;
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{IdbDatabase, IdbFactory, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+    const OBJECT_STORE: &str = "nullifiers";
+    const SCHEMA_VERSION: u32 = 1;
+
+    thread_local! {
+        // One connection per database, so write transactions run in the order they were issued
+        static CONNECTIONS: RefCell<HashMap<String, IdbDatabase>> = RefCell::new(HashMap::new());
+        // Per database, one promise per write transaction not yet awaited by `committed`
+        static PENDING: RefCell<HashMap<String, Vec<js_sys::Promise>>> = RefCell::new(HashMap::new());
+    }
+
+    /// Browser verifier store. Lookups go to a cache loaded when the database is opened, which keeps
+    /// `check_and_insert` synchronous and atomic within one page; each change is written through,
+    /// and `committed` reports whether those writes reached the database.
+    ///
+    /// Tabs sharing a database are not atomic with each other: each checks its own cache, and sees
+    /// another tab's inserts only when reopened. A new nullifier is written with `add`, so when two
+    /// tabs accept the same proof the later commit fails with `Conflict`; a nullifier whose earlier
+    /// entry expired is overwritten and can still be accepted once per tab.
+    #[derive(Debug)]
+    pub struct IndexedDbNullifierStore {
+        database: String,
+        cache: MemoryNullifierStore,
+    }
+
+    impl IndexedDbNullifierStore {
+        pub async fn open(database: &str) -> Result<Self, NullifierStoreError> {
+            let connection = open_connection(database).await?;
+            let transaction = connection
+                .transaction_with_str_and_mode(OBJECT_STORE, IdbTransactionMode::Readonly)
+                .map_err(js_error)?;
+            let request = transaction.object_store(OBJECT_STORE).and_then(|store| store.get_all()).map_err(js_error)?;
+            let values: js_sys::Array = request_result(&request).await?.unchecked_into();
+
+            let cache = MemoryNullifierStore::new();
+            let records = values
+                .iter()
+                .map(|value| {
+                    let json = value.as_string().unwrap_or_default();
+                    serde_json::from_str(&json).map_err(|error| NullifierStoreError::Corrupt(error.to_string()))
+                })
+                .collect::<Result<Vec<NullifierRecord>, _>>()?;
+            cache.import(records)?;
+
+            CONNECTIONS.with(|connections| connections.borrow_mut().insert(database.to_string(), connection));
+            Ok(Self { database: database.to_string(), cache })
+        }
+
+        /// Wait until every write issued so far has committed. The first aborted one fails this
+        /// with its reason; its records stay in this page's cache.
+        pub async fn committed(&self) -> Result<(), NullifierStoreError> {
+            let pending = PENDING.with(|pending| pending.borrow_mut().remove(&self.database).unwrap_or_default());
+            let mut result = Ok(());
+            for completion in pending {
+                if let Err(error) = JsFuture::from(completion).await {
+                    result = result.and(Err(write_error(error)));
+                }
+            }
+            result
+        }
+
+        /// Queue one readwrite transaction that deletes `removed`, stores `written` and adds
+        /// `added`, which must not be stored yet; `committed` waits for it
+        fn write_through(
+            &self,
+            removed: &[NullifierRecord],
+            written: &[NullifierRecord],
+            added: &[NullifierRecord],
+        ) -> Result<(), NullifierStoreError> {
+            let transaction = CONNECTIONS
+                .with(|connections| {
+                    let connections = connections.borrow();
+                    let connection = connections.get(&self.database).ok_or_else(|| JsValue::from_str("database is closed"))?;
+                    connection.transaction_with_str_and_mode(OBJECT_STORE, IdbTransactionMode::Readwrite)
+                })
+                .map_err(js_error)?;
+            let store = transaction.object_store(OBJECT_STORE).map_err(js_error)?;
+            for record in removed {
+                store.delete(&record_key(record)).map_err(js_error)?;
+            }
+            for record in written {
+                store.put_with_key(&record_line(record).into(), &record_key(record)).map_err(js_error)?;
+            }
+            for record in added {
+                store.add_with_key(&record_line(record).into(), &record_key(record)).map_err(js_error)?;
+            }
+
+            let database = self.database.clone();
+            let completion = js_sys::Promise::new(&mut |resolve, reject| {
+                transaction.set_oncomplete(Some(
+                    Closure::once_into_js(move || {
+                        let _ = resolve.call0(&JsValue::NULL);
+                    })
+                    .unchecked_ref(),
+                ));
+                let failed = transaction.clone();
+                let database = database.clone();
+                transaction.set_onabort(Some(
+                    Closure::once_into_js(move || {
+                        let error = failed.error();
+                        log_warn!(
+                            "Nullifier write to IndexedDB was aborted",
+                            Field::public("database", &database),
+                            Field::public("reason", error.as_ref().map(|error| error.message()).unwrap_or_default())
+                        );
+                        let _ = reject.call1(&JsValue::NULL, &error.map(JsValue::from).unwrap_or(JsValue::UNDEFINED));
+                    })
+                    .unchecked_ref(),
+                ));
+            });
+            PENDING.with(|pending| pending.borrow_mut().entry(database).or_default().push(completion));
+            Ok(())
+        }
+    }
+
+    impl NullifierStore for IndexedDbNullifierStore {
+        fn check_and_insert(&self, record: NullifierRecord, now_millis: u64) -> Result<bool, NullifierStoreError> {
+            let replaces_expired = self.cache.records().contains_key(&record.key());
+            if !self.cache.check_and_insert(record.clone(), now_millis)? {
+                return Ok(false);
+            }
+            if replaces_expired {
+                self.write_through(&[], &[record], &[])?;
+            } else {
+                self.write_through(&[], &[], &[record])?;
+            }
+            Ok(true)
+        }
+
+        fn prune(&self, current_epoch: u64, now_millis: u64) -> Result<usize, NullifierStoreError> {
+            let before = self.cache.export()?;
+            let removed = self.cache.prune(current_epoch, now_millis)?;
+            if removed > 0 {
+                let stale: Vec<NullifierRecord> = before
+                    .into_iter()
+                    .filter(|record| record.epoch < current_epoch || !record.is_live(now_millis))
+                    .collect();
+                self.write_through(&stale, &[], &[])?;
+            }
+            Ok(removed)
+        }
+
+        fn export(&self) -> Result<Vec<NullifierRecord>, NullifierStoreError> {
+            self.cache.export()
+        }
+
+        fn import(&self, records: Vec<NullifierRecord>) -> Result<(), NullifierStoreError> {
+            self.cache.import(records)?;
+            self.write_through(&[], &self.cache.export()?, &[])
+        }
+    }
+
+    fn record_key(record: &NullifierRecord) -> JsValue {
+        format!("{}:{}", record.epoch, record.nullifier).into()
+    }
+
+    async fn open_connection(database: &str) -> Result<IdbDatabase, NullifierStoreError> {
+        let factory: IdbFactory = js_sys::Reflect::get(&js_sys::global(), &"indexedDB".into())
+            .and_then(|factory| factory.dyn_into())
+            .map_err(|_| NullifierStoreError::IndexedDb("IndexedDB is not available".to_string()))?;
+        let request: IdbOpenDbRequest = factory.open_with_u32(database, SCHEMA_VERSION).map_err(js_error)?;
+
+        let upgrading = request.clone();
+        request.set_onupgradeneeded(Some(
+            Closure::once_into_js(move || {
+                if let Ok(connection) = upgrading.result().and_then(|result| result.dyn_into::<IdbDatabase>()) {
+                    if !connection.object_store_names().contains(OBJECT_STORE) {
+                        let _ = connection.create_object_store(OBJECT_STORE);
+                    }
+                }
+            })
+            .unchecked_ref(),
+        ));
+
+        request_result(&request).await?.dyn_into().map_err(js_error)
+    }
+
+    /// Resolve once the request succeeds, with its result
+    async fn request_result(request: &IdbRequest) -> Result<JsValue, NullifierStoreError> {
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            let succeeded = request.clone();
+            request.set_onsuccess(Some(
+                Closure::once_into_js(move || {
+                    let _ = resolve.call1(&JsValue::NULL, &succeeded.result().unwrap_or(JsValue::UNDEFINED));
+                })
+                .unchecked_ref(),
+            ));
+            let failed = request.clone();
+            request.set_onerror(Some(
+                Closure::once_into_js(move || {
+                    let error = failed.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
+                    let _ = reject.call1(&JsValue::NULL, &error);
+                })
+                .unchecked_ref(),
+            ));
+        });
+        JsFuture::from(promise).await.map_err(js_error)
+    }
+
+    /// Reason an aborted write transaction gives; a `ConstraintError` means the key was added elsewhere
+    fn write_error(error: JsValue) -> NullifierStoreError {
+        match js_sys::Reflect::get(&error, &"name".into()).ok().and_then(|name| name.as_string()) {
+            Some(name) if name == "ConstraintError" => NullifierStoreError::Conflict,
+            _ => js_error(error),
+        }
+    }
+
+    fn js_error(error: JsValue) -> NullifierStoreError {
+        let message = js_sys::Reflect::get(&error, &"message".into())
+            .ok()
+            .and_then(|message| message.as_string())
+            .or_else(|| error.as_string())
+            .unwrap_or_else(|| format!("{:?}", error));
+        NullifierStoreError::IndexedDb(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60 * 1000;
+
+    #[test]
+    fn test_memory_store_rejects_replays_within_epoch_and_ttl() {
+        let store = MemoryNullifierStore::new();
+        let policy = NullifierPolicy { epoch: 1, ttl_millis: Some(HOUR) };
+
+        assert!(store.check_and_insert(policy.record("0x01".into(), 0), 0).unwrap());
+        assert!(!store.check_and_insert(policy.record("0x01".into(), 10), 10).unwrap());
+
+        // A new epoch or an expired entry admits the nullifier again
+        let next_epoch = NullifierPolicy { epoch: 2, ..policy };
+        assert!(store.check_and_insert(next_epoch.record("0x01".into(), 10), 10).unwrap());
+        assert!(store.check_and_insert(policy.record("0x01".into(), HOUR), HOUR).unwrap());
+
+        assert!(store.check_and_insert(policy.record("0x02".into(), 0), 0).unwrap());
+        assert_eq!(store.prune(2, HOUR + 5).unwrap(), 2);
+        assert_eq!(store.export().unwrap(), vec![next_epoch.record("0x01".into(), 10)]);
+    }
+
+    #[test]
+    fn test_export_import_keeps_longer_lived_entry() {
+        let source = MemoryNullifierStore::new();
+        let forever = NullifierPolicy::default();
+        source.check_and_insert(forever.record("0x01".into(), 5), 5).unwrap();
+
+        let target = MemoryNullifierStore::new();
+        let short = NullifierPolicy { epoch: 0, ttl_millis: Some(10) };
+        target.check_and_insert(short.record("0x01".into(), 0), 0).unwrap();
+        target.import(source.export().unwrap()).unwrap();
+
+        assert!(!target.check_and_insert(short.record("0x01".into(), HOUR), HOUR).unwrap());
+        assert_eq!(target.export().unwrap()[0].expires_at, None);
+    }
+
+    #[test]
+    fn test_file_store_is_shared_between_handles() {
+        let path = std::env::temp_dir().join(format!("patriconid-nullifiers-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let policy = NullifierPolicy { epoch: 0, ttl_millis: Some(HOUR) };
+
+        let first = FileNullifierStore::open(&path).unwrap();
+        let second = FileNullifierStore::open(&path).unwrap();
+        assert!(first.check_and_insert(policy.record("0x01".into(), 0), 0).unwrap());
+        assert!(!second.check_and_insert(policy.record("0x01".into(), 1), 1).unwrap());
+
+        // Exactly one of several concurrent verifiers wins the same nullifier
+        let winners = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| FileNullifierStore::open(&path).unwrap().check_and_insert(policy.record("0x02".into(), 2), 2).unwrap()))
+                .collect();
+            handles.into_iter().filter_map(|handle| handle.join().unwrap().then_some(())).count()
+        });
+        assert_eq!(winners, 1);
+
+        assert_eq!(second.prune(0, HOUR + 2).unwrap(), 2);
+        assert!(first.export().unwrap().is_empty());
+
+        std::fs::write(&path, "not json\n").unwrap();
+        assert!(matches!(FileNullifierStore::open(&path), Err(NullifierStoreError::Corrupt(_))));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(first.lock_path);
+    }
+}
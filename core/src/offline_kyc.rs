@@ -0,0 +1,487 @@
+// Typed Offline e-KYC Document Model
+// Maps OfflinePaperlessKyc (UidData > Poi / Poa / Pht + Signature) and legacy PrintLetterBarcodeData
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use crate::aadhaar_xml_parser::{strip_whitespace, AadhaarAddress, AadhaarVerificationError, VerifiedAadhaarData};
+use crate::birth_date::BirthDate;
+use crate::freshness::ReferenceId;
+use crate::sensitive::Sensitive;
+use crate::xml_c14n::{XmlDocument, XmlElement, XMLDSIG_NAMESPACE};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum KycSchemaError {
+    #[error("Unrecognised e-KYC document root <{0}>")]
+    UnknownRoot(String),
+    #[error("Unexpected element <{element}> in <{parent}>")]
+    UnexpectedElement { parent: String, element: String },
+    #[error("Duplicate element <{element}> in <{parent}>")]
+    DuplicateElement { parent: String, element: String },
+    #[error("Missing element <{element}> in <{parent}>")]
+    MissingElement { parent: String, element: String },
+    #[error("Missing attribute {attribute} on <{element}>")]
+    MissingAttribute { element: String, attribute: String },
+}
+
+/// Which UIDAI layout a document uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KycSchemaVersion {
+    /// Signed offline e-KYC ZIP (2018 onwards)
+    OfflinePaperlessKyc,
+    /// Unsigned attributes-only XML from the old Aadhaar letter QR code
+    PrintLetterBarcodeData,
+}
+
+impl KycSchemaVersion {
+    pub fn detect(root: &XmlElement) -> Result<Self, KycSchemaError> {
+        if root.is("", "OfflinePaperlessKyc") {
+            Ok(KycSchemaVersion::OfflinePaperlessKyc)
+        } else if root.is("", "PrintLetterBarcodeData") {
+            Ok(KycSchemaVersion::PrintLetterBarcodeData)
+        } else {
+            Err(KycSchemaError::UnknownRoot(root.qualified_name()))
+        }
+    }
+}
+
+/// Parsed document. Personal data sits in `Sensitive` fields and none of the model is `Serialize`,
+/// so it leaves only through `into_verified_data`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AadhaarKycDocument {
+    OfflinePaperlessKyc(OfflinePaperlessKyc),
+    PrintLetterBarcodeData(PrintLetterBarcodeData),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflinePaperlessKyc {
+    pub reference_id: String, // referenceId: last 4 Aadhaar digits + YYYYMMDDHHMMSSsss
+    pub uid_data: UidData,
+    pub signature: Option<SignatureBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UidData {
+    pub poi: Poi,
+    pub poa: Sensitive<Poa>,
+    pub pht: Sensitive<Option<String>>, // Base64 JPEG
+}
+
+/// Proof of identity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poi {
+    pub name: Sensitive<String>,
+    pub dob: Sensitive<String>, // DD-MM-YYYY
+    pub gender: String,
+    pub mobile_hash: Sensitive<Option<String>>, // m
+    pub email_hash: Sensitive<Option<String>>,  // e
+}
+
+/// Proof of address
+#[derive(Debug, Clone, Default, PartialEq, Zeroize)]
+pub struct Poa {
+    pub careof: Option<String>,
+    pub country: Option<String>,
+    pub dist: String,
+    pub house: Option<String>,
+    pub landmark: Option<String>,
+    pub loc: Option<String>,
+    pub pc: String,
+    pub po: Option<String>,
+    pub state: String,
+    pub street: Option<String>,
+    pub subdist: Option<String>,
+    pub vtc: String,
+}
+
+/// XMLDSig block as it appears in the document; verification works on the raw tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureBlock {
+    #[serde(rename = "SignatureMethod")]
+    pub signature_method: String,
+    #[serde(rename = "DigestValue")]
+    pub digest_value: String,
+    #[serde(rename = "SignatureValue")]
+    pub signature_value: String,
+    #[serde(rename = "X509Certificate")]
+    pub certificate: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintLetterBarcodeData {
+    pub uid: Sensitive<String>, // The full Aadhaar number; only its last 4 digits are kept
+    pub name: Sensitive<String>,
+    pub gender: String,
+    pub yob: Sensitive<Option<String>>,
+    pub dob: Sensitive<Option<String>>,
+    pub co: Sensitive<Option<String>>,
+    pub house: Sensitive<Option<String>>,
+    pub street: Sensitive<Option<String>>,
+    pub lm: Sensitive<Option<String>>,
+    pub loc: Sensitive<Option<String>>,
+    pub vtc: Sensitive<String>,
+    pub po: Sensitive<Option<String>>,
+    pub dist: Sensitive<String>,
+    pub subdist: Sensitive<Option<String>>,
+    pub state: Sensitive<String>,
+    pub pc: Sensitive<String>,
+}
+
+impl AadhaarKycDocument {
+    pub fn parse(xml_content: &str) -> Result<Self, AadhaarVerificationError> {
+        let document = XmlDocument::parse(xml_content)?;
+        Ok(Self::from_document(&document)?)
+    }
+
+    pub fn from_document(document: &XmlDocument) -> Result<Self, KycSchemaError> {
+        let root = &document.root;
+        match KycSchemaVersion::detect(root)? {
+            KycSchemaVersion::OfflinePaperlessKyc => Ok(AadhaarKycDocument::OfflinePaperlessKyc(
+                OfflinePaperlessKyc::from_element(root)?,
+            )),
+            KycSchemaVersion::PrintLetterBarcodeData => Ok(AadhaarKycDocument::PrintLetterBarcodeData(
+                PrintLetterBarcodeData::from_element(root)?,
+            )),
+        }
+    }
+
+    pub fn schema_version(&self) -> KycSchemaVersion {
+        match self {
+            AadhaarKycDocument::OfflinePaperlessKyc(_) => KycSchemaVersion::OfflinePaperlessKyc,
+            AadhaarKycDocument::PrintLetterBarcodeData(_) => KycSchemaVersion::PrintLetterBarcodeData,
+        }
+    }
+
+    /// Flatten into the verification result; signature fields are left for the caller to set
+    pub fn into_verified_data(self) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+        match self {
+            AadhaarKycDocument::OfflinePaperlessKyc(kyc) => kyc.into_verified_data(),
+            AadhaarKycDocument::PrintLetterBarcodeData(letter) => letter.into_verified_data(),
+        }
+    }
+}
+
+impl OfflinePaperlessKyc {
+    fn from_element(root: &XmlElement) -> Result<Self, KycSchemaError> {
+        let [uid_data, signature] = unique_children(root, [("", "UidData"), (XMLDSIG_NAMESPACE, "Signature")])?;
+        let uid_data = uid_data.ok_or_else(|| missing_element(root, "UidData"))?;
+
+        Ok(Self {
+            reference_id: required_attribute(root, "referenceId")?,
+            uid_data: UidData::from_element(uid_data)?,
+            signature: signature.map(SignatureBlock::from_element).transpose()?,
+        })
+    }
+
+    fn into_verified_data(self) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+        let reference = ReferenceId::parse(&self.reference_id)?;
+        let UidData { poi, poa, pht } = self.uid_data;
+        let poa = poa.into_inner();
+
+        Ok(VerifiedAadhaarData {
+            date_of_birth: BirthDate::parse(poi.dob.expose())?.to_string().into(),
+            name: poi.name,
+            gender: poi.gender,
+            address: AadhaarAddress {
+                care_of: poa.careof,
+                house: poa.house,
+                street: poa.street,
+                landmark: poa.landmark,
+                locality: poa.loc,
+                vtc: poa.vtc,
+                post_office: poa.po,
+                subdist: poa.subdist,
+                district: poa.dist,
+                state: poa.state,
+                pincode: poa.pc,
+                country: poa.country.unwrap_or_else(|| "India".to_string()),
+            }.into(),
+            photo_base64: pht,
+            photo_commitment: None,
+            mobile_hash: poi.mobile_hash,
+            email_hash: poi.email_hash,
+            reference_id: self.reference_id,
+            generated_date: reference.generated_date(),
+            generated_at: Some(reference.generated_at),
+            signature_valid: false,
+            certificate_valid: false,
+            signer_certificate: None,
+            aadhaar_last_4_digits: reference.aadhaar_last_4_digits,
+        })
+    }
+}
+
+impl UidData {
+    fn from_element(uid_data: &XmlElement) -> Result<Self, KycSchemaError> {
+        let [poi, poa, pht] = unique_children(uid_data, [("", "Poi"), ("", "Poa"), ("", "Pht")])?;
+        let poi = poi.ok_or_else(|| missing_element(uid_data, "Poi"))?;
+        let poa = poa.ok_or_else(|| missing_element(uid_data, "Poa"))?;
+
+        Ok(Self {
+            poi: Poi {
+                name: required_attribute(poi, "name")?.into(),
+                dob: required_attribute(poi, "dob")?.into(),
+                gender: required_attribute(poi, "gender")?,
+                mobile_hash: optional_attribute(poi, "m").into(),
+                email_hash: optional_attribute(poi, "e").into(),
+            },
+            poa: Poa {
+                careof: optional_attribute(poa, "careof"),
+                country: optional_attribute(poa, "country"),
+                dist: required_attribute(poa, "dist")?,
+                house: optional_attribute(poa, "house"),
+                landmark: optional_attribute(poa, "landmark"),
+                loc: optional_attribute(poa, "loc"),
+                pc: required_attribute(poa, "pc")?,
+                po: optional_attribute(poa, "po"),
+                state: required_attribute(poa, "state")?,
+                street: optional_attribute(poa, "street"),
+                subdist: optional_attribute(poa, "subdist"),
+                vtc: required_attribute(poa, "vtc")?,
+            }.into(),
+            pht: pht.map(|photo| strip_whitespace(&photo.text())).filter(|photo| !photo.is_empty()).into(),
+        })
+    }
+}
+
+impl SignatureBlock {
+    fn from_element(signature: &XmlElement) -> Result<Self, KycSchemaError> {
+        let [signed_info, signature_value, key_info] = unique_children(signature, [
+            (XMLDSIG_NAMESPACE, "SignedInfo"),
+            (XMLDSIG_NAMESPACE, "SignatureValue"),
+            (XMLDSIG_NAMESPACE, "KeyInfo"),
+        ])?;
+        let signed_info = signed_info.ok_or_else(|| missing_element(signature, "SignedInfo"))?;
+        let signature_value = signature_value.ok_or_else(|| missing_element(signature, "SignatureValue"))?;
+        let signature_method = signed_info.find_child(XMLDSIG_NAMESPACE, "SignatureMethod")
+            .ok_or_else(|| missing_element(signed_info, "SignatureMethod"))?;
+        let reference = signed_info.find_child(XMLDSIG_NAMESPACE, "Reference")
+            .ok_or_else(|| missing_element(signed_info, "Reference"))?;
+        let digest_value = reference.find_child(XMLDSIG_NAMESPACE, "DigestValue")
+            .ok_or_else(|| missing_element(reference, "DigestValue"))?;
+
+        Ok(Self {
+            signature_method: required_attribute(signature_method, "Algorithm")?,
+            digest_value: strip_whitespace(&digest_value.text()),
+            signature_value: strip_whitespace(&signature_value.text()),
+            certificate: key_info
+                .and_then(|key_info| key_info.find_descendant(XMLDSIG_NAMESPACE, "X509Certificate"))
+                .map(|certificate| strip_whitespace(&certificate.text())),
+        })
+    }
+}
+
+impl PrintLetterBarcodeData {
+    fn from_element(root: &XmlElement) -> Result<Self, KycSchemaError> {
+        unique_children(root, [])?;
+
+        Ok(Self {
+            uid: required_attribute(root, "uid")?.into(),
+            name: required_attribute(root, "name")?.into(),
+            gender: required_attribute(root, "gender")?,
+            yob: optional_attribute(root, "yob").into(),
+            dob: optional_attribute(root, "dob").into(),
+            co: optional_attribute(root, "co").into(),
+            house: optional_attribute(root, "house").into(),
+            street: optional_attribute(root, "street").into(),
+            lm: optional_attribute(root, "lm").into(),
+            loc: optional_attribute(root, "loc").into(),
+            vtc: required_attribute(root, "vtc")?.into(),
+            po: optional_attribute(root, "po").into(),
+            dist: required_attribute(root, "dist")?.into(),
+            subdist: optional_attribute(root, "subdist").into(),
+            state: required_attribute(root, "state")?.into(),
+            pc: required_attribute(root, "pc")?.into(),
+        })
+    }
+
+    fn into_verified_data(self) -> Result<VerifiedAadhaarData, AadhaarVerificationError> {
+        // Letter QR codes write DD/MM/YYYY; older letters only carry the year of birth
+        let dob = self.dob.expose().as_ref().or(self.yob.expose().as_ref())
+            .ok_or_else(|| AadhaarVerificationError::MissingDemographicData("dob".to_string()))?;
+        let date_of_birth = BirthDate::parse(dob)?.to_string();
+        let digits: Sensitive<String> = self.uid.expose().chars().filter(char::is_ascii_digit).collect::<String>().into();
+        let aadhaar_last_4_digits = digits.expose().get(digits.expose().len().saturating_sub(4)..)
+            .filter(|last_4| last_4.len() == 4)
+            .unwrap_or("****")
+            .to_string();
+
+        Ok(VerifiedAadhaarData {
+            name: self.name,
+            date_of_birth: date_of_birth.into(),
+            gender: self.gender,
+            address: AadhaarAddress {
+                care_of: self.co.into_inner(),
+                house: self.house.into_inner(),
+                street: self.street.into_inner(),
+                landmark: self.lm.into_inner(),
+                locality: self.loc.into_inner(),
+                vtc: self.vtc.into_inner(),
+                post_office: self.po.into_inner(),
+                subdist: self.subdist.into_inner(),
+                district: self.dist.into_inner(),
+                state: self.state.into_inner(),
+                pincode: self.pc.into_inner(),
+                country: "India".to_string(),
+            }.into(),
+            photo_base64: None.into(),
+            photo_commitment: None,
+            mobile_hash: None.into(),
+            email_hash: None.into(),
+            // Letter QR codes carry neither a reference id nor a generation date, and the uid is
+            // the Aadhaar number itself
+            reference_id: String::new(),
+            generated_date: String::new(),
+            generated_at: None,
+            signature_valid: false,
+            certificate_valid: false,
+            signer_certificate: None,
+            aadhaar_last_4_digits,
+        })
+    }
+}
+
+/// Look up the allowed children of `parent`, rejecting anything unknown or repeated
+fn unique_children<'a, const N: usize>(
+    parent: &'a XmlElement,
+    allowed: [(&str, &str); N],
+) -> Result<[Option<&'a XmlElement>; N], KycSchemaError> {
+    let mut found = [None; N];
+    for child in parent.child_elements() {
+        let index = allowed.iter()
+            .position(|(namespace, local_name)| child.is(namespace, local_name))
+            .ok_or_else(|| KycSchemaError::UnexpectedElement {
+                parent: parent.qualified_name(),
+                element: child.qualified_name(),
+            })?;
+        if found[index].replace(child).is_some() {
+            return Err(KycSchemaError::DuplicateElement {
+                parent: parent.qualified_name(),
+                element: child.qualified_name(),
+            });
+        }
+    }
+    Ok(found)
+}
+
+fn missing_element(parent: &XmlElement, element: &str) -> KycSchemaError {
+    KycSchemaError::MissingElement {
+        parent: parent.qualified_name(),
+        element: element.to_string(),
+    }
+}
+
+fn required_attribute(element: &XmlElement, name: &str) -> Result<String, KycSchemaError> {
+    optional_attribute(element, name).ok_or_else(|| KycSchemaError::MissingAttribute {
+        element: element.qualified_name(),
+        attribute: name.to_string(),
+    })
+}
+
+/// UIDAI writes absent values as empty attributes
+fn optional_attribute(element: &XmlElement, name: &str) -> Option<String> {
+    element.attribute(name)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFLINE_KYC_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<OfflinePaperlessKyc referenceId="234520250101120000000">
+  <UidData>
+    <Poi dob="15-08-1990" e="" gender="M" m="abcd" name="Test User"/>
+    <Poa careof="S/O Father" country="India" dist="Mumbai" house="12" landmark="" loc="Fort" pc="400001" po="GPO" state="Maharashtra" street="MG Road" subdist="" vtc="Mumbai"/>
+    <Pht>
+      /9j/4AAQ
+      SkZJRg==
+    </Pht>
+  </UidData>
+  <Signature xmlns="http://www.w3.org/2000/09/xmldsig#">
+    <SignedInfo>
+      <SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"/>
+      <Reference URI=""><DigestValue>ZGlnZXN0</DigestValue></Reference>
+    </SignedInfo>
+    <SignatureValue>c2ln
+bmF0dXJl</SignatureValue>
+  </Signature>
+</OfflinePaperlessKyc>"#;
+
+    #[test]
+    fn test_parse_offline_paperless_kyc() {
+        let document = AadhaarKycDocument::parse(OFFLINE_KYC_XML).unwrap();
+        assert_eq!(document.schema_version(), KycSchemaVersion::OfflinePaperlessKyc);
+
+        let AadhaarKycDocument::OfflinePaperlessKyc(kyc) = &document else {
+            panic!("expected OfflinePaperlessKyc");
+        };
+        assert_eq!(kyc.uid_data.poi.mobile_hash.expose().as_deref(), Some("abcd"));
+        assert_eq!(kyc.uid_data.poi.email_hash.expose(), &None);
+        assert_eq!(kyc.uid_data.poa.expose().landmark, None);
+        assert_eq!(kyc.uid_data.pht.expose().as_deref(), Some("/9j/4AAQSkZJRg=="));
+        let debug = format!("{:?}", kyc);
+        assert!(!debug.contains("Test User") && !debug.contains("15-08-1990") && !debug.contains("400001"));
+        let signature = kyc.signature.as_ref().unwrap();
+        assert_eq!(signature.signature_value, "c2lnbmF0dXJl");
+        assert_eq!(signature.digest_value, "ZGlnZXN0");
+
+        let data = document.into_verified_data().unwrap();
+        assert_eq!(data.name.expose(), "Test User");
+        assert_eq!(data.address.expose().care_of.as_deref(), Some("S/O Father"));
+        assert_eq!(data.address.expose().pincode, "400001");
+        assert_eq!(data.aadhaar_last_4_digits, "2345");
+        assert_eq!(data.generated_date, "2025-01-01");
+    }
+
+    #[test]
+    fn test_parse_legacy_print_letter_barcode() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<PrintLetterBarcodeData uid="999988887777" name="Test User" gender="F" yob="1990" co="D/O Parent" lm="" vtc="Pune" dist="Pune" state="Maharashtra" pc="411001" dob="15/08/1990"/>"#;
+
+        let document = AadhaarKycDocument::parse(xml).unwrap();
+        assert_eq!(document.schema_version(), KycSchemaVersion::PrintLetterBarcodeData);
+        assert!(!format!("{:?}", document).contains("999988887777"));
+
+        // Only the last 4 digits of the Aadhaar number survive
+        let data = document.into_verified_data().unwrap();
+        assert_eq!(data.reference_id, "");
+        assert!(!data.to_exposed_json().unwrap().contains("99998888"));
+        assert_eq!(data.date_of_birth.expose(), "15-08-1990");
+        assert_eq!(data.address.expose().care_of.as_deref(), Some("D/O Parent"));
+        assert_eq!(data.address.expose().landmark, None);
+        assert_eq!(data.aadhaar_last_4_digits, "7777");
+
+        let year_only = xml.replace(r#" dob="15/08/1990""#, "");
+        let data = AadhaarKycDocument::parse(&year_only).unwrap().into_verified_data().unwrap();
+        assert_eq!(data.date_of_birth.expose(), "1990");
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let parse = |xml: &str| AadhaarKycDocument::from_document(&XmlDocument::parse(xml).unwrap());
+
+        assert_eq!(parse("<Kyc/>"), Err(KycSchemaError::UnknownRoot("Kyc".to_string())));
+
+        let duplicated = OFFLINE_KYC_XML.replace("<Pht>", r#"<Poi dob="01-01-1980" gender="F" name="Other"/><Pht>"#);
+        assert_eq!(parse(&duplicated), Err(KycSchemaError::DuplicateElement {
+            parent: "UidData".to_string(),
+            element: "Poi".to_string(),
+        }));
+
+        let unknown = OFFLINE_KYC_XML.replace("<Pht>", "<Bio/><Pht>");
+        assert_eq!(parse(&unknown), Err(KycSchemaError::UnexpectedElement {
+            parent: "UidData".to_string(),
+            element: "Bio".to_string(),
+        }));
+
+        let missing = OFFLINE_KYC_XML.replace(r#" pc="400001""#, "");
+        assert_eq!(parse(&missing), Err(KycSchemaError::MissingAttribute {
+            element: "Poa".to_string(),
+            attribute: "pc".to_string(),
+        }));
+    }
+}
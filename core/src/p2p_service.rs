@@ -1,31 +1,310 @@
-use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use web_sys::console;
-use crate::aadhaar_xml_parser::{AadhaarXMLParser, VerifiedAadhaarData};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use crate::aadhaar_xml_parser::{AadhaarVerificationError, AadhaarXMLParser, VerifiedAadhaarData};
+use crate::birth_date::{date_from_numeric, BirthDate, BirthDateError, YearOnlyMode};
+use crate::clock::{Clock, SystemClock};
+use crate::contact_verification::{contact_commitment, contact_document_hash, ContactKind};
+use crate::logging::Field;
+use crate::field_element::{CircuitInput, FieldElement, FieldElementError};
+use crate::freshness::FreshnessPolicy;
+use crate::merkle_tree::MerkleFrontier;
+use crate::nullifier_store::{MemoryNullifierStore, NullifierPolicy, NullifierStore, NullifierStoreError};
+#[cfg(feature = "wasm")]
+use crate::nullifier_store::{IndexedDbNullifierStore, NullifierRecord};
+use crate::poseidon::poseidon2_hash;
+use crate::proof_type::{ProofSource, ProofTypeError, ProofTypeRegistry, ProofTypeSpec, PublicSignal};
+use crate::rng::{Rng, SystemRng};
+use crate::trust_store::TrustStore;
+use crate::attestation::{self, AttestationType, PasskeyRegistration};
+use crate::proof_envelope::{self, EnvelopeError, EnvelopeSize, QR_CAPACITY};
+use crate::webauthn::{self, PasskeyAssertion, RelyingParty, WebAuthnError};
+use crate::{log_info, log_warn};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
 
 // P2P Proof Service - Client-side ZK proof generation and verification
 // No backend required - everything runs locally
 
+#[derive(Debug, Error)]
+pub enum ProofServiceError {
+    #[error("Invalid {input}: {source}")]
+    InvalidInput { input: &'static str, source: serde_json::Error },
+    #[error("Invalid proof request: {0}")]
+    InvalidProofRequest(#[source] ProofTypeError),
+    #[error("Unsupported contact kind: {0}")]
+    UnsupportedContactKind(String),
+    #[error("Date parsing failed: {0}")]
+    InvalidDate(#[from] BirthDateError),
+    #[error("Invalid {input}: {source}")]
+    InvalidFieldElement { input: &'static str, source: FieldElementError },
+    #[error("Proof request expired at {0}")]
+    RequestExpired(u64),
+    #[error("Invalid proof envelope: {0}")]
+    InvalidEnvelope(#[from] EnvelopeError),
+    #[error("Serialization error: {0}")]
+    Serialization(serde_json::Error),
+    #[error("Unknown proof type: {0}")]
+    UnknownProofType(u8),
+    #[error("Circuit execution failed: {0}")]
+    CircuitFailed(String),
+    #[error("Passkey signature failed: {0}")]
+    SignatureFailed(String),
+    #[error("Passkey verification failed: {0}")]
+    Passkey(#[from] WebAuthnError),
+    #[error("No passkey is bound to commitment {0}")]
+    PasskeyNotBound(String),
+    #[error("Passkey binding failed: {0}")]
+    PasskeyBinding(String),
+    #[error("Nullifier has already been used")]
+    NullifierReused,
+    #[error("Nullifier store failed: {0}")]
+    NullifierStore(#[from] NullifierStoreError),
+    #[error("Unsupported channel: {0}")]
+    UnsupportedChannel(String),
+    #[error("Proof delivery failed: {0}")]
+    TransportFailed(String),
+    #[error("Proof takes {size} bytes, over the {limit} the channel carries")]
+    ProofTooLarge { size: usize, limit: usize },
+    #[error("Parser initialization failed: {0}")]
+    ParserInitialization(#[source] AadhaarVerificationError),
+    #[error("Aadhaar verification failed: {0}")]
+    Aadhaar(#[from] AadhaarVerificationError),
+    #[error("UIDAI signature verification failed")]
+    UidaiSignatureInvalid,
+}
+
+/// Failure family a `ProofServiceError` belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCategory {
+    Parse,
+    Circuit,
+    Signature,
+    Nullifier,
+    Transport,
+    Aadhaar,
+}
+
+/// Machine-readable part of an error, attached to JS errors as `details`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub category: ErrorCategory,
+    pub numeric_code: u32,
+    pub source: Option<String>, // Message of the nested error, e.g. the AadhaarVerificationError
+}
+
+impl ProofServiceError {
+    /// Stable string code; frontends should match on this, never on the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProofServiceError::InvalidInput { .. } => "INVALID_INPUT",
+            ProofServiceError::InvalidProofRequest(_) => "INVALID_PROOF_REQUEST",
+            ProofServiceError::UnsupportedContactKind(_) => "UNSUPPORTED_CONTACT_KIND",
+            ProofServiceError::InvalidDate(_) => "INVALID_DATE",
+            ProofServiceError::InvalidFieldElement { .. } => "INVALID_FIELD_ELEMENT",
+            ProofServiceError::RequestExpired(_) => "REQUEST_EXPIRED",
+            ProofServiceError::InvalidEnvelope(_) => "INVALID_PROOF_ENVELOPE",
+            ProofServiceError::Serialization(_) => "SERIALIZATION_FAILED",
+            ProofServiceError::UnknownProofType(_) => "UNKNOWN_PROOF_TYPE",
+            ProofServiceError::CircuitFailed(_) => "CIRCUIT_FAILED",
+            ProofServiceError::SignatureFailed(_) => "SIGNATURE_FAILED",
+            ProofServiceError::Passkey(_) => "PASSKEY_VERIFICATION_FAILED",
+            ProofServiceError::PasskeyNotBound(_) => "PASSKEY_NOT_BOUND",
+            ProofServiceError::PasskeyBinding(_) => "PASSKEY_BINDING_FAILED",
+            ProofServiceError::NullifierReused => "NULLIFIER_REUSED",
+            ProofServiceError::NullifierStore(_) => "NULLIFIER_STORE_FAILED",
+            ProofServiceError::UnsupportedChannel(_) => "UNSUPPORTED_CHANNEL",
+            ProofServiceError::TransportFailed(_) => "TRANSPORT_FAILED",
+            ProofServiceError::ProofTooLarge { .. } => "PROOF_TOO_LARGE",
+            ProofServiceError::ParserInitialization(_) => "AADHAAR_PARSER_INIT_FAILED",
+            ProofServiceError::Aadhaar(_) => "AADHAAR_VERIFICATION_FAILED",
+            ProofServiceError::UidaiSignatureInvalid => "UIDAI_SIGNATURE_INVALID",
+        }
+    }
+
+    /// Stable numeric code; the thousands digit is the category
+    pub fn numeric_code(&self) -> u32 {
+        match self {
+            ProofServiceError::InvalidInput { .. } => 1001,
+            ProofServiceError::UnsupportedContactKind(_) => 1002,
+            ProofServiceError::InvalidDate(_) => 1003,
+            ProofServiceError::Serialization(_) => 1004,
+            ProofServiceError::InvalidProofRequest(_) => 1005,
+            ProofServiceError::InvalidFieldElement { .. } => 1006,
+            ProofServiceError::RequestExpired(_) => 1007,
+            ProofServiceError::InvalidEnvelope(_) => 1008,
+            ProofServiceError::CircuitFailed(_) => 2001,
+            ProofServiceError::UnknownProofType(_) => 2002,
+            ProofServiceError::SignatureFailed(_) => 3001,
+            ProofServiceError::Passkey(_) => 3002,
+            ProofServiceError::PasskeyNotBound(_) => 3003,
+            ProofServiceError::PasskeyBinding(_) => 3004,
+            ProofServiceError::NullifierReused => 4001,
+            ProofServiceError::NullifierStore(_) => 4002,
+            ProofServiceError::UnsupportedChannel(_) => 5001,
+            ProofServiceError::TransportFailed(_) => 5002,
+            ProofServiceError::ProofTooLarge { .. } => 5003,
+            ProofServiceError::ParserInitialization(_) => 6001,
+            ProofServiceError::Aadhaar(_) => 6002,
+            ProofServiceError::UidaiSignatureInvalid => 6003,
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self.numeric_code() / 1000 {
+            1 => ErrorCategory::Parse,
+            2 => ErrorCategory::Circuit,
+            3 => ErrorCategory::Signature,
+            4 => ErrorCategory::Nullifier,
+            5 => ErrorCategory::Transport,
+            _ => ErrorCategory::Aadhaar,
+        }
+    }
+
+    pub fn details(&self) -> ErrorDetails {
+        ErrorDetails {
+            category: self.category(),
+            numeric_code: self.numeric_code(),
+            source: std::error::Error::source(self).map(|source| source.to_string()),
+        }
+    }
+}
+
+impl From<ProofTypeError> for ProofServiceError {
+    fn from(error: ProofTypeError) -> Self {
+        match error {
+            ProofTypeError::UnknownProofType(id) => ProofServiceError::UnknownProofType(id),
+            other => ProofServiceError::InvalidProofRequest(other),
+        }
+    }
+}
+
+/// A JS `Error` whose message is the display text, with `code` and `details` properties
+#[cfg(feature = "wasm")]
+impl From<ProofServiceError> for JsValue {
+    fn from(error: ProofServiceError) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        let details = serde_json::to_string(&error.details())
+            .ok()
+            .and_then(|json| js_sys::JSON::parse(&json).ok())
+            .unwrap_or(JsValue::NULL);
+        // Setting properties on a fresh Error object cannot fail
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code().into());
+        let _ = js_sys::Reflect::set(&js_error, &"details".into(), &details);
+        js_error.into()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct P2PProofRequest {
-    pub proof_type: u8, // 1=age, 2=residency, 3=nationality, 4=credit, 5=composite
-    pub challenge: ProofChallenge,
-    pub verifier_address: String,
-    pub nonce: String,
+    pub proof_type: u8, // Id in the service's ProofTypeRegistry; see ProofType for the built-in ones
+    #[serde(alias = "challenge")] // Requests made before the split; a secret in them is ignored
+    pub requirements: ProofRequirements,
+    pub verifier_address: String, // 0x-prefixed 20-byte address
+    pub nonce: FieldElement,      // Fresh per request, see `P2PProofService::create_request`
+    pub expires_at: u64,          // Unix millis; no response is made or accepted after this
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Thresholds the verifier sets; safe to send and carried in `P2PProofRequest`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofRequirements {
+    pub current_date: u64,
+    pub min_age: u64,
+    pub required_nationality: u64,
+    pub required_residency: u64,
+    pub min_credit_score: u64,
+}
+
+impl ProofRequirements {
+    /// poseidon2_hash of the thresholds in field order, published as the `RequirementsHash` signal
+    pub fn hash(&self) -> FieldElement {
+        poseidon2_hash(&[
+            self.current_date.into(),
+            self.min_age.into(),
+            self.required_nationality.into(),
+            self.required_residency.into(),
+            self.min_credit_score.into(),
+        ])
+    }
+}
+
+/// Inputs only the holder knows. There is no `Serialize` impl and `Debug` is redacted, so they
+/// cannot travel with a request or proof or end up in a log.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct HolderSecrets {
+    pub nullifier_secret: FieldElement, // Keep it across proofs: a new secret means a new nullifier
+}
+
+impl HolderSecrets {
+    pub fn new(nullifier_secret: FieldElement) -> Self {
+        Self { nullifier_secret }
+    }
+
+    /// Fresh secrets from `rng`; 31 random bytes always lie below the modulus
+    pub fn generate(rng: &dyn Rng) -> Self {
+        let mut secret = [0u8; 31];
+        rng.fill_bytes(&mut secret);
+        Self::new(FieldElement::from_hash(&secret))
+    }
+
+    /// Blinding of the contact commitment answering the request with `nonce`. It is derived
+    /// rather than drawn, so the holder can hand it to the verifier without storing it.
+    pub fn contact_blinding(&self, nonce: FieldElement) -> FieldElement {
+        poseidon2_hash(&[FieldElement::from_text(CONTACT_BLINDING_DOMAIN), self.nullifier_secret, nonce])
+    }
+}
+
+impl fmt::Debug for HolderSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HolderSecrets([REDACTED])")
+    }
+}
+
+/// The circuit's `ProofChallenge` witness, assembled on the holder's device. Its JSON is the
+/// combined challenge format used before requirements and secrets were split.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProofChallenge {
     pub current_date: u64,
     pub min_age: u64,
     pub required_nationality: u64,
     pub required_residency: u64,
     pub min_credit_score: u64,
-    pub nullifier_secret: String,
+    pub nullifier_secret: FieldElement,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ProofChallenge {
+    pub fn new(requirements: &ProofRequirements, secrets: &HolderSecrets) -> Self {
+        Self {
+            current_date: requirements.current_date,
+            min_age: requirements.min_age,
+            required_nationality: requirements.required_nationality,
+            required_residency: requirements.required_residency,
+            min_credit_score: requirements.min_credit_score,
+            nullifier_secret: secrets.nullifier_secret,
+        }
+    }
+
+    pub fn requirements(&self) -> ProofRequirements {
+        ProofRequirements {
+            current_date: self.current_date,
+            min_age: self.min_age,
+            required_nationality: self.required_nationality,
+            required_residency: self.required_residency,
+            min_credit_score: self.min_credit_score,
+        }
+    }
+
+    pub fn split(self) -> (ProofRequirements, HolderSecrets) {
+        (self.requirements(), HolderSecrets::new(self.nullifier_secret))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct P2PProofResponse {
     pub proof: String,
     pub public_signals: Vec<String>,
@@ -40,156 +319,649 @@ pub struct IDData {
     pub birthdate: u64,
     pub nationality: u64,
     pub residency_code: u64,
-    pub document_hash: String,
+    pub document_hash: FieldElement, // Document digest reduced mod p, see `FieldElement::from_hash`
     pub credit_score: u64,
 }
 
-#[wasm_bindgen]
+impl CircuitInput for IDData {
+    fn to_fields(&self) -> Vec<(&'static str, FieldElement)> {
+        vec![
+            ("birthdate", self.birthdate.into()),
+            ("nationality", self.nationality.into()),
+            ("residency_code", self.residency_code.into()),
+            ("document_hash", self.document_hash),
+            ("credit_score", self.credit_score.into()),
+        ]
+    }
+}
+
+impl CircuitInput for ProofChallenge {
+    fn to_fields(&self) -> Vec<(&'static str, FieldElement)> {
+        vec![
+            ("current_date", self.current_date.into()),
+            ("min_age", self.min_age.into()),
+            ("required_nationality", self.required_nationality.into()),
+            ("required_residency", self.required_residency.into()),
+            ("min_credit_score", self.min_credit_score.into()),
+            ("nullifier_secret", self.nullifier_secret),
+        ]
+    }
+}
+
+/// The request a proof answers, as its public signals carry it; all zero for proofs made without one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct RequestBinding {
+    verifier_address: FieldElement,
+    nonce: FieldElement,
+    expires_at: FieldElement,
+    contact_commitment: FieldElement, // Contact proofs only; zero elsewhere
+}
+
+impl RequestBinding {
+    fn of(request: &P2PProofRequest) -> Result<Self, ProofServiceError> {
+        let verifier_address = FieldElement::from_address(&request.verifier_address)
+            .map_err(|source| ProofServiceError::InvalidFieldElement { input: "verifier address", source })?;
+        Ok(Self {
+            verifier_address,
+            nonce: request.nonce,
+            expires_at: request.expires_at.into(),
+            contact_commitment: FieldElement::default(),
+        })
+    }
+
+    /// Binding of a contact proof answering `request`, committing to `contact` under its nonce
+    fn with_contact(
+        request: &P2PProofRequest,
+        contact_kind: ContactKind,
+        contact: &str,
+        blinding: FieldElement,
+    ) -> Result<Self, ProofServiceError> {
+        Ok(Self { contact_commitment: contact_commitment(contact_kind, contact, request.nonce, blinding), ..Self::of(request)? })
+    }
+
+    fn value(&self, signal: PublicSignal) -> Option<FieldElement> {
+        match signal {
+            PublicSignal::VerifierAddress => Some(self.verifier_address),
+            PublicSignal::VerifierNonce => Some(self.nonce),
+            PublicSignal::ExpiresAt => Some(self.expires_at),
+            PublicSignal::ContactCommitment => Some(self.contact_commitment),
+            _ => None,
+        }
+    }
+}
+
+const CONTACT_BLINDING_DOMAIN: &str = "PatriconID/contact/blinding/v1";
+const PASSKEY_BINDING_DOMAIN: &[u8] = b"PatriconID/passkey/binding/v1";
+
+/// A passkey bound to an identity commitment; only it can sign that commitment's proofs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasskeyBinding {
+    pub commitment: FieldElement,
+    pub credential_id: String, // base64url
+    pub public_key: String,    // base64url COSE key, what verifiers pass to `verify_proof`
+    pub attestation: AttestationType,
+    pub bound_at: u64,         // Unix millis
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct P2PProofService {
-    circuits: HashMap<u8, String>,
-    passkey_bound: bool,
+    proof_types: ProofTypeRegistry,
+    registry: MerkleFrontier, // Local view of the commitments registered in P2PIdentityRegistry
+    nullifiers: Arc<dyn NullifierStore>,
+    nullifier_policy: NullifierPolicy,
+    #[cfg(feature = "wasm")]
+    indexed_db: Option<Arc<IndexedDbNullifierStore>>, // Same store as `nullifiers`, when it is IndexedDB
+    relying_party: Option<RelyingParty>,         // Scope holders' passkeys must sign under
+    passkey_sign_counts: Mutex<HashMap<String, u32>>, // Last counter seen per credential public key
+    passkeys: HashMap<FieldElement, PasskeyBinding>,  // Holder side, by commitment
+    pending_bindings: HashMap<FieldElement, [u8; 32]>, // Registration challenges not yet answered
+    uidai_certificates: Vec<String>, // PEM; the only certificates Aadhaar signatures are checked against
+    freshness_policy: FreshnessPolicy, // Applied to every offline e-KYC file this service parses
+    qr_freshness_policy: FreshnessPolicy, // Applied to Secure QR codes, which printed cards carry for years
+    clock: Arc<dyn Clock>,
+    rng: Arc<dyn Rng>,
 }
 
-#[wasm_bindgen]
 impl P2PProofService {
-    #[wasm_bindgen(constructor)]
     pub fn new() -> P2PProofService {
-        console::log_1(&"Initializing P2P Proof Service".into());
+        Self::with_platform(Arc::new(SystemClock), Arc::new(SystemRng))
+    }
+
+    /// Service with injected time and randomness, e.g. fixed values for native tests
+    pub fn with_platform(clock: Arc<dyn Clock>, rng: Arc<dyn Rng>) -> P2PProofService {
+        log_info!("Initializing P2P Proof Service");
         
         P2PProofService {
-            circuits: HashMap::new(),
-            passkey_bound: false,
+            proof_types: ProofTypeRegistry::with_builtin_types(),
+            registry: MerkleFrontier::new(),
+            nullifiers: Arc::new(MemoryNullifierStore::new()),
+            nullifier_policy: NullifierPolicy::default(),
+            #[cfg(feature = "wasm")]
+            indexed_db: None,
+            relying_party: None,
+            passkey_sign_counts: Mutex::new(HashMap::new()),
+            passkeys: HashMap::new(),
+            pending_bindings: HashMap::new(),
+            uidai_certificates: Vec::new(),
+            freshness_policy: FreshnessPolicy::default(),
+            qr_freshness_policy: FreshnessPolicy::unrestricted(),
+            clock,
+            rng,
         }
     }
 
+    /// Whether any commitment has a passkey bound on this service instance
+    pub fn passkey_bound(&self) -> bool {
+        !self.passkeys.is_empty()
+    }
+
     /// Initialize the service with circuit files
-    #[wasm_bindgen]
-    pub async fn initialize(&mut self) -> Result<(), JsValue> {
-        // Load compiled ACIR circuits
-        self.circuits.insert(1, "age_proof_circuit.acir".to_string());
-        self.circuits.insert(2, "residency_proof_circuit.acir".to_string());
-        self.circuits.insert(3, "nationality_proof_circuit.acir".to_string());
-        self.circuits.insert(4, "credit_proof_circuit.acir".to_string());
-        self.circuits.insert(5, "composite_proof_circuit.acir".to_string());
-        
-        console::log_1(&"P2P Proof Service initialized".into());
+    pub fn initialize(&mut self) {
+        // Compiled ACIR circuits are named by the registered proof types
+        log_info!("P2P Proof Service initialized", Field::public("circuits", self.proof_types.len()));
+    }
+
+    pub fn proof_types(&self) -> &ProofTypeRegistry {
+        &self.proof_types
+    }
+
+    /// Accept an additional proof type; built-in ids cannot be replaced
+    pub fn register_proof_type(&mut self, spec: ProofTypeSpec) -> Result<(), ProofServiceError> {
+        Ok(self.proof_types.register(spec)?)
+    }
+
+    /// Root published as the `MerkleRoot` public signal
+    pub fn registry_root(&self) -> FieldElement {
+        self.registry.root()
+    }
+
+    /// Replace the registry view, e.g. with a frontier synced from an indexer
+    pub fn set_registry_frontier(&mut self, frontier: MerkleFrontier) {
+        self.registry = frontier;
+    }
+
+    /// Replace where spent nullifiers are kept, e.g. with a file or IndexedDB store
+    pub fn set_nullifier_store(&mut self, store: Arc<dyn NullifierStore>) {
+        self.nullifiers = store;
+        #[cfg(feature = "wasm")]
+        {
+            self.indexed_db = None;
+        }
+    }
+
+    pub fn nullifier_store(&self) -> &Arc<dyn NullifierStore> {
+        &self.nullifiers
+    }
+
+    pub fn set_nullifier_policy(&mut self, policy: NullifierPolicy) {
+        self.nullifier_policy = policy;
+    }
+
+    /// Trust another UIDAI signing certificate, e.g. a rotated one, when verifying Aadhaar files
+    pub fn add_uidai_certificate(&mut self, certificate_pem: &str) -> Result<(), ProofServiceError> {
+        TrustStore::new().add_pem(certificate_pem.as_bytes())?;
+        self.uidai_certificates.push(certificate_pem.to_string());
+        Ok(())
+    }
+
+    pub fn freshness_policy(&self) -> &FreshnessPolicy {
+        &self.freshness_policy
+    }
+
+    /// How old an offline e-KYC file may be; by default `DEFAULT_MAX_AGE_DAYS`
+    pub fn set_freshness_policy(&mut self, freshness_policy: FreshnessPolicy) {
+        self.freshness_policy = freshness_policy;
+    }
+
+    pub fn qr_freshness_policy(&self) -> &FreshnessPolicy {
+        &self.qr_freshness_policy
+    }
+
+    /// How old a Secure QR code may be; by default any age, as the QR printed on a card is never reissued
+    pub fn set_qr_freshness_policy(&mut self, qr_freshness_policy: FreshnessPolicy) {
+        self.qr_freshness_policy = qr_freshness_policy;
+    }
+
+    /// Relying party whose passkeys sign proofs; passkey verification fails until one is set
+    pub fn set_relying_party(&mut self, relying_party: RelyingParty) {
+        self.relying_party = Some(relying_party);
+    }
+
+    /// Start binding a passkey to `commitment`: the returned base64url challenge goes to
+    /// `navigator.credentials.create()`, and the registration it yields to `bind_passkey`
+    pub fn begin_passkey_binding(&mut self, commitment: &str) -> Result<String, ProofServiceError> {
+        let commitment = parse_commitment(commitment)?;
+        let mut nonce = [0u8; 32];
+        self.rng.fill_bytes(&mut nonce);
+        let challenge: [u8; 32] = Sha256::new()
+            .chain_update(PASSKEY_BINDING_DOMAIN)
+            .chain_update(commitment.to_bytes())
+            .chain_update(nonce)
+            .finalize()
+            .into();
+        self.pending_bindings.insert(commitment, challenge);
+        Ok(URL_SAFE_NO_PAD.encode(challenge))
+    }
+
+    /// Verify the attestation made over `begin_passkey_binding`'s challenge and bind the new
+    /// credential to `commitment`, replacing any passkey bound to it before
+    pub fn bind_passkey(
+        &mut self,
+        commitment: &str,
+        registration: &PasskeyRegistration,
+    ) -> Result<PasskeyBinding, ProofServiceError> {
+        let commitment = parse_commitment(commitment)?;
+        let relying_party = self.relying_party()?;
+        let challenge = self.pending_bindings.get(&commitment)
+            .ok_or_else(|| ProofServiceError::PasskeyBinding(format!("no binding was started for {}", commitment)))?;
+        let verified = attestation::verify_registration(relying_party, registration, challenge)?;
+        self.pending_bindings.remove(&commitment);
+
+        let credential_id = URL_SAFE_NO_PAD.encode(&verified.credential.credential_id);
+        self.ensure_credential_unbound(&credential_id, &commitment)?;
+        let binding = PasskeyBinding {
+            commitment,
+            credential_id,
+            public_key: webauthn::encode_public_key(&verified.credential.public_key),
+            attestation: verified.attestation,
+            bound_at: self.now_millis(),
+        };
+        if self.passkeys.insert(commitment, binding.clone()).is_some() {
+            log_info!("Replaced the passkey bound to a commitment", Field::public("commitment", commitment));
+        }
+        Ok(binding)
+    }
+
+    pub fn passkey_binding(&self, commitment: &FieldElement) -> Option<&PasskeyBinding> {
+        self.passkeys.get(commitment)
+    }
+
+    pub fn passkey_bindings(&self) -> impl Iterator<Item = &PasskeyBinding> {
+        self.passkeys.values()
+    }
+
+    /// Reinstate a binding kept from an earlier session. Its public key must decode and its
+    /// credential must not be bound to another commitment here already.
+    pub fn restore_passkey_binding(&mut self, binding: PasskeyBinding) -> Result<(), ProofServiceError> {
+        webauthn::decode_public_key(&binding.public_key)
+            .map_err(|error| ProofServiceError::PasskeyBinding(format!("invalid public key: {}", error)))?;
+        if URL_SAFE_NO_PAD.decode(&binding.credential_id).map_or(true, |credential_id| credential_id.is_empty()) {
+            return Err(ProofServiceError::PasskeyBinding("invalid credential id".to_string()));
+        }
+        self.ensure_credential_unbound(&binding.credential_id, &binding.commitment)?;
+        self.passkeys.insert(binding.commitment, binding);
+        Ok(())
+    }
+
+    /// One credential cannot vouch for two identities
+    fn ensure_credential_unbound(&self, credential_id: &str, commitment: &FieldElement) -> Result<(), ProofServiceError> {
+        if self.passkeys.values().any(|binding| binding.credential_id == credential_id && binding.commitment != *commitment) {
+            return Err(ProofServiceError::PasskeyBinding("credential is bound to another commitment".to_string()));
+        }
         Ok(())
     }
 
+    /// Forget nullifiers that expired or belong to an earlier epoch than the current policy's
+    pub fn prune_nullifiers(&self) -> Result<usize, ProofServiceError> {
+        Ok(self.nullifiers.prune(self.nullifier_policy.epoch, self.now_millis())?)
+    }
+
     /// Generate ZK proof locally (no backend)
-    #[wasm_bindgen]
-    pub async fn generate_proof(
+    pub fn generate_proof(
         &self,
         id_data_json: &str,
-        challenge_json: &str,
+        requirements_json: &str,
+        secrets: &HolderSecrets,
         proof_type: u8,
-    ) -> Result<String, JsValue> {
+    ) -> Result<String, ProofServiceError> {
         let id_data: IDData = serde_json::from_str(id_data_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid ID data: {}", e)))?;
+            .map_err(|source| ProofServiceError::InvalidInput { input: "ID data", source })?;
         
-        let challenge: ProofChallenge = serde_json::from_str(challenge_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid challenge: {}", e)))?;
+        let requirements: ProofRequirements = serde_json::from_str(requirements_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "requirements", source })?;
+
+        let challenge = ProofChallenge::new(&requirements, secrets);
+        self.prove(&id_data, &challenge, proof_type, ProofSource::Direct, &RequestBinding::default())
+    }
+
+    /// Verifier side: ask for `proof_type` under `requirements_json`, answerable for `ttl_millis`.
+    /// The nonce is fresh, so the response cannot be replayed against another request.
+    pub fn create_request(
+        &self,
+        proof_type: u8,
+        requirements_json: &str,
+        verifier_address: &str,
+        ttl_millis: u64,
+    ) -> Result<String, ProofServiceError> {
+        self.proof_types.get(proof_type)?;
+        let requirements: ProofRequirements = serde_json::from_str(requirements_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "requirements", source })?;
+
+        // 31 random bytes always lie below the modulus, so the nonce is uniform
+        let mut nonce = [0u8; 31];
+        self.rng.fill_bytes(&mut nonce);
+        let request = P2PProofRequest {
+            proof_type,
+            requirements,
+            verifier_address: verifier_address.to_string(),
+            nonce: FieldElement::from_hash(&nonce),
+            expires_at: self.now_millis().saturating_add(ttl_millis),
+        };
+        RequestBinding::of(&request)?;
+
+        serde_json::to_string(&request)
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// Holder side: prove the request's requirements with a proof bound to its verifier, nonce and expiry
+    pub fn respond_to_request(
+        &self,
+        id_data_json: &str,
+        request_json: &str,
+        secrets: &HolderSecrets,
+    ) -> Result<String, ProofServiceError> {
+        let id_data: IDData = serde_json::from_str(id_data_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "ID data", source })?;
+        let request: P2PProofRequest = serde_json::from_str(request_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof request", source })?;
+        if self.now_millis() > request.expires_at {
+            return Err(ProofServiceError::RequestExpired(request.expires_at));
+        }
+
+        let binding = RequestBinding::of(&request)?;
+        let challenge = ProofChallenge::new(&request.requirements, secrets);
+        self.prove(&id_data, &challenge, request.proof_type, ProofSource::Direct, &binding)
+    }
+
+    fn prove(
+        &self,
+        id_data: &IDData,
+        challenge: &ProofChallenge,
+        proof_type: u8,
+        source: ProofSource,
+        binding: &RequestBinding,
+    ) -> Result<String, ProofServiceError> {
+        let spec = self.proof_types.validate(proof_type, source, id_data)?;
 
         // Generate nullifier hash
-        let nullifier_hash = self.generate_nullifier(&challenge.nullifier_secret, &id_data.document_hash);
+        let nullifier_hash = self.generate_nullifier(challenge.nullifier_secret, id_data.document_hash);
         
         // Generate identity commitment
-        let commitment = self.generate_commitment(&id_data, &challenge.nullifier_secret);
+        let commitment = self.generate_commitment(id_data, challenge.nullifier_secret);
         
         // Simulate circuit execution (in real implementation, use Barretenberg)
-        let proof_data = self.execute_circuit(&id_data, &challenge, proof_type).await?;
+        let proof_data = self.execute_circuit(id_data, challenge, spec)?;
         
-        // Clone proof before moving
-        let proof_clone = proof_data.proof.clone();
-        
-        // Create proof response
+        // Create proof response; the holder's passkey signs it afterwards, see `passkey_challenge`
         let response = P2PProofResponse {
             proof: proof_data.proof,
-            public_signals: vec![
-                proof_type.to_string(),
-                nullifier_hash.clone(),
-                "merkle_root_placeholder".to_string(),
-                commitment.clone(),
-            ],
-            signature: self.sign_proof_with_passkey(&proof_clone).await?,
+            public_signals: spec.public_signals.iter().map(|signal| match signal {
+                PublicSignal::ProofType => proof_type.to_string(),
+                PublicSignal::NullifierHash => nullifier_hash.clone(),
+                PublicSignal::MerkleRoot => self.registry.root().to_hex(),
+                PublicSignal::Commitment => commitment.clone(),
+                PublicSignal::VerifierAddress => binding.verifier_address.to_hex(),
+                PublicSignal::VerifierNonce => binding.nonce.to_hex(),
+                PublicSignal::ExpiresAt => binding.expires_at.to_hex(),
+                PublicSignal::RequirementsHash => challenge.requirements().hash().to_hex(),
+                PublicSignal::ContactCommitment => binding.contact_commitment.to_hex(),
+            }).collect(),
+            signature: String::new(),
             nullifier_hash,
             commitment,
-            timestamp: js_sys::Date::now() as u64,
+            timestamp: self.now_millis(),
         };
         
         serde_json::to_string(&response)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// WebAuthn challenge for a generated proof, as base64url for `navigator.credentials.get()`.
+    /// It hashes the proof and its public signals, so the assertion fits this proof only.
+    /// Fails unless a passkey is bound to the proof's commitment.
+    pub fn passkey_challenge(&self, proof_json: &str) -> Result<String, ProofServiceError> {
+        let proof: P2PProofResponse = serde_json::from_str(proof_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof", source })?;
+        self.bound_passkey(&proof)?;
+        Ok(URL_SAFE_NO_PAD.encode(webauthn::proof_challenge(&proof.proof, &proof.public_signals)))
     }
 
-    /// Verify ZK proof locally (P2P verification)
-    #[wasm_bindgen]
-    pub async fn verify_proof(
+    /// Put the holder's assertion over `passkey_challenge` into the proof's signature, after
+    /// checking it comes from the passkey bound to the proof's commitment
+    pub fn attach_passkey_assertion(
         &self,
         proof_json: &str,
-        verifier_public_key: &str,
-    ) -> Result<bool, JsValue> {
+        assertion: &PasskeyAssertion,
+    ) -> Result<String, ProofServiceError> {
+        let mut proof: P2PProofResponse = serde_json::from_str(proof_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof", source })?;
+        let binding = self.bound_passkey(&proof)?;
+        if URL_SAFE_NO_PAD.encode(&assertion.credential_id) != binding.credential_id {
+            return Err(ProofServiceError::PasskeyBinding("assertion is from another credential".to_string()));
+        }
+        // Counters are the verifier's to track; the holder only checks the signature
+        let challenge = webauthn::proof_challenge(&proof.proof, &proof.public_signals);
+        let public_key = webauthn::decode_public_key(&binding.public_key)?;
+        webauthn::verify_assertion(self.relying_party()?, &public_key, assertion, &challenge, 0)?;
+
+        proof.signature = assertion.to_compact();
+        serde_json::to_string(&proof)
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// Verify ZK proof locally (P2P verification) and the holder's passkey signature over it.
+    /// `passkey_public_key` is the holder's credential public key, base64url of its COSE encoding.
+    /// A valid proof spends its nullifier, so presenting it again fails with `NullifierReused`.
+    /// Proofs answering a request are refused here; check those with `verify_response`.
+    pub fn verify_proof(
+        &self,
+        proof_json: &str,
+        passkey_public_key: &str,
+    ) -> Result<bool, ProofServiceError> {
         let proof: P2PProofResponse = serde_json::from_str(proof_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid proof: {}", e)))?;
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof", source })?;
+
+        self.verify(&proof, passkey_public_key, &RequestBinding::default())
+    }
+
+    /// Verifier side: check a response against the request it answers. Fails on a different
+    /// proof type, requirements, verifier address, nonce or expiry; once the request has expired
+    /// it errors with `RequestExpired`, as `respond_to_request` does.
+    pub fn verify_response(
+        &self,
+        request_json: &str,
+        response_json: &str,
+        passkey_public_key: &str,
+    ) -> Result<bool, ProofServiceError> {
+        let request: P2PProofRequest = serde_json::from_str(request_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof request", source })?;
+        self.verify_answer(&request, response_json, passkey_public_key, &RequestBinding::of(&request)?)
+    }
+
+    /// Verifier side: check a contact proof against its request and the contact the verifier
+    /// expects, e.g. one it has just confirmed by OTP. Fails for any other contact.
+    /// `contact_blinding` is the holder's `contact_blinding` for this request, sent to this verifier only.
+    pub fn verify_contact_response(
+        &self,
+        request_json: &str,
+        response_json: &str,
+        passkey_public_key: &str,
+        contact_kind: &str,
+        expected_contact: &str,
+        contact_blinding: &str,
+    ) -> Result<bool, ProofServiceError> {
+        let kind = parse_contact_kind(contact_kind)?;
+        let request = self.contact_request(request_json)?;
+        let blinding = contact_blinding.parse()
+            .map_err(|source| ProofServiceError::InvalidFieldElement { input: "contact blinding", source })?;
+        let binding = RequestBinding::with_contact(&request, kind, expected_contact, blinding)?;
+        self.verify_answer(&request, response_json, passkey_public_key, &binding)
+    }
+
+    fn verify_answer(
+        &self,
+        request: &P2PProofRequest,
+        response_json: &str,
+        passkey_public_key: &str,
+        binding: &RequestBinding,
+    ) -> Result<bool, ProofServiceError> {
+        let proof: P2PProofResponse = serde_json::from_str(response_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof", source })?;
+
+        if self.now_millis() > request.expires_at {
+            log_warn!("Rejected a response to an expired request", Field::public("expires_at", request.expires_at));
+            return Err(ProofServiceError::RequestExpired(request.expires_at));
+        }
+        if proof.public_signals.first() != Some(&request.proof_type.to_string()) {
+            return Ok(false);
+        }
+        if !self.proves_requirements(&proof, request)? {
+            log_warn!("Rejected a response proven against other requirements");
+            return Ok(false);
+        }
+        self.verify(&proof, passkey_public_key, binding)
+    }
+
+    /// Whether the response publishes the hash of the request's requirements
+    fn proves_requirements(&self, proof: &P2PProofResponse, request: &P2PProofRequest) -> Result<bool, ProofServiceError> {
+        let spec = self.proof_types.get(request.proof_type)?;
+        let signal = spec.public_signals.iter().position(|signal| *signal == PublicSignal::RequirementsHash);
+        let proven = signal.and_then(|index| proof.public_signals.get(index)?.parse::<FieldElement>().ok());
+        Ok(proven == Some(request.requirements.hash()))
+    }
+
+    /// A request for a proof type that publishes a contact commitment
+    fn contact_request(&self, request_json: &str) -> Result<P2PProofRequest, ProofServiceError> {
+        let request: P2PProofRequest = serde_json::from_str(request_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "proof request", source })?;
+        let spec = self.proof_types.get(request.proof_type)?;
+        if !spec.public_signals.contains(&PublicSignal::ContactCommitment) {
+            return Err(ProofServiceError::InvalidProofRequest(ProofTypeError::MissingSignal {
+                proof_type: spec.name.clone(),
+                signal: PublicSignal::ContactCommitment,
+            }));
+        }
+        Ok(request)
+    }
+
+    fn verify(
+        &self,
+        proof: &P2PProofResponse,
+        passkey_public_key: &str,
+        binding: &RequestBinding,
+    ) -> Result<bool, ProofServiceError> {
+        // 0. Public signals must follow the layout of the proof type they claim
+        let Some(proof_type) = proof.public_signals.first().and_then(|signal| signal.parse::<u8>().ok()) else {
+            return Ok(false);
+        };
+        let spec = self.proof_types.get(proof_type)?;
+        if spec.public_signals.len() != proof.public_signals.len() {
+            return Ok(false);
+        }
+
+        // ...and carry exactly the request binding expected, zero for a proof made without a request
+        let bound = spec.public_signals.iter().zip(&proof.public_signals)
+            .filter(|(signal, _)| signal.is_request_binding())
+            .all(|(signal, value)| value.parse::<FieldElement>().ok() == binding.value(*signal));
+        if !bound {
+            log_warn!("Rejected a proof bound to a different request");
+            return Ok(false);
+        }
 
         // 1. Verify ZK proof using circuit verifier
-        let zk_valid = self.verify_zk_proof(&proof.proof, &proof.public_signals).await?;
-        
+        if !self.verify_zk_proof(&proof.proof, &proof.public_signals)? {
+            return Ok(false);
+        }
+
         // 2. Verify passkey/biometric signature
-        let signature_valid = self.verify_passkey_signature(
-            &proof.proof,
-            &proof.signature,
-            verifier_public_key,
-        ).await?;
-        
-        // 3. Check nullifier is not reused (in real implementation, check local storage)
-        let nullifier_fresh = self.check_nullifier_freshness(&proof.nullifier_hash)?;
-        
-        Ok(zk_valid && signature_valid && nullifier_fresh)
+        if !self.verify_passkey_signature(proof, passkey_public_key)? {
+            return Ok(false);
+        }
+
+        // 3. Spend the nullifier; only proofs that verified get this far, so rejects burn nothing
+        let Some(nullifier) = self.proven_nullifier(proof, spec) else {
+            return Ok(false);
+        };
+        let now = self.now_millis();
+        if !self.nullifiers.check_and_insert(self.nullifier_policy.record(nullifier.to_hex(), now), now)? {
+            log_warn!("Rejected a replayed proof", Field::public("nullifier", nullifier));
+            return Err(ProofServiceError::NullifierReused);
+        }
+        Ok(true)
+    }
+
+    /// Binary envelope of a proof, for channels that carry bytes
+    pub fn encode_proof(&self, proof_json: &str) -> Result<Vec<u8>, ProofServiceError> {
+        Ok(proof_envelope::encode(&parse_proof(proof_json)?))
+    }
+
+    /// JSON form of an envelope, as `verify_proof` takes it
+    pub fn decode_proof(&self, envelope: &[u8]) -> Result<String, ProofServiceError> {
+        serde_json::to_string(&proof_envelope::decode(envelope)?)
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// JSON form of a proof scanned from a `send_proof_p2p` QR link
+    pub fn decode_proof_uri(&self, uri: &str) -> Result<String, ProofServiceError> {
+        serde_json::to_string(&proof_envelope::from_qr_uri(uri)?)
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// How large a proof is as JSON, as an envelope and as a QR link, before picking a channel
+    pub fn proof_size(&self, proof_json: &str) -> Result<EnvelopeSize, ProofServiceError> {
+        Ok(proof_envelope::measure(&parse_proof(proof_json)?))
     }
 
     /// Send proof via P2P channel (QR, WalletConnect, direct link)
-    #[wasm_bindgen]
-    pub async fn send_proof_p2p(
+    pub fn send_proof_p2p(
         &self,
         proof_json: &str,
         channel: &str, // "qr", "walletconnect", "direct"
         recipient: &str,
-    ) -> Result<String, JsValue> {
+    ) -> Result<String, ProofServiceError> {
         match channel {
             "qr" => {
-                // Generate QR code with proof data
-                let qr_data = format!("patricon://verify?proof={}", 
-                    base64::encode(proof_json));
+                // The envelope, base64url so the link needs no escaping
+                let qr_data = proof_envelope::to_qr_uri(&self.encode_proof(proof_json)?);
+                if qr_data.len() > QR_CAPACITY {
+                    return Err(ProofServiceError::ProofTooLarge { size: qr_data.len(), limit: QR_CAPACITY });
+                }
                 Ok(qr_data)
             }
             "walletconnect" => {
                 // Send via WalletConnect protocol
-                self.send_via_walletconnect(proof_json, recipient).await
+                self.send_via_walletconnect(proof_json, recipient)
             }
             "direct" => {
                 // Direct P2P transfer (WebRTC, etc.)
-                self.send_direct(proof_json, recipient).await
+                self.send_direct(proof_json, recipient)
             }
-            _ => Err(JsValue::from_str("Unsupported channel"))
+            _ => Err(ProofServiceError::UnsupportedChannel(channel.to_string()))
         }
     }
 
     // Private helper methods
 
-    async fn execute_circuit(
+    fn now_millis(&self) -> u64 {
+        self.clock.now().timestamp_millis().max(0) as u64
+    }
+
+    fn execute_circuit(
         &self,
-        id_data: &IDData,
-        challenge: &ProofChallenge,
-        proof_type: u8,
-    ) -> Result<CircuitResult, JsValue> {
+        _id_data: &IDData,
+        _challenge: &ProofChallenge,
+        spec: &ProofTypeSpec,
+    ) -> Result<CircuitResult, ProofServiceError> {
         // In real implementation, this would:
-        // 1. Load the compiled ACIR circuit
+        // 1. Load the compiled ACIR circuit (spec.circuit_artifact)
         // 2. Generate witness using Noir
         // 3. Create proof using Barretenberg
         // 4. Return proof data
         
         // Simplified simulation for now
-        let proof = format!("proof_{}_{}", proof_type, js_sys::Date::now());
+        let mut nonce = [0u8; 16];
+        self.rng.fill_bytes(&mut nonce);
+        let proof = format!("proof_{}_{}", spec.id, hex::encode(nonce));
         
         Ok(CircuitResult {
             proof,
@@ -197,210 +969,1184 @@ impl P2PProofService {
         })
     }
 
-    async fn sign_proof_with_passkey(&self, proof: &str) -> Result<String, JsValue> {
-        // WebAuthn/Passkey signing to prevent proof transfer
-        // This binds the proof to the device/biometric
-        
-        let signature = format!("passkey_sig_{}", base64::encode(proof.as_bytes()));
-        Ok(signature)
-    }
-
-    async fn verify_zk_proof(
+    fn verify_zk_proof(
         &self,
         proof: &str,
         public_signals: &[String],
-    ) -> Result<bool, JsValue> {
+    ) -> Result<bool, ProofServiceError> {
         // In real implementation, use Barretenberg verifier
         // For now, simulate verification
         Ok(!proof.is_empty() && !public_signals.is_empty())
     }
 
-    async fn verify_passkey_signature(
+    /// Check the WebAuthn assertion in the proof's signature; this ensures only the holder
+    /// of the passkey can present the proof. An unusable key is an error, a bad assertion is not.
+    fn verify_passkey_signature(
         &self,
-        proof: &str,
-        signature: &str,
-        public_key: &str,
-    ) -> Result<bool, JsValue> {
-        // Verify WebAuthn/passkey signature
-        // This ensures only the original holder can use the proof
-        Ok(signature.contains("passkey_sig"))
+        proof: &P2PProofResponse,
+        passkey_public_key: &str,
+    ) -> Result<bool, ProofServiceError> {
+        let relying_party = self.relying_party()?;
+        let public_key = webauthn::decode_public_key(passkey_public_key)?;
+        let assertion = match PasskeyAssertion::from_compact(&proof.signature) {
+            Ok(assertion) => assertion,
+            Err(error) => {
+                log_warn!("Rejected a proof without a passkey assertion", Field::public("reason", error));
+                return Ok(false);
+            }
+        };
+
+        // Counters are kept per public key: the credential id in the assertion is not signed
+        let challenge = webauthn::proof_challenge(&proof.proof, &proof.public_signals);
+        let credential = webauthn::encode_public_key(&public_key);
+        let mut sign_counts = self.passkey_sign_counts.lock().unwrap_or_else(PoisonError::into_inner);
+        let stored = sign_counts.get(&credential).copied().unwrap_or(0);
+        match webauthn::verify_assertion(relying_party, &public_key, &assertion, &challenge, stored) {
+            Ok(authenticator_data) => {
+                sign_counts.insert(credential, authenticator_data.sign_count);
+                Ok(true)
+            }
+            Err(error) => {
+                log_warn!("Rejected a passkey assertion", Field::public("reason", error));
+                Ok(false)
+            }
+        }
+    }
+
+    fn relying_party(&self) -> Result<&RelyingParty, ProofServiceError> {
+        self.relying_party.as_ref()
+            .ok_or_else(|| ProofServiceError::SignatureFailed("no relying party configured".to_string()))
     }
 
-    fn check_nullifier_freshness(&self, nullifier: &str) -> Result<bool, JsValue> {
-        // Check if nullifier has been used before
-        // In real implementation, check IndexedDB or local storage
-        Ok(!nullifier.is_empty())
+    fn bound_passkey(&self, proof: &P2PProofResponse) -> Result<&PasskeyBinding, ProofServiceError> {
+        proof.commitment.parse::<FieldElement>().ok()
+            .and_then(|commitment| self.passkeys.get(&commitment))
+            .ok_or_else(|| ProofServiceError::PasskeyNotBound(proof.commitment.clone()))
     }
 
-    fn generate_nullifier(&self, secret: &str, document_hash: &str) -> String {
-        // Generate anti-replay nullifier
-        format!("nullifier_{}_{}", 
-            base64::encode(secret.as_bytes()), 
-            base64::encode(document_hash.as_bytes()))
+    /// The response's nullifier as a field element, if it is one and matches the proven signal.
+    /// Parsing canonicalizes it, so `0xAB` and `0x00ab` cannot pass as two nullifiers.
+    fn proven_nullifier(&self, proof: &P2PProofResponse, spec: &ProofTypeSpec) -> Option<FieldElement> {
+        let nullifier: FieldElement = proof.nullifier_hash.parse().ok()?;
+        let signal = spec.public_signals.iter().position(|signal| *signal == PublicSignal::NullifierHash);
+        match signal.map(|index| proof.public_signals[index].parse::<FieldElement>()) {
+            Some(Ok(proven)) if proven == nullifier => Some(nullifier),
+            Some(_) => None,
+            None => Some(nullifier),
+        }
+    }
+
+    /// poseidon2_hash([secret, document_hash]), as in circuits/p2p_selective_disclosure
+    fn generate_nullifier(&self, secret: FieldElement, document_hash: FieldElement) -> String {
+        poseidon2_hash(&[secret, document_hash]).to_hex()
     }
 
-    fn generate_commitment(&self, id_data: &IDData, secret: &str) -> String {
-        // Generate identity commitment for controlled linkability
-        format!("commitment_{}_{}_{}_{}_{}", 
-            id_data.birthdate,
-            id_data.nationality,
-            id_data.residency_code,
-            base64::encode(secret.as_bytes()),
-            js_sys::Date::now())
+    /// poseidon2_hash([birthdate, nationality, residency, secret]), as in circuits/p2p_selective_disclosure
+    fn generate_commitment(&self, id_data: &IDData, secret: FieldElement) -> String {
+        poseidon2_hash(&[
+            id_data.birthdate.into(),
+            id_data.nationality.into(),
+            id_data.residency_code.into(),
+            secret,
+        ]).to_hex()
     }
 
-    async fn send_via_walletconnect(&self, proof: &str, recipient: &str) -> Result<String, JsValue> {
+    fn send_via_walletconnect(&self, _proof: &str, recipient: &str) -> Result<String, ProofServiceError> {
         Ok(format!("Sent to {} via WalletConnect", recipient))
     }
 
-    async fn send_direct(&self, proof: &str, recipient: &str) -> Result<String, JsValue> {
+    fn send_direct(&self, _proof: &str, recipient: &str) -> Result<String, ProofServiceError> {
         Ok(format!("Sent to {} directly", recipient))
     }
 
-    /// Verify Aadhaar XML with UIDAI digital signature
-    #[wasm_bindgen]
-    pub async fn verify_aadhaar_xml(
+    /// Parser sharing this service's clock, so freshness and certificate checks follow it
+    fn aadhaar_parser(&self) -> Result<AadhaarXMLParser, ProofServiceError> {
+        let mut parser = AadhaarXMLParser::new();
+        for certificate_pem in &self.uidai_certificates {
+            parser.trust_store_mut().add_pem(certificate_pem.as_bytes()).map_err(ProofServiceError::ParserInitialization)?;
+        }
+        parser.trust_store_mut().set_clock(Box::new(self.clock.clone()));
+        parser.set_freshness_policy(self.freshness_policy);
+        parser.set_qr_freshness_policy(self.qr_freshness_policy);
+        Ok(parser)
+    }
+
+    /// Verify Aadhaar XML with UIDAI digital signature. Returns the redacted view; the holder's own
+    /// device takes `export_aadhaar_xml` instead when it goes on to prove.
+    pub fn verify_aadhaar_xml(
         &self,
         zip_file_data: &[u8],
         share_code: &str,
-    ) -> Result<String, JsValue> {
-        console::log_1(&"Starting Aadhaar XML verification...".into());
+    ) -> Result<String, ProofServiceError> {
+        let verified_data = self.verified_aadhaar_xml(zip_file_data, share_code)?;
+        serde_json::to_string(&verified_data.redacted_on(self.clock.now().date_naive()))
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// Verify the decimal payload scanned from an Aadhaar Secure QR code; redacted like `verify_aadhaar_xml`
+    pub fn verify_aadhaar_qr(&self, qr_payload: &str) -> Result<String, ProofServiceError> {
+        let verified_data = self.verified_aadhaar_qr(qr_payload)?;
+        serde_json::to_string(&verified_data.redacted_on(self.clock.now().date_naive()))
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// Verify Aadhaar XML and return every field in clear, the input `generate_aadhaar_proof` takes
+    pub fn export_aadhaar_xml(&self, zip_file_data: &[u8], share_code: &str) -> Result<String, ProofServiceError> {
+        self.verified_aadhaar_xml(zip_file_data, share_code)?
+            .to_exposed_json()
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    /// Secure QR counterpart of `export_aadhaar_xml`
+    pub fn export_aadhaar_qr(&self, qr_payload: &str) -> Result<String, ProofServiceError> {
+        self.verified_aadhaar_qr(qr_payload)?
+            .to_exposed_json()
+            .map_err(ProofServiceError::Serialization)
+    }
+
+    fn verified_aadhaar_xml(&self, zip_file_data: &[u8], share_code: &str) -> Result<VerifiedAadhaarData, ProofServiceError> {
+        log_info!("Starting Aadhaar XML verification", Field::public("zip_bytes", zip_file_data.len()));
         
-        let parser = AadhaarXMLParser::new()
-            .map_err(|e| JsValue::from_str(&format!("Parser initialization failed: {}", e)))?;
+        let parser = self.aadhaar_parser()?;
         
-        let verified_data = parser.parse_aadhaar_xml(zip_file_data, share_code)
-            .map_err(|e| JsValue::from_str(&format!("Aadhaar verification failed: {}", e)))?;
+        let verified_data = parser.parse_aadhaar_xml(zip_file_data, share_code)?;
         
         if !verified_data.signature_valid {
-            return Err(JsValue::from_str("UIDAI signature verification failed"));
+            log_warn!("UIDAI signature verification failed", Field::public("source", "xml"));
+            return Err(ProofServiceError::UidaiSignatureInvalid);
         }
         
-        console::log_1(&format!("✅ Aadhaar verified for: {}", verified_data.name).into());
-        
-        serde_json::to_string(&verified_data)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        log_info!(
+            "Aadhaar verified",
+            Field::public("last_4", &verified_data.aadhaar_last_4_digits),
+            Field::sensitive("name", &verified_data.name),
+        );
+        Ok(verified_data)
     }
 
-    /// Generate ZK proof from verified Aadhaar data
-    #[wasm_bindgen]
-    pub async fn generate_aadhaar_proof(
-        &self,
-        verified_data_json: &str,
-        proof_type: u8, // 1=age, 2=residency, 3=composite
+    fn verified_aadhaar_qr(&self, qr_payload: &str) -> Result<VerifiedAadhaarData, ProofServiceError> {
+        log_info!("Starting Aadhaar Secure QR verification");
+        
+        let parser = self.aadhaar_parser()?;
+        
+        let verified_data = parser.parse_secure_qr(qr_payload)?;
+        
+        if !verified_data.signature_valid {
+            log_warn!("UIDAI signature verification failed", Field::public("source", "qr"));
+            return Err(ProofServiceError::UidaiSignatureInvalid);
+        }
+        Ok(verified_data)
+    }
+
+    /// Generate ZK proof from verified Aadhaar data
+    pub fn generate_aadhaar_proof(
+        &self,
+        verified_data_json: &str,
+        proof_type: u8, // Must list ProofSource::Aadhaar among its sources
         requirements_json: &str,
-    ) -> Result<String, JsValue> {
-        console::log_1(&"Generating ZK proof from Aadhaar data...".into());
+        secrets: &HolderSecrets,
+    ) -> Result<String, ProofServiceError> {
+        log_info!("Generating ZK proof from Aadhaar data", Field::public("proof_type", proof_type));
         
         let aadhaar_data: VerifiedAadhaarData = serde_json::from_str(verified_data_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid Aadhaar data: {}", e)))?;
+            .map_err(|source| ProofServiceError::InvalidInput { input: "Aadhaar data", source })?;
         
-        let challenge: ProofChallenge = serde_json::from_str(requirements_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid requirements: {}", e)))?;
+        let requirements: ProofRequirements = serde_json::from_str(requirements_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "requirements", source })?;
         
         // Convert Aadhaar data to circuit inputs
-        let birth_numeric = aadhaar_data.birth_date_numeric()
-            .map_err(|e| JsValue::from_str(&format!("Date parsing failed: {}", e)))?;
-        
-        let id_data = IDData {
-            birthdate: birth_numeric as u64,
-            nationality: 356, // India ISO 3166-1 numeric code
-            residency_code: aadhaar_data.address.state_code() as u64,
-            document_hash: self.hash_aadhaar_data(&aadhaar_data),
-            credit_score: 0, // Not applicable for Aadhaar
-        };
+        let id_data = self.aadhaar_id_data(&aadhaar_data)?;
         
         // Generate proof using circuit
-        let id_data_json = serde_json::to_string(&id_data)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-        
-        let challenge_json = serde_json::to_string(&challenge)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-        
-        let proof_json = self.generate_proof(&id_data_json, &challenge_json, proof_type).await?;
+        let challenge = ProofChallenge::new(&requirements, secrets);
+        let proof_json = self.prove(&id_data, &challenge, proof_type, ProofSource::Aadhaar, &RequestBinding::default())?;
         
-        console::log_1(&"✅ ZK proof generated successfully".into());
+        log_info!("ZK proof generated", Field::public("proof_type", proof_type));
         
         Ok(proof_json)
     }
 
+    /// Prove the holder knows the mobile/email registered with UIDAI without revealing it,
+    /// answering a `ProofType::Contact` request. The offline e-KYC file is verified here, and its
+    /// signed contact hash is folded into the proven document hash, so the proof stands for that
+    /// document only; the verifier checks it with `verify_contact_response`, given `contact_blinding`.
+    pub fn generate_contact_proof(
+        &self,
+        zip_file_data: &[u8],
+        share_code: &str,
+        contact_kind: &str, // "mobile" or "email"
+        contact: &str,
+        request_json: &str,
+        secrets: &HolderSecrets,
+    ) -> Result<String, ProofServiceError> {
+        let kind = parse_contact_kind(contact_kind)?;
+        let request = self.contact_request(request_json)?;
+        if self.now_millis() > request.expires_at {
+            return Err(ProofServiceError::RequestExpired(request.expires_at));
+        }
+
+        let aadhaar_data = self.aadhaar_parser()?.parse_aadhaar_xml(zip_file_data, share_code)?;
+        if !aadhaar_data.signature_valid {
+            log_warn!("UIDAI signature verification failed", Field::public("source", "contact"));
+            return Err(ProofServiceError::UidaiSignatureInvalid);
+        }
+        let matches = match kind {
+            ContactKind::Mobile => aadhaar_data.verify_mobile(contact, share_code)?,
+            ContactKind::Email => aadhaar_data.verify_email(contact, share_code)?,
+        };
+        if !matches {
+            return Err(AadhaarVerificationError::ContactMismatch.into());
+        }
+
+        let mut id_data = self.aadhaar_id_data(&aadhaar_data)?;
+        id_data.document_hash = contact_document_hash(&aadhaar_data, kind, id_data.document_hash)?;
+        let binding = RequestBinding::with_contact(&request, kind, contact, secrets.contact_blinding(request.nonce))?;
+        let challenge = ProofChallenge::new(&request.requirements, secrets);
+        self.prove(&id_data, &challenge, request.proof_type, ProofSource::Aadhaar, &binding)
+    }
+
+    /// Holder side: the blinding of a contact proof answering `request_json`. Send it to the
+    /// requesting verifier alone, never with the proof, since it opens the contact commitment.
+    pub fn contact_blinding(&self, request_json: &str, secrets: &HolderSecrets) -> Result<String, ProofServiceError> {
+        let request = self.contact_request(request_json)?;
+        Ok(secrets.contact_blinding(request.nonce).to_hex())
+    }
+
     /// Validate Aadhaar number using Verhoeff algorithm
-    #[wasm_bindgen]
     pub fn validate_aadhaar_number(&self, aadhaar: &str) -> bool {
         crate::aadhaar_xml_parser::validate_aadhaar_number(aadhaar)
     }
 
-    /// Calculate age from Aadhaar date of birth
-    #[wasm_bindgen]
-    pub fn calculate_age_from_dob(&self, dob_ddmmyyyy: &str, current_date_yyyymmdd: u32) -> Result<u32, JsValue> {
-        // Parse DD-MM-YYYY format
-        let parts: Vec<&str> = dob_ddmmyyyy.split('-').collect();
-        if parts.len() != 3 {
-            return Err(JsValue::from_str("Invalid date format. Expected DD-MM-YYYY"));
-        }
-        
-        let day: u32 = parts[0].parse()
-            .map_err(|_| JsValue::from_str("Invalid day"))?;
-        let month: u32 = parts[1].parse()
-            .map_err(|_| JsValue::from_str("Invalid month"))?;
-        let year: u32 = parts[2].parse()
-            .map_err(|_| JsValue::from_str("Invalid year"))?;
-        
-        let birth_numeric = year * 10000 + month * 100 + day;
-        
-        let birth_year = birth_numeric / 10000;
-        let birth_month = (birth_numeric / 100) % 100;
-        let birth_day = birth_numeric % 100;
-        
-        let current_year = current_date_yyyymmdd / 10000;
-        let current_month = (current_date_yyyymmdd / 100) % 100;
-        let current_day = current_date_yyyymmdd % 100;
+    /// Calculate age from Aadhaar date of birth (DD-MM-YYYY, YYYY-MM-DD, DD/MM/YYYY or YYYY)
+    pub fn calculate_age_from_dob(&self, dob: &str, current_date_yyyymmdd: u32) -> Result<u32, ProofServiceError> {
+        let today = date_from_numeric(current_date_yyyymmdd)?;
         
-        let mut age = current_year - birth_year;
-        
-        // Adjust if birthday hasn't occurred this year
-        if current_month < birth_month || (current_month == birth_month && current_day < birth_day) {
-            age -= 1;
-        }
-        
-        Ok(age)
+        Ok(BirthDate::parse(dob)?.age_on(today, YearOnlyMode::Strict)?)
+    }
+
+    fn aadhaar_id_data(&self, aadhaar_data: &VerifiedAadhaarData) -> Result<IDData, ProofServiceError> {
+        // A year-only record is proven as 31 December so a minimum age is never overstated
+        let birth_numeric = aadhaar_data.birth_date_numeric(YearOnlyMode::Latest)?;
+
+        Ok(IDData {
+            birthdate: birth_numeric as u64,
+            nationality: 356, // India ISO 3166-1 numeric code
+            residency_code: aadhaar_data.address.expose().state_code() as u64,
+            document_hash: self.hash_aadhaar_data(aadhaar_data),
+            credit_score: 0, // Not applicable for Aadhaar
+        })
     }
 
     // Private helper for hashing Aadhaar data
-    fn hash_aadhaar_data(&self, data: &VerifiedAadhaarData) -> String {
+    fn hash_aadhaar_data(&self, data: &VerifiedAadhaarData) -> FieldElement {
         use sha2::{Sha256, Digest};
         
         let combined = format!(
             "{}{}{}{}{}",
-            data.name,
-            data.date_of_birth,
+            data.name.expose(),
+            data.date_of_birth.expose(),
             data.gender,
-            data.address.full_address(),
+            data.address.expose().full_address(),
             data.reference_id
         );
         
         let mut hasher = Sha256::new();
         hasher.update(combined.as_bytes());
-        let result = hasher.finalize();
-        
-        format!("0x{}", hex::encode(result))
+        FieldElement::from_hash(&hasher.finalize())
+    }
+}
+
+/// JavaScript surface: thin wrappers over the native API above, keeping the original export names
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl P2PProofService {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new() -> P2PProofService {
+        Self::new()
+    }
+
+    #[wasm_bindgen(getter, js_name = passkey_bound)]
+    pub fn js_passkey_bound(&self) -> bool {
+        self.passkey_bound()
+    }
+
+    #[wasm_bindgen(js_name = initialize)]
+    pub async fn js_initialize(&mut self) -> Result<(), JsValue> {
+        self.initialize();
+        Ok(())
+    }
+
+    /// Registered proof types as JSON, so frontends need not hard-code ids
+    #[wasm_bindgen(js_name = proof_types)]
+    pub fn js_proof_types(&self) -> Result<String, JsValue> {
+        let specs: Vec<&ProofTypeSpec> = self.proof_types.specs().collect();
+        serde_json::to_string(&specs).map_err(|e| ProofServiceError::Serialization(e).into())
+    }
+
+    /// Load a registry frontier serialized as JSON
+    #[wasm_bindgen(js_name = set_registry_frontier)]
+    pub fn js_set_registry_frontier(&mut self, frontier_json: &str) -> Result<(), JsValue> {
+        let frontier = serde_json::from_str(frontier_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "registry frontier", source })?;
+        self.set_registry_frontier(frontier);
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter, js_name = registry_root)]
+    pub fn js_registry_root(&self) -> String {
+        self.registry_root().to_hex()
+    }
+
+    /// Keep spent nullifiers in the named IndexedDB database instead of memory
+    #[wasm_bindgen(js_name = use_indexed_db_nullifier_store)]
+    pub async fn js_use_indexed_db_nullifier_store(&mut self, database: &str) -> Result<(), JsValue> {
+        let store = Arc::new(IndexedDbNullifierStore::open(database).await.map_err(ProofServiceError::from)?);
+        self.set_nullifier_store(store.clone());
+        self.indexed_db = Some(store);
+        Ok(())
+    }
+
+    /// Set the replay scope from JSON, e.g. `{"epoch": 3, "ttl_millis": 86400000}`
+    #[wasm_bindgen(js_name = set_nullifier_policy)]
+    pub fn js_set_nullifier_policy(&mut self, policy_json: &str) -> Result<(), JsValue> {
+        let policy = serde_json::from_str(policy_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "nullifier policy", source })?;
+        self.set_nullifier_policy(policy);
+        Ok(())
+    }
+
+    /// Spent nullifiers as a JSON array of records
+    #[wasm_bindgen(js_name = export_nullifiers)]
+    pub fn js_export_nullifiers(&self) -> Result<String, JsValue> {
+        let records = self.nullifiers.export().map_err(ProofServiceError::from)?;
+        serde_json::to_string(&records).map_err(|e| ProofServiceError::Serialization(e).into())
+    }
+
+    #[wasm_bindgen(js_name = import_nullifiers)]
+    pub async fn js_import_nullifiers(&self, records_json: &str) -> Result<(), JsValue> {
+        let records: Vec<NullifierRecord> = serde_json::from_str(records_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "nullifier records", source })?;
+        self.nullifiers.import(records).map_err(ProofServiceError::from)?;
+        Ok(self.nullifiers_committed().await?)
+    }
+
+    #[wasm_bindgen(js_name = prune_nullifiers)]
+    pub async fn js_prune_nullifiers(&self) -> Result<usize, JsValue> {
+        let removed = self.prune_nullifiers()?;
+        self.nullifiers_committed().await?;
+        Ok(removed)
+    }
+
+    /// Without `secrets_json`, `requirements_json` is read as a combined challenge holding the secret
+    #[wasm_bindgen(js_name = generate_proof)]
+    pub async fn js_generate_proof(
+        &self,
+        id_data_json: &str,
+        requirements_json: &str,
+        proof_type: u8,
+        secrets_json: Option<String>,
+    ) -> Result<String, JsValue> {
+        let secrets = holder_secrets(secrets_json.as_deref(), requirements_json)?;
+        self.generate_proof(id_data_json, requirements_json, &secrets, proof_type).map_err(JsValue::from)
+    }
+
+    /// Set from JSON, e.g. `{"id": "patricon.id", "origins": ["https://patricon.id"]}`
+    #[wasm_bindgen(js_name = set_relying_party)]
+    pub fn js_set_relying_party(&mut self, relying_party_json: &str) -> Result<(), JsValue> {
+        let relying_party = serde_json::from_str(relying_party_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "relying party", source })?;
+        self.set_relying_party(relying_party);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = begin_passkey_binding)]
+    pub fn js_begin_passkey_binding(&mut self, commitment: &str) -> Result<String, JsValue> {
+        self.begin_passkey_binding(commitment).map_err(JsValue::from)
+    }
+
+    /// `registration_json` holds the attestationObject and clientDataJSON, base64url-encoded;
+    /// returns the binding as JSON, to be kept for `import_passkey_bindings`
+    #[wasm_bindgen(js_name = bind_passkey)]
+    pub fn js_bind_passkey(&mut self, commitment: &str, registration_json: &str) -> Result<String, JsValue> {
+        let registration: PasskeyRegistration = serde_json::from_str(registration_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "passkey registration", source })?;
+        let binding = self.bind_passkey(commitment, &registration)?;
+        serde_json::to_string(&binding).map_err(|e| ProofServiceError::Serialization(e).into())
+    }
+
+    #[wasm_bindgen(js_name = export_passkey_bindings)]
+    pub fn js_export_passkey_bindings(&self) -> Result<String, JsValue> {
+        let bindings: Vec<&PasskeyBinding> = self.passkey_bindings().collect();
+        serde_json::to_string(&bindings).map_err(|e| ProofServiceError::Serialization(e).into())
+    }
+
+    #[wasm_bindgen(js_name = import_passkey_bindings)]
+    pub fn js_import_passkey_bindings(&mut self, bindings_json: &str) -> Result<(), JsValue> {
+        let bindings: Vec<PasskeyBinding> = serde_json::from_str(bindings_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "passkey bindings", source })?;
+        // All or nothing: one rejected binding leaves the ones held before untouched
+        let previous = self.passkeys.clone();
+        for binding in bindings {
+            if let Err(error) = self.restore_passkey_binding(binding) {
+                self.passkeys = previous;
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = passkey_challenge)]
+    pub fn js_passkey_challenge(&self, proof_json: &str) -> Result<String, JsValue> {
+        self.passkey_challenge(proof_json).map_err(JsValue::from)
+    }
+
+    /// `assertion_json` holds the credential's rawId and response buffers, base64url-encoded
+    #[wasm_bindgen(js_name = attach_passkey_assertion)]
+    pub fn js_attach_passkey_assertion(&self, proof_json: &str, assertion_json: &str) -> Result<String, JsValue> {
+        let assertion: PasskeyAssertion = serde_json::from_str(assertion_json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "passkey assertion", source })?;
+        self.attach_passkey_assertion(proof_json, &assertion).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = verify_proof)]
+    pub async fn js_verify_proof(&self, proof_json: &str, passkey_public_key: &str) -> Result<bool, JsValue> {
+        let verified = self.verify_proof(proof_json, passkey_public_key)?;
+        self.nullifiers_committed().await?;
+        Ok(verified)
+    }
+
+    #[wasm_bindgen(js_name = create_request)]
+    pub fn js_create_request(
+        &self,
+        proof_type: u8,
+        requirements_json: &str,
+        verifier_address: &str,
+        ttl_millis: u32,
+    ) -> Result<String, JsValue> {
+        self.create_request(proof_type, requirements_json, verifier_address, ttl_millis.into()).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = respond_to_request)]
+    pub async fn js_respond_to_request(
+        &self,
+        id_data_json: &str,
+        request_json: &str,
+        secrets_json: &str,
+    ) -> Result<String, JsValue> {
+        let secrets = holder_secrets(Some(secrets_json), request_json)?;
+        self.respond_to_request(id_data_json, request_json, &secrets).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = verify_response)]
+    pub async fn js_verify_response(
+        &self,
+        request_json: &str,
+        response_json: &str,
+        passkey_public_key: &str,
+    ) -> Result<bool, JsValue> {
+        let verified = self.verify_response(request_json, response_json, passkey_public_key)?;
+        self.nullifiers_committed().await?;
+        Ok(verified)
+    }
+
+    #[wasm_bindgen(js_name = encode_proof)]
+    pub fn js_encode_proof(&self, proof_json: &str) -> Result<Vec<u8>, JsValue> {
+        self.encode_proof(proof_json).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = decode_proof)]
+    pub fn js_decode_proof(&self, envelope: &[u8]) -> Result<String, JsValue> {
+        self.decode_proof(envelope).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = decode_proof_uri)]
+    pub fn js_decode_proof_uri(&self, uri: &str) -> Result<String, JsValue> {
+        self.decode_proof_uri(uri).map_err(JsValue::from)
+    }
+
+    /// `{ json_bytes, envelope_bytes, qr_uri_bytes, fits_qr }` as JSON
+    #[wasm_bindgen(js_name = proof_size)]
+    pub fn js_proof_size(&self, proof_json: &str) -> Result<String, JsValue> {
+        let size = self.proof_size(proof_json)?;
+        serde_json::to_string(&size).map_err(|e| ProofServiceError::Serialization(e).into())
+    }
+
+    #[wasm_bindgen(js_name = send_proof_p2p)]
+    pub async fn js_send_proof_p2p(&self, proof_json: &str, channel: &str, recipient: &str) -> Result<String, JsValue> {
+        self.send_proof_p2p(proof_json, channel, recipient).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = verify_aadhaar_xml)]
+    pub async fn js_verify_aadhaar_xml(&self, zip_file_data: &[u8], share_code: &str) -> Result<String, JsValue> {
+        self.verify_aadhaar_xml(zip_file_data, share_code).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = verify_aadhaar_qr)]
+    pub async fn js_verify_aadhaar_qr(&self, qr_payload: &str) -> Result<String, JsValue> {
+        self.verify_aadhaar_qr(qr_payload).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = export_aadhaar_xml)]
+    pub async fn js_export_aadhaar_xml(&self, zip_file_data: &[u8], share_code: &str) -> Result<String, JsValue> {
+        self.export_aadhaar_xml(zip_file_data, share_code).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = export_aadhaar_qr)]
+    pub async fn js_export_aadhaar_qr(&self, qr_payload: &str) -> Result<String, JsValue> {
+        self.export_aadhaar_qr(qr_payload).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = generate_aadhaar_proof)]
+    pub async fn js_generate_aadhaar_proof(
+        &self,
+        verified_data_json: &str,
+        proof_type: u8,
+        requirements_json: &str,
+        secrets_json: Option<String>,
+    ) -> Result<String, JsValue> {
+        let secrets = holder_secrets(secrets_json.as_deref(), requirements_json)?;
+        self.generate_aadhaar_proof(verified_data_json, proof_type, requirements_json, &secrets).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = generate_contact_proof)]
+    pub async fn js_generate_contact_proof(
+        &self,
+        zip_file_data: &[u8],
+        share_code: &str,
+        contact_kind: &str,
+        contact: &str,
+        request_json: &str,
+        secrets_json: &str,
+    ) -> Result<String, JsValue> {
+        let secrets = holder_secrets(Some(secrets_json), request_json)?;
+        self.generate_contact_proof(zip_file_data, share_code, contact_kind, contact, request_json, &secrets)
+            .map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = contact_blinding)]
+    pub fn js_contact_blinding(&self, request_json: &str, secrets_json: &str) -> Result<String, JsValue> {
+        let secrets = holder_secrets(Some(secrets_json), request_json)?;
+        self.contact_blinding(request_json, &secrets).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = verify_contact_response)]
+    pub async fn js_verify_contact_response(
+        &self,
+        request_json: &str,
+        response_json: &str,
+        passkey_public_key: &str,
+        contact_kind: &str,
+        expected_contact: &str,
+        contact_blinding: &str,
+    ) -> Result<bool, JsValue> {
+        let verified = self.verify_contact_response(
+            request_json,
+            response_json,
+            passkey_public_key,
+            contact_kind,
+            expected_contact,
+            contact_blinding,
+        )?;
+        self.nullifiers_committed().await?;
+        Ok(verified)
+    }
+
+    /// Maximum age of accepted offline e-KYC files in days; `undefined` accepts any age
+    #[wasm_bindgen(js_name = set_freshness_policy)]
+    pub fn js_set_freshness_policy(&mut self, max_age_days: Option<u32>) {
+        self.set_freshness_policy(match max_age_days {
+            Some(days) => FreshnessPolicy::max_age_days(days.into()),
+            None => FreshnessPolicy::unrestricted(),
+        });
+    }
+
+    /// Maximum age of accepted Secure QR codes in days; `undefined`, the default, accepts any age
+    #[wasm_bindgen(js_name = set_qr_freshness_policy)]
+    pub fn js_set_qr_freshness_policy(&mut self, max_age_days: Option<u32>) {
+        self.set_qr_freshness_policy(match max_age_days {
+            Some(days) => FreshnessPolicy::max_age_days(days.into()),
+            None => FreshnessPolicy::unrestricted(),
+        });
+    }
+
+    #[wasm_bindgen(js_name = add_uidai_certificate)]
+    pub fn js_add_uidai_certificate(&mut self, certificate_pem: &str) -> Result<(), JsValue> {
+        self.add_uidai_certificate(certificate_pem).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = validate_aadhaar_number)]
+    pub fn js_validate_aadhaar_number(&self, aadhaar: &str) -> bool {
+        self.validate_aadhaar_number(aadhaar)
+    }
+
+    #[wasm_bindgen(js_name = calculate_age_from_dob)]
+    pub fn js_calculate_age_from_dob(&self, dob: &str, current_date_yyyymmdd: u32) -> Result<u32, JsValue> {
+        self.calculate_age_from_dob(dob, current_date_yyyymmdd).map_err(JsValue::from)
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl P2PProofService {
+    /// Wait for IndexedDB to commit the nullifiers written so far. A nullifier another tab stored
+    /// first means the proof was replayed there.
+    async fn nullifiers_committed(&self) -> Result<(), ProofServiceError> {
+        let Some(store) = &self.indexed_db else {
+            return Ok(());
+        };
+        match store.committed().await {
+            Err(NullifierStoreError::Conflict) => Err(ProofServiceError::NullifierReused),
+            result => Ok(result?),
+        }
+    }
+}
+
+/// Parse `secrets_json`, or fall back to the secret inside a pre-split combined challenge
+#[cfg(feature = "wasm")]
+fn holder_secrets(secrets_json: Option<&str>, combined_json: &str) -> Result<HolderSecrets, ProofServiceError> {
+    match secrets_json {
+        Some(json) => serde_json::from_str(json)
+            .map_err(|source| ProofServiceError::InvalidInput { input: "holder secrets", source }),
+        None => {
+            log_warn!("Reading the nullifier secret from a combined challenge; pass holder secrets separately");
+            let challenge: ProofChallenge = serde_json::from_str(combined_json)
+                .map_err(|source| ProofServiceError::InvalidInput { input: "challenge", source })?;
+            Ok(challenge.split().1)
+        }
+    }
+}
+
+fn parse_proof(proof_json: &str) -> Result<P2PProofResponse, ProofServiceError> {
+    serde_json::from_str(proof_json).map_err(|source| ProofServiceError::InvalidInput { input: "proof", source })
+}
+
+fn parse_contact_kind(contact_kind: &str) -> Result<ContactKind, ProofServiceError> {
+    ContactKind::from_name(contact_kind)
+        .ok_or_else(|| ProofServiceError::UnsupportedContactKind(contact_kind.to_string()))
+}
+
+fn parse_commitment(commitment: &str) -> Result<FieldElement, ProofServiceError> {
+    commitment.parse()
+        .map_err(|source| ProofServiceError::InvalidFieldElement { input: "commitment", source })
+}
+
+impl Default for P2PProofService {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[derive(Debug, Clone)]
 struct CircuitResult {
     proof: String,
+    #[allow(dead_code)] // Reported once the Barretenberg backend is wired in
     constraints: u32,
 }
 
-// Export for TypeScript bindings
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::rng::FixedRng;
+    use crate::webauthn::testing::SoftwarePasskey;
+    use chrono::{TimeZone, Utc};
+
+    fn service() -> P2PProofService {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2025, 10, 6, 12, 0, 0).unwrap());
+        let mut service = P2PProofService::with_platform(Arc::new(clock), Arc::new(FixedRng(0xAB)));
+        service.set_relying_party(SoftwarePasskey::relying_party());
+        service.initialize();
+        service
+    }
+
+    /// Commitment of the test identity under `secrets()`
+    const COMMITMENT: &str = "0x133956954dd83127fdea953d0e557014d1c6d99198bc91cb53eedd3f7efaab10";
+
+    /// Register `passkey` and bind it to the test identity, as the browser would after `navigator.credentials.create()`
+    fn bind(service: &mut P2PProofService, passkey: &SoftwarePasskey) -> PasskeyBinding {
+        let challenge = URL_SAFE_NO_PAD.decode(service.begin_passkey_binding(COMMITMENT).unwrap()).unwrap();
+        service.bind_passkey(COMMITMENT, &passkey.register(&challenge, "packed")).unwrap()
+    }
+
+    /// Sign a proof with the holder's passkey, as the browser would after `navigator.credentials.get()`
+    fn sign(service: &P2PProofService, proof_json: &str, passkey: &mut SoftwarePasskey) -> String {
+        let challenge = URL_SAFE_NO_PAD.decode(service.passkey_challenge(proof_json).unwrap()).unwrap();
+        service.attach_passkey_assertion(proof_json, &passkey.assert(&challenge)).unwrap()
+    }
+
+    fn requirements_json() -> String {
+        serde_json::to_string(&ProofRequirements {
+            current_date: 20251006,
+            min_age: 18,
+            required_nationality: 356,
+            required_residency: 0,
+            min_credit_score: 0,
+        }).unwrap()
+    }
+
+    fn secrets() -> HolderSecrets {
+        HolderSecrets::new(FieldElement::from_text("secret"))
+    }
+
+    fn id_data_json() -> String {
+        serde_json::to_string(&IDData {
+            birthdate: 19900815,
+            nationality: 356,
+            residency_code: 27,
+            document_hash: FieldElement::from_u64(0xabc),
+            credit_score: 0,
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_native_proof_round_trip() {
+        let mut service = service();
+        let id_data = id_data_json();
+
+        let mut passkey = SoftwarePasskey::new(1);
+        bind(&mut service, &passkey);
+        let proof_json = service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap();
+        let proof: P2PProofResponse = serde_json::from_str(&proof_json).unwrap();
+        assert_eq!(proof.timestamp, 1759752000000);
+        assert_eq!(proof.proof, format!("proof_1_{}", "ab".repeat(16)));
+        assert!(service.verify_proof(&sign(&service, &proof_json, &mut passkey), &passkey.encoded_public_key()).unwrap());
+
+        // Poseidon2 values the Noir circuit recomputes; the secret is SHA-256("secret") mod p
+        assert_eq!(proof.nullifier_hash, "0x0dc00f4afc47a98c718fd54b229844d52061a8aa9dbc71b8f4f7576477c20492");
+        assert_eq!(proof.commitment, COMMITMENT);
+        assert_eq!(proof.public_signals[1], proof.nullifier_hash);
+        assert_eq!(proof.public_signals[2], crate::merkle_tree::zero_hashes()[crate::merkle_tree::TREE_DEPTH].to_hex());
+
+        // Same injected clock and randomness, same proof
+        assert_eq!(service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap(), proof_json);
+        
+        let error = service.generate_proof("{}", &requirements_json(), &secrets(), 1).unwrap_err();
+        assert_eq!(error.code(), "INVALID_INPUT");
+        assert!(error.to_string().starts_with("Invalid ID data"));
+
+        let error = service.generate_proof(&id_data, &requirements_json(), &secrets(), 4).unwrap_err();
+        assert_eq!(error.code(), "INVALID_PROOF_REQUEST");
+        assert!(matches!(service.generate_proof(&id_data, &requirements_json(), &secrets(), 42), Err(ProofServiceError::UnknownProofType(42))));
+
+        // A proof whose signals do not match its type's layout is rejected
+        let mut tampered = proof.clone();
+        tampered.public_signals.pop();
+        let tampered = sign(&service, &serde_json::to_string(&tampered).unwrap(), &mut passkey);
+        assert!(!service.verify_proof(&tampered, &passkey.encoded_public_key()).unwrap());
+    }
+
+    #[test]
+    fn test_verified_proof_spends_nullifier() {
+        let mut service = service();
+        let id_data = id_data_json();
+        let proof_json = service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap();
+        let mut passkey = SoftwarePasskey::new(1);
+        let key = bind(&mut service, &passkey).public_key;
+
+        // A response whose nullifier differs from the proven signal spends nothing
+        let mut swapped: P2PProofResponse = serde_json::from_str(&proof_json).unwrap();
+        swapped.nullifier_hash = FieldElement::from_u64(1).to_hex();
+        let swapped_json = sign(&service, &serde_json::to_string(&swapped).unwrap(), &mut passkey);
+        assert!(!service.verify_proof(&swapped_json, &key).unwrap());
+
+        // Each presentation carries a fresh assertion, so the nullifier is what stops the replay
+        assert!(service.verify_proof(&sign(&service, &proof_json, &mut passkey), &key).unwrap());
+        let error = service.verify_proof(&sign(&service, &proof_json, &mut passkey), &key).unwrap_err();
+        assert_eq!((error.code(), error.category()), ("NULLIFIER_REUSED", ErrorCategory::Nullifier));
+
+        // Hex case does not make a second nullifier
+        let upper = proof_json.replace(&swapped.public_signals[1], &swapped.public_signals[1].to_uppercase().replacen("0X", "0x", 1));
+        assert!(matches!(service.verify_proof(&sign(&service, &upper, &mut passkey), &key), Err(ProofServiceError::NullifierReused)));
+
+        // The next epoch accepts it again; export carries the spent set to another verifier
+        service.set_nullifier_policy(NullifierPolicy { epoch: 1, ttl_millis: None });
+        assert!(service.verify_proof(&sign(&service, &proof_json, &mut passkey), &key).unwrap());
+        assert_eq!(service.prune_nullifiers().unwrap(), 1);
 
-#[macro_export]
-macro_rules! console_log {
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
-}
\ No newline at end of file
+        let mut other = self::service();
+        bind(&mut other, &passkey);
+        other.set_nullifier_policy(NullifierPolicy { epoch: 1, ttl_millis: None });
+        other.nullifier_store().import(service.nullifier_store().export().unwrap()).unwrap();
+        assert!(matches!(other.verify_proof(&sign(&other, &proof_json, &mut passkey), &key), Err(ProofServiceError::NullifierReused)));
+    }
+
+    #[test]
+    fn test_request_response_binding() {
+        let verifier = service();
+        let mut holder = service();
+        let id_data = id_data_json();
+        let address = format!("0x{}", "11".repeat(20));
+
+        let request_json = verifier.create_request(1, &requirements_json(), &address, 60_000).unwrap();
+        let request: P2PProofRequest = serde_json::from_str(&request_json).unwrap();
+        assert!(!request_json.contains("nullifier_secret"));
+        assert_eq!(request.expires_at, 1759752060000);
+
+        let mut passkey = SoftwarePasskey::new(1);
+        let key = bind(&mut holder, &passkey).public_key;
+        let response_json = sign(&holder, &holder.respond_to_request(&id_data, &request_json, &secrets()).unwrap(), &mut passkey);
+        let response: P2PProofResponse = serde_json::from_str(&response_json).unwrap();
+        assert_eq!(response.public_signals[4], FieldElement::from_address(&address).unwrap().to_hex());
+        assert_eq!(response.public_signals[5], request.nonce.to_hex());
+        assert_eq!(response.public_signals[7], request.requirements.hash().to_hex());
+
+        // Shown to another verifier, under another nonce or requirements, or outside the request flow, it fails
+        let other_verifier = P2PProofRequest { verifier_address: format!("0x{}", "22".repeat(20)), ..request.clone() };
+        let other_nonce = P2PProofRequest { nonce: FieldElement::from_u64(1), ..request.clone() };
+        let other_type = P2PProofRequest { proof_type: 3, ..request.clone() };
+        let other_requirements = P2PProofRequest {
+            requirements: ProofRequirements { min_age: 21, ..request.requirements.clone() },
+            ..request.clone()
+        };
+        for mismatched in [other_verifier, other_nonce, other_type, other_requirements] {
+            let mismatched = serde_json::to_string(&mismatched).unwrap();
+            assert!(!verifier.verify_response(&mismatched, &response_json, &key).unwrap());
+        }
+        assert!(!verifier.verify_proof(&response_json, &key).unwrap());
+
+        let later = FixedClock(Utc.with_ymd_and_hms(2025, 10, 6, 12, 1, 1).unwrap());
+        let late = P2PProofService::with_platform(Arc::new(later), Arc::new(FixedRng(0xAB)));
+        assert!(matches!(
+            late.verify_response(&request_json, &response_json, &key),
+            Err(ProofServiceError::RequestExpired(1759752060000))
+        ));
+        assert!(matches!(
+            late.respond_to_request(&id_data, &request_json, &secrets()),
+            Err(ProofServiceError::RequestExpired(1759752060000))
+        ));
+
+        assert!(verifier.verify_response(&request_json, &response_json, &key).unwrap());
+
+        let error = verifier.create_request(1, &requirements_json(), "verifier.example", 60_000).unwrap_err();
+        assert_eq!(error.code(), "INVALID_FIELD_ELEMENT");
+    }
+
+    #[test]
+    fn test_contact_proof_bound_to_signed_document() {
+        use crate::aadhaar_xml_parser::tests::{sign_test_document, zip_with_entries, TEST_CERT_PEM, TEST_KEY_PEM};
+        use crate::aadhaar_xml_parser::RSA_SHA256_SIGNATURE_METHOD;
+        use crate::contact_verification::uidai_contact_hash;
+        use crate::proof_type::ProofType;
+
+        // Reference ids start with 1234, so UIDAI hashes the mobile 4 times
+        let archive_for = |mobile: &str| {
+            let uid_data = format!(
+                concat!(
+                    r#"<UidData><Poi dob="15-08-1990" gender="M" m="{}" name="Test User"></Poi>"#,
+                    r#"<Poa dist="Mumbai" pc="400001" state="Maharashtra" vtc="Mumbai"></Poa></UidData>"#
+                ),
+                uidai_contact_hash(mobile, "1234", 4)
+            );
+            let xml = sign_test_document(&uid_data, RSA_SHA256_SIGNATURE_METHOD, TEST_KEY_PEM, "");
+            zip_with_entries(&[("kyc.xml", xml.as_bytes())])
+        };
+        let archive = archive_for("9876543210");
+        let verifier = service();
+        let mut holder = service();
+        let address = format!("0x{}", "11".repeat(20));
+        let request_json = verifier.create_request(ProofType::Contact.id(), &requirements_json(), &address, 60_000).unwrap();
+
+        // Only a fresh file signed by a trusted UIDAI certificate, registered to this contact, backs a proof
+        assert!(matches!(
+            holder.generate_contact_proof(&archive, "1234", "mobile", "9876543210", &request_json, &secrets()),
+            Err(ProofServiceError::Aadhaar(AadhaarVerificationError::NoTrustedCertificates))
+        ));
+        holder.add_uidai_certificate(TEST_CERT_PEM).unwrap();
+        assert!(matches!(
+            holder.generate_contact_proof(&archive, "1234", "mobile", "9876543210", &request_json, &secrets()),
+            Err(ProofServiceError::Aadhaar(AadhaarVerificationError::StaleDocument { max_age_days: 3, .. }))
+        ));
+        holder.set_freshness_policy(FreshnessPolicy::max_age_days(365)); // The test file dates from January
+        assert!(matches!(
+            holder.generate_contact_proof(&archive, "1234", "mobile", "9876543211", &request_json, &secrets()),
+            Err(ProofServiceError::Aadhaar(AadhaarVerificationError::ContactMismatch))
+        ));
+        let age_request = verifier.create_request(1, &requirements_json(), &address, 60_000).unwrap();
+        assert!(matches!(
+            holder.generate_contact_proof(&archive, "1234", "mobile", "9876543210", &age_request, &secrets()),
+            Err(ProofServiceError::InvalidProofRequest(ProofTypeError::MissingSignal { .. }))
+        ));
+
+        let mut passkey = SoftwarePasskey::new(1);
+        let key = bind(&mut holder, &passkey).public_key;
+        let proof_json = holder.generate_contact_proof(&archive, "1234", "mobile", "+91 98765 43210", &request_json, &secrets()).unwrap();
+        assert!(!proof_json.contains("9876543210"));
+        let response_json = sign(&holder, &proof_json, &mut passkey);
+
+        // The registered contact hash is part of the proven document, so another file proves another nullifier
+        let other_json = holder.generate_contact_proof(&archive_for("9876500000"), "1234", "mobile", "9876500000", &request_json, &secrets()).unwrap();
+        let other: P2PProofResponse = serde_json::from_str(&other_json).unwrap();
+        let response: P2PProofResponse = serde_json::from_str(&response_json).unwrap();
+        assert_ne!(other.nullifier_hash, response.nullifier_hash);
+        assert_eq!(other.commitment, response.commitment);
+
+        // It verifies only against the contact the verifier expects, never as a plain response
+        let blinding = holder.contact_blinding(&request_json, &secrets()).unwrap();
+        assert!(!response_json.contains(&blinding));
+        assert!(!verifier.verify_response(&request_json, &response_json, &key).unwrap());
+        assert!(!verifier.verify_contact_response(&request_json, &response_json, &key, "mobile", "9876543211", &blinding).unwrap());
+        assert!(!verifier.verify_contact_response(&request_json, &response_json, &key, "email", "9876543210", &blinding).unwrap());
+        assert!(matches!(
+            verifier.verify_contact_response(&age_request, &response_json, &key, "mobile", "9876543210", &blinding),
+            Err(ProofServiceError::InvalidProofRequest(ProofTypeError::MissingSignal { .. }))
+        ));
+
+        // Without the holder's blinding even the right contact does not open the commitment
+        let guess = FieldElement::default().to_hex();
+        assert!(!verifier.verify_contact_response(&request_json, &response_json, &key, "mobile", "9876543210", &guess).unwrap());
+        assert!(verifier.verify_contact_response(&request_json, &response_json, &key, "mobile", "9876543210", &blinding).unwrap());
+    }
+
+    #[test]
+    fn test_verified_aadhaar_redacted_unless_exported() {
+        use crate::aadhaar_xml_parser::tests::{sign_test_document, zip_with_entries, TEST_CERT_PEM, TEST_KEY_PEM};
+        use crate::aadhaar_xml_parser::{RedactedAadhaarData, RSA_SHA256_SIGNATURE_METHOD};
+        use crate::birth_date::AgeBracket;
+
+        let uid_data = concat!(
+            r#"<UidData><Poi dob="15-08-1990" gender="M" name="Test User"></Poi>"#,
+            r#"<Poa dist="Mumbai" pc="400001" state="Maharashtra" vtc="Mumbai"></Poa></UidData>"#
+        );
+        let xml = sign_test_document(uid_data, RSA_SHA256_SIGNATURE_METHOD, TEST_KEY_PEM, "");
+        let archive = zip_with_entries(&[("kyc.xml", xml.as_bytes())]);
+        let mut service = service();
+        service.add_uidai_certificate(TEST_CERT_PEM).unwrap();
+        service.set_freshness_policy(FreshnessPolicy::max_age_days(365));
+
+        // What verification hands to JS carries no name, birth date or address
+        let verified_json = service.verify_aadhaar_xml(&archive, "1234").unwrap();
+        assert!(!verified_json.contains("Test User") && !verified_json.contains("15-08-1990") && !verified_json.contains("400001"));
+        let redacted: RedactedAadhaarData = serde_json::from_str(&verified_json).unwrap();
+        assert_eq!(redacted.age_bracket, Some(AgeBracket::From35To44));
+        assert_eq!(redacted.state_code, 27);
+
+        // The holder's device asks for the full data explicitly, and only to prove from it
+        let exported = service.export_aadhaar_xml(&archive, "1234").unwrap();
+        assert!(exported.contains("Test User"));
+        let proof_json = service.generate_aadhaar_proof(&exported, 1, &requirements_json(), &secrets()).unwrap();
+        assert!(!proof_json.contains("Test User"));
+    }
+
+    #[test]
+    fn test_secure_qr_not_held_to_ekyc_freshness() {
+        use crate::aadhaar_photo::tests::test_j2k;
+        use crate::aadhaar_qr::tests::{build_payload, V2_FIELDS};
+        use crate::aadhaar_xml_parser::tests::TEST_CERT_PEM;
+
+        // Generated on 2025-01-01, nine months before the service clock
+        let payload = build_payload(&V2_FIELDS, &[&test_j2k(120, 150), &[0xAA; 32], &[0xBB; 32]]);
+        let mut service = service();
+        service.add_uidai_certificate(TEST_CERT_PEM).unwrap();
+        service.verify_aadhaar_qr(&payload).unwrap();
+
+        // Verifiers who want recent QR codes opt in separately from the e-KYC policy
+        service.set_qr_freshness_policy(FreshnessPolicy::max_age_days(30));
+        assert!(matches!(
+            service.verify_aadhaar_qr(&payload),
+            Err(ProofServiceError::Aadhaar(AadhaarVerificationError::StaleDocument { max_age_days: 30, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_passkey_binds_proof_to_holder() {
+        let mut service = service();
+        let id_data = id_data_json();
+        let proof_json = service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap();
+        let mut holder = SoftwarePasskey::new(1);
+        let mut thief = SoftwarePasskey::new(2);
+
+        // Nothing can be signed before a passkey is bound to the commitment
+        assert!(!service.passkey_bound());
+        let error = service.passkey_challenge(&proof_json).unwrap_err();
+        assert_eq!((error.code(), error.numeric_code()), ("PASSKEY_NOT_BOUND", 3003));
+        let binding = bind(&mut service, &holder);
+        assert!(service.passkey_bound());
+        assert_eq!(binding.attestation, AttestationType::SelfAttestation);
+        assert_eq!(binding.public_key, holder.encoded_public_key());
+        assert_eq!(service.passkey_binding(&COMMITMENT.parse().unwrap()), Some(&binding));
+
+        // The holder's service will not attach another credential's assertion
+        let challenge = URL_SAFE_NO_PAD.decode(service.passkey_challenge(&proof_json).unwrap()).unwrap();
+        let error = service.attach_passkey_assertion(&proof_json, &thief.assert(&challenge)).unwrap_err();
+        assert_eq!((error.code(), error.numeric_code()), ("PASSKEY_BINDING_FAILED", 3004));
+
+        // Unsigned, forged in the old format, or signed by a passkey the thief bound on their own device: all rejected
+        let mut stolen = self::service();
+        bind(&mut stolen, &thief);
+        let forged = proof_json.replace("\"signature\":\"\"", "\"signature\":\"passkey_sig_cHJvb2Y\"");
+        assert_ne!(forged, proof_json);
+        for rejected in [proof_json.clone(), forged, sign(&stolen, &proof_json, &mut thief)] {
+            assert!(!service.verify_proof(&rejected, &holder.encoded_public_key()).unwrap());
+        }
+
+        // A captured assertion cannot be replayed: the counter must move forward
+        let signed = sign(&service, &proof_json, &mut holder);
+        let replayed = sign(&service, &proof_json, &mut SoftwarePasskey::new(1));
+        assert!(service.verify_proof(&signed, &holder.encoded_public_key()).unwrap());
+        assert!(!service.verify_proof(&replayed, &holder.encoded_public_key()).unwrap());
+
+        let error = service.verify_proof(&signed, "not a key").unwrap_err();
+        assert_eq!((error.code(), error.numeric_code()), ("PASSKEY_VERIFICATION_FAILED", 3002));
+        let unconfigured = P2PProofService::new();
+        assert!(matches!(
+            unconfigured.verify_proof(&signed, &holder.encoded_public_key()),
+            Err(ProofServiceError::SignatureFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_passkey_binding_ceremony() {
+        let mut service = service();
+        let holder = SoftwarePasskey::new(1);
+
+        // A registration must answer the challenge issued for that commitment, once
+        let error = service.bind_passkey(COMMITMENT, &holder.register(&[0; 32], "none")).unwrap_err();
+        assert_eq!(error.code(), "PASSKEY_BINDING_FAILED");
+        let challenge = URL_SAFE_NO_PAD.decode(service.begin_passkey_binding(COMMITMENT).unwrap()).unwrap();
+        assert!(matches!(
+            service.bind_passkey(COMMITMENT, &holder.register(&[0; 32], "none")),
+            Err(ProofServiceError::Passkey(WebAuthnError::ChallengeMismatch))
+        ));
+        let binding = service.bind_passkey(COMMITMENT, &holder.register(&challenge, "none")).unwrap();
+        assert_eq!(binding.attestation, AttestationType::None);
+        assert_eq!(binding.bound_at, 1759752000000);
+        assert!(service.bind_passkey(COMMITMENT, &holder.register(&challenge, "none")).is_err());
+
+        // One credential cannot vouch for two identities
+        let other = FieldElement::from_u64(7).to_hex();
+        let challenge = URL_SAFE_NO_PAD.decode(service.begin_passkey_binding(&other).unwrap()).unwrap();
+        let error = service.bind_passkey(&other, &holder.register(&challenge, "none")).unwrap_err();
+        assert!(matches!(error, ProofServiceError::PasskeyBinding(_)));
+
+        // Bindings survive a restart through export and restore
+        let mut restarted = self::service();
+        let exported = serde_json::to_string(&service.passkey_bindings().collect::<Vec<_>>()).unwrap();
+        for binding in serde_json::from_str::<Vec<PasskeyBinding>>(&exported).unwrap() {
+            restarted.restore_passkey_binding(binding).unwrap();
+        }
+        assert_eq!(restarted.passkey_binding(&binding.commitment), Some(&binding));
+
+        // ...but only with a usable key, and never moving a credential to another commitment
+        let garbled = PasskeyBinding { public_key: "AAAA".to_string(), ..binding.clone() };
+        assert_eq!(restarted.restore_passkey_binding(garbled).unwrap_err().numeric_code(), 3004);
+        let no_credential = PasskeyBinding { credential_id: String::new(), ..binding.clone() };
+        assert_eq!(restarted.restore_passkey_binding(no_credential).unwrap_err().numeric_code(), 3004);
+        let moved = PasskeyBinding { commitment: FieldElement::from_u64(7), ..binding.clone() };
+        assert!(matches!(restarted.restore_passkey_binding(moved), Err(ProofServiceError::PasskeyBinding(_))));
+        assert_eq!(restarted.passkey_bindings().count(), 1);
+        assert_eq!(service.begin_passkey_binding("not a field").unwrap_err().code(), "INVALID_FIELD_ELEMENT");
+    }
+
+    #[test]
+    fn test_combined_challenge_compatibility() {
+        let service = service();
+        let id_data = id_data_json();
+
+        // The pre-split challenge JSON splits into the same requirements and secrets
+        let combined = serde_json::to_string(&ProofChallenge::new(&serde_json::from_str(&requirements_json()).unwrap(), &secrets())).unwrap();
+        assert!(combined.contains("nullifier_secret"));
+        let (requirements, holder_secrets) = serde_json::from_str::<ProofChallenge>(&combined).unwrap().split();
+        assert_eq!(holder_secrets, secrets());
+        assert_eq!(
+            service.generate_proof(&id_data, &combined, &holder_secrets, 1).unwrap(),
+            service.generate_proof(&id_data, &serde_json::to_string(&requirements).unwrap(), &secrets(), 1).unwrap()
+        );
+
+        // A request serialized with the old `challenge` member still parses, dropping its secret
+        let legacy_request = format!(
+            r#"{{"proof_type":1,"challenge":{},"verifier_address":"0x{}","nonce":"0x01","expires_at":0}}"#,
+            combined, "11".repeat(20)
+        );
+        let request: P2PProofRequest = serde_json::from_str(&legacy_request).unwrap();
+        assert_eq!(request.requirements, requirements);
+        assert!(!serde_json::to_string(&request).unwrap().contains("nullifier_secret"));
+
+        assert_eq!(format!("{:?}", holder_secrets), "HolderSecrets([REDACTED])");
+        assert_eq!(HolderSecrets::generate(&FixedRng(0xAB)).nullifier_secret.to_hex(), format!("0x00{}", "ab".repeat(31)));
+    }
+
+    #[test]
+    fn test_registry_root_signal() {
+        let mut service = service();
+        let id_data = id_data_json();
+        let first: P2PProofResponse = serde_json::from_str(&service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap()).unwrap();
+
+        // Register the commitment, sync the holder's frontier, and prove again against the new root
+        let commitment: FieldElement = first.commitment.parse().unwrap();
+        let tree = crate::merkle_tree::MerkleTree::from_leaves([FieldElement::from_u64(7), commitment]).unwrap();
+        let frontier_json = serde_json::to_string(tree.frontier()).unwrap();
+        service.set_registry_frontier(serde_json::from_str(&frontier_json).unwrap());
+
+        let second: P2PProofResponse = serde_json::from_str(&service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap()).unwrap();
+        assert_eq!(second.public_signals[2], tree.root().to_hex());
+        assert!(tree.path(1).unwrap().verify(commitment, service.registry_root()));
+    }
+
+    #[test]
+    fn test_id_data_field_encoding() {
+        let json = |document_hash: &str| format!(
+            r#"{{"birthdate":19900815,"nationality":356,"residency_code":27,"document_hash":"{}","credit_score":0}}"#,
+            document_hash
+        );
+        let id_data: IDData = serde_json::from_str(&json("0xabc")).unwrap();
+        let inputs = id_data.to_noir_value();
+        assert_eq!(inputs["birthdate"], FieldElement::from_u64(19900815).to_hex());
+        assert_eq!(inputs["document_hash"], FieldElement::from_u64(0xabc).to_hex());
+
+        // A raw SHA-256 digest may exceed the modulus and must be encoded with FieldElement::from_hash first
+        assert!(serde_json::from_str::<IDData>(&json(&format!("0x{}", "ff".repeat(32)))).is_err());
+    }
+
+    #[test]
+    fn test_calculate_age_from_dob() {
+        let service = service();
+        assert_eq!(service.calculate_age_from_dob("15-08-1990", 20251006).unwrap(), 35);
+        assert!(matches!(
+            service.calculate_age_from_dob("1990", 20250601),
+            Err(ProofServiceError::InvalidDate(BirthDateError::AmbiguousAge))
+        ));
+    }
+
+    #[test]
+    fn test_error_codes_and_nested_source() {
+        let service = service();
+        let error = service.verify_aadhaar_xml(b"not a zip", "1234").unwrap_err();
+        assert_eq!(error.code(), "AADHAAR_VERIFICATION_FAILED");
+        assert_eq!(error.details(), ErrorDetails {
+            category: ErrorCategory::Aadhaar,
+            numeric_code: 6002,
+            source: Some("Invalid or corrupt ZIP file".to_string()),
+        });
+        assert!(matches!(error, ProofServiceError::Aadhaar(AadhaarVerificationError::InvalidZipFile)));
+
+        let error = service.send_proof_p2p("{}", "carrier-pigeon", "bob").unwrap_err();
+        assert_eq!((error.code(), error.category()), ("UNSUPPORTED_CHANNEL", ErrorCategory::Transport));
+        let error = service.decode_proof(&[0xa0]).unwrap_err();
+        assert_eq!((error.code(), error.numeric_code()), ("INVALID_PROOF_ENVELOPE", 1008));
+    }
+
+    #[test]
+    fn test_qr_transport_uses_envelope() {
+        let mut service = service();
+        let id_data = id_data_json();
+        let mut passkey = SoftwarePasskey::new(1);
+        let key = bind(&mut service, &passkey).public_key;
+        let proof_json = sign(&service, &service.generate_proof(&id_data, &requirements_json(), &secrets(), 1).unwrap(), &mut passkey);
+
+        let size = service.proof_size(&proof_json).unwrap();
+        assert!(size.fits_qr && size.envelope_bytes < size.json_bytes);
+        let qr_data = service.send_proof_p2p(&proof_json, "qr", "bob").unwrap();
+        assert_eq!(qr_data.len(), size.qr_uri_bytes);
+
+        // The scanned proof is the one sent, assertion included
+        let scanned = service.decode_proof_uri(&qr_data).unwrap();
+        assert_eq!(scanned, service.decode_proof(&service.encode_proof(&proof_json).unwrap()).unwrap());
+        assert!(service.verify_proof(&scanned, &key).unwrap());
+
+        let mut large: P2PProofResponse = serde_json::from_str(&proof_json).unwrap();
+        large.proof = format!("0x{}", "00".repeat(QR_CAPACITY));
+        let large = serde_json::to_string(&large).unwrap();
+        assert!(!service.proof_size(&large).unwrap().fits_qr);
+        let error = service.send_proof_p2p(&large, "qr", "bob").unwrap_err();
+        assert_eq!((error.code(), error.numeric_code()), ("PROOF_TOO_LARGE", 5003));
+    }
+}
@@ -0,0 +1,31 @@
+// Resource Limits for Untrusted Uploads
+// Bounds ZIP extraction and XML tree building so hostile files fail fast instead of exhausting memory
+
+/// Caps applied while unpacking and parsing offline e-KYC files and Secure QR payloads.
+/// Defaults leave ample headroom over genuine UIDAI downloads (tens of KB, one entry).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    pub max_compressed_size: u64,
+    pub max_uncompressed_size: u64,
+    pub max_compression_ratio: u64,
+    pub max_zip_entries: usize,
+    pub max_element_depth: usize,
+    pub max_attributes: usize,
+    pub max_text_length: usize,
+    pub max_qr_payload_length: usize, // Decimal digits; a version 40 QR code holds at most 7089
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_compressed_size: 1024 * 1024,
+            max_uncompressed_size: 4 * 1024 * 1024,
+            max_compression_ratio: 100,
+            max_zip_entries: 4,
+            max_element_depth: 32,
+            max_attributes: 32,
+            max_text_length: 1024 * 1024,
+            max_qr_payload_length: 8 * 1024,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// Poseidon Hashing over BN254
+// Poseidon2 and legacy Poseidon matching the Noir stdlib bit for bit, so hashes computed here satisfy the circuits
+
+use ark_ff::{AdditiveGroup, BigInteger, Field, PrimeField};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+use crate::field_element::FieldElement;
+
+pub use ark_bn254::Fr;
+
+const FULL_ROUNDS: usize = 8;
+const POSEIDON2_WIDTH: usize = 4;
+const POSEIDON2_RATE: usize = 3;
+const POSEIDON2_PARTIAL_ROUNDS: usize = 56;
+
+/// Legacy Poseidon partial rounds by state width 2..=17 (circomlib / `std::hash::poseidon::bn254`)
+const LEGACY_PARTIAL_ROUNDS: [usize; 16] = [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68];
+pub const MAX_LEGACY_INPUTS: usize = LEGACY_PARTIAL_ROUNDS.len();
+
+/// Poseidon2 internal matrix is diag(D) plus the all-ones matrix; D as published with barretenberg's t = 4 parameters
+const POSEIDON2_INTERNAL_DIAGONAL: [&str; POSEIDON2_WIDTH] = [
+    "10dc6e9c006ea38b04b1e03b4bd9490c0d03f98929ca1d7fb56821fd19d3b6e7",
+    "0c28145b6a44df3e0149b3d0a30b3bb599df9756d4dd9b84a86b38cfb45a740b",
+    "00544b8338791518b2c7645a50392798b21f75bb60e3596170067d00141cac15",
+    "222c01175718386f2e2e82eb122789e352e105a3b8fa852613bc534433ee428b",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PoseidonError {
+    #[error("Legacy Poseidon takes 1 to {MAX_LEGACY_INPUTS} inputs, got {0}")]
+    UnsupportedInputCount(usize),
+}
+
+/// `std::hash::poseidon2_hash`: sponge over the t = 4 permutation, domain-separated by input length
+pub fn poseidon2_hash(inputs: &[FieldElement]) -> FieldElement {
+    let mut state = [Fr::from(0u64); POSEIDON2_WIDTH];
+    state[POSEIDON2_RATE] = Fr::from(inputs.len() as u64) * Fr::from(1u128 << 64);
+
+    // Absorb rate-sized chunks; the last (possibly partial) chunk is absorbed on squeeze
+    let mut chunks = inputs.chunks(POSEIDON2_RATE).peekable();
+    while let Some(chunk) = chunks.next() {
+        for (lane, input) in state.iter_mut().zip(chunk) {
+            *lane += input.inner();
+        }
+        if chunks.peek().is_some() {
+            poseidon2_permutation(&mut state);
+        }
+    }
+    poseidon2_permutation(&mut state);
+    state[0].into()
+}
+
+/// Poseidon2 permutation for t = 4, x^5 S-box, 8 full and 56 partial rounds
+pub fn poseidon2_permutation(state: &mut [Fr; POSEIDON2_WIDTH]) {
+    let params = poseidon2_params();
+    let (first_full, rest) = params.round_constants.split_at(FULL_ROUNDS / 2 * POSEIDON2_WIDTH);
+    let (partial, last_full) = rest.split_at(POSEIDON2_PARTIAL_ROUNDS);
+
+    external_matrix(state);
+    for constants in first_full.chunks(POSEIDON2_WIDTH) {
+        poseidon2_full_round(state, constants);
+    }
+    for constant in partial {
+        state[0] = sbox(state[0] + constant);
+        let sum: Fr = state.iter().sum();
+        for (lane, diagonal) in state.iter_mut().zip(&params.internal_diagonal) {
+            *lane = *lane * diagonal + sum;
+        }
+    }
+    for constants in last_full.chunks(POSEIDON2_WIDTH) {
+        poseidon2_full_round(state, constants);
+    }
+}
+
+fn poseidon2_full_round(state: &mut [Fr; POSEIDON2_WIDTH], constants: &[Fr]) {
+    for (lane, constant) in state.iter_mut().zip(constants) {
+        *lane = sbox(*lane + constant);
+    }
+    external_matrix(state);
+}
+
+/// `std::hash::poseidon::bn254::hash_N` for N = `inputs.len()`
+pub fn poseidon_hash(inputs: &[FieldElement]) -> Result<FieldElement, PoseidonError> {
+    if inputs.is_empty() || inputs.len() > MAX_LEGACY_INPUTS {
+        return Err(PoseidonError::UnsupportedInputCount(inputs.len()));
+    }
+    let params = legacy_params(inputs.len() + 1);
+
+    let mut state = Vec::with_capacity(params.width);
+    state.push(Fr::from(0u64));
+    state.extend(inputs.iter().map(FieldElement::inner));
+
+    for (round, constants) in params.round_constants.chunks(params.width).enumerate() {
+        for (lane, constant) in state.iter_mut().zip(constants) {
+            *lane += constant;
+        }
+        if round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + params.partial_rounds {
+            state.iter_mut().for_each(|lane| *lane = sbox(*lane));
+        } else {
+            state[0] = sbox(state[0]);
+        }
+        state = params.mds.iter()
+            .map(|row| row.iter().zip(&state).map(|(entry, lane)| *entry * lane).sum())
+            .collect();
+    }
+    Ok(state[0].into())
+}
+
+fn sbox(x: Fr) -> Fr {
+    x.square().square() * x
+}
+
+/// Poseidon2 external layer: the 4x4 MDS [[5,7,1,3],[4,6,1,1],[1,3,5,7],[1,1,4,6]]
+fn external_matrix(state: &mut [Fr; POSEIDON2_WIDTH]) {
+    let [a, b, c, d] = *state;
+    let t0 = a + b;
+    let t1 = c + d;
+    let t2 = b.double() + t1;
+    let t3 = d.double() + t0;
+    let t4 = t1.double().double() + t3;
+    let t5 = t0.double().double() + t2;
+    let t6 = t3 + t5;
+    let t7 = t2 + t4;
+    *state = [t6, t5, t7, t4];
+}
+
+struct Poseidon2Params {
+    round_constants: Vec<Fr>, // 4 per full round, 1 per partial round
+    internal_diagonal: [Fr; POSEIDON2_WIDTH],
+}
+
+struct LegacyParams {
+    width: usize,
+    partial_rounds: usize,
+    round_constants: Vec<Fr>, // `width` per round
+    mds: Vec<Vec<Fr>>,
+}
+
+fn poseidon2_params() -> &'static Poseidon2Params {
+    static PARAMS: OnceLock<Poseidon2Params> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        let mut grain = Grain::new(POSEIDON2_WIDTH, POSEIDON2_PARTIAL_ROUNDS);
+        let count = FULL_ROUNDS * POSEIDON2_WIDTH + POSEIDON2_PARTIAL_ROUNDS;
+        Poseidon2Params {
+            round_constants: (0..count).map(|_| grain.field_element()).collect(),
+            internal_diagonal: POSEIDON2_INTERNAL_DIAGONAL.map(|hex| FieldElement::from_hex(hex).expect("valid diagonal constant").inner()),
+        }
+    })
+}
+
+fn legacy_params(width: usize) -> &'static LegacyParams {
+    static PARAMS: [OnceLock<LegacyParams>; MAX_LEGACY_INPUTS] = [const { OnceLock::new() }; MAX_LEGACY_INPUTS];
+    PARAMS[width - 2].get_or_init(|| {
+        let partial_rounds = LEGACY_PARTIAL_ROUNDS[width - 2];
+        let mut grain = Grain::new(width, partial_rounds);
+        let round_constants = (0..(FULL_ROUNDS + partial_rounds) * width)
+            .map(|_| grain.field_element())
+            .collect();
+
+        // Cauchy matrix 1 / (x_i + y_j) from the next 2t draws, reduced rather than resampled
+        let points: Vec<Fr> = (0..2 * width).map(|_| grain.reduced_element()).collect();
+        let (xs, ys) = points.split_at(width);
+        let mds = xs.iter()
+            .map(|x| ys.iter().map(|y| (*x + y).inverse().expect("Cauchy entries are non-zero")).collect())
+            .collect();
+
+        LegacyParams { width, partial_rounds, round_constants, mds }
+    })
+}
+
+/// Grain LFSR from the Poseidon reference scripts, seeded with the field and round parameters
+struct Grain {
+    state: u128, // 80 bits, oldest in bit 0
+}
+
+impl Grain {
+    fn new(width: usize, partial_rounds: usize) -> Self {
+        // Prime field, x^alpha S-box, 254-bit modulus, then the round shape and 30 padding ones
+        let seed = [(1, 2), (0, 4), (254, 12), (width, 12), (FULL_ROUNDS, 10), (partial_rounds, 10), ((1 << 30) - 1, 30)];
+        let mut state = 0u128;
+        let mut position = 0;
+        for (value, bits) in seed {
+            for bit in (0..bits).rev() {
+                state |= (((value >> bit) & 1) as u128) << position;
+                position += 1;
+            }
+        }
+
+        let mut grain = Self { state };
+        for _ in 0..160 {
+            grain.clock();
+        }
+        grain
+    }
+
+    fn clock(&mut self) -> bool {
+        let bit = [0, 13, 23, 38, 51, 62].iter().fold(0, |acc, tap| acc ^ ((self.state >> tap) & 1));
+        self.state = (self.state >> 1) | (bit << 79);
+        bit == 1
+    }
+
+    /// Self-shrinking output: emit the second bit of each pair whose first bit is set
+    fn bit(&mut self) -> bool {
+        loop {
+            let keep = self.clock();
+            let bit = self.clock();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    fn bytes_be(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for index in 2..256 {
+            if self.bit() {
+                bytes[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        bytes
+    }
+
+    /// Uniform element by rejection sampling 254-bit draws
+    fn field_element(&mut self) -> Fr {
+        loop {
+            let bytes = self.bytes_be();
+            let value = Fr::from_be_bytes_mod_order(&bytes);
+            if value.into_bigint().to_bytes_be() == bytes {
+                return value;
+            }
+        }
+    }
+
+    fn reduced_element(&mut self) -> Fr {
+        Fr::from_be_bytes_mod_order(&self.bytes_be())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(value: impl Into<FieldElement>) -> String {
+        value.into().to_hex()
+    }
+
+    fn fields(values: &[u64]) -> Vec<FieldElement> {
+        values.iter().map(|value| FieldElement::from_u64(*value)).collect()
+    }
+
+    #[test]
+    fn test_poseidon2_matches_barretenberg() {
+        let a = FieldElement::from_hash(&hex::decode("9a807b615c4d3e2fa0b1c2d3e4f56789fedcba9876543210abcdef0123456789").unwrap());
+        let mut state = [a.inner(); 4];
+        poseidon2_permutation(&mut state);
+        assert_eq!(state.map(hex), [
+            "0x2bf1eaf87f7d27e8dc4056e9af975985bccc89077a21891d6c7b6ccce0631f95",
+            "0x0c01fa1b8d0748becafbe452c0cb0231c38224ea824554c9362518eebdd5701f",
+            "0x018555a8eb50cf07f64b019ebaf3af3c925c93e631f3ecd455db07bbb52bbdd3",
+            "0x0cbea457c91c22c6c31fd89afd2541efc2edf31736b9f721e823b2165c90fd41",
+        ]);
+        assert_eq!(hex(poseidon2_hash(&[a; 4])), "0x2f43a0f83b51a6f5fc839dea0ecec74947637802a579fa9841930a25a0bcec11");
+        assert_ne!(poseidon2_hash(&fields(&[1, 2])), poseidon2_hash(&fields(&[1, 2, 0])));
+    }
+
+    #[test]
+    fn test_legacy_poseidon_matches_circomlib() {
+        assert_eq!(hex(poseidon_hash(&fields(&[1])).unwrap()), "0x29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133");
+        assert_eq!(hex(poseidon_hash(&fields(&[1, 2])).unwrap()), "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a");
+        assert_eq!(hex(poseidon_hash(&fields(&[1, 2, 3])).unwrap()), "0x0e7732d89e6939c0ff03d5e58dab6302f3230e269dc5b968f725df34ab36d732");
+        assert_eq!(hex(poseidon_hash(&fields(&[1, 2, 3, 4])).unwrap()), "0x299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465");
+        assert_eq!(poseidon_hash(&[]), Err(PoseidonError::UnsupportedInputCount(0)));
+        assert_eq!(poseidon_hash(&fields(&[0; 17])), Err(PoseidonError::UnsupportedInputCount(17)));
+    }
+}
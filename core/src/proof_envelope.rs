@@ -0,0 +1,268 @@
+// Binary Proof Envelope
+// Versioned CBOR form of a P2PProofResponse, small enough to fit a QR code where the JSON does not
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cbor::{self, CborError, CborValue};
+use crate::p2p_service::P2PProofResponse;
+use crate::webauthn::PasskeyAssertion;
+
+pub const ENVELOPE_VERSION: i128 = 1;
+
+/// Byte-mode capacity of a version 40 QR code at error correction level L
+pub const QR_CAPACITY: usize = 2953;
+
+/// Scheme and path of the link a QR code carries; the envelope follows as base64url
+pub const QR_URI_PREFIX: &str = "patricon://verify?proof=";
+
+// Map keys; a key's meaning never changes within a version
+const KEY_VERSION: i128 = 0;
+const KEY_PROOF: i128 = 1;
+const KEY_PUBLIC_SIGNALS: i128 = 2;
+const KEY_SIGNATURE: i128 = 3;
+const KEY_NULLIFIER_HASH: i128 = 4;
+const KEY_COMMITMENT: i128 = 5;
+const KEY_TIMESTAMP: i128 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EnvelopeError {
+    #[error("Invalid envelope CBOR: {0}")]
+    Cbor(#[from] CborError),
+    #[error("Unsupported envelope version {0}")]
+    UnsupportedVersion(i128),
+    #[error("Envelope is missing {0}")]
+    Missing(&'static str),
+    #[error("Envelope has a malformed {0}")]
+    Malformed(&'static str),
+    #[error("Unknown envelope key {0}")]
+    UnknownKey(i128),
+    #[error("Duplicate envelope key {0}")]
+    DuplicateKey(i128),
+    #[error("Not a {QR_URI_PREFIX} link")]
+    NotAProofUri,
+}
+
+/// Sizes of one proof in each form, to pick a transport before encoding it for one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSize {
+    pub json_bytes: usize,
+    pub envelope_bytes: usize,
+    pub qr_uri_bytes: usize, // `QR_URI_PREFIX` plus the base64url envelope
+    pub fits_qr: bool,
+}
+
+/// Encode a response. Hex strings become byte strings and the passkey assertion its four
+/// buffers; anything else stays text, so `decode` gives back exactly the same response.
+pub fn encode(response: &P2PProofResponse) -> Vec<u8> {
+    let signature = match PasskeyAssertion::from_compact(&response.signature) {
+        Ok(assertion) if assertion.to_compact() == response.signature => CborValue::Array(
+            [assertion.credential_id, assertion.authenticator_data, assertion.client_data_json, assertion.signature]
+                .into_iter()
+                .map(CborValue::Bytes)
+                .collect(),
+        ),
+        _ => CborValue::Text(response.signature.clone()),
+    };
+    CborValue::Map(vec![
+        (CborValue::Integer(KEY_VERSION), CborValue::Integer(ENVELOPE_VERSION)),
+        (CborValue::Integer(KEY_PROOF), pack(&response.proof)),
+        (CborValue::Integer(KEY_PUBLIC_SIGNALS), CborValue::Array(response.public_signals.iter().map(|signal| pack(signal)).collect())),
+        (CborValue::Integer(KEY_SIGNATURE), signature),
+        (CborValue::Integer(KEY_NULLIFIER_HASH), pack(&response.nullifier_hash)),
+        (CborValue::Integer(KEY_COMMITMENT), pack(&response.commitment)),
+        (CborValue::Integer(KEY_TIMESTAMP), CborValue::Integer(response.timestamp as i128)),
+    ])
+    .encode()
+}
+
+pub fn decode(envelope: &[u8]) -> Result<P2PProofResponse, EnvelopeError> {
+    let value = cbor::decode(envelope)?;
+    let entries = value.as_map().ok_or(EnvelopeError::Malformed("envelope"))?;
+    let mut seen = [false; KEY_TIMESTAMP as usize + 1];
+    for (key, _) in entries {
+        match key.as_integer() {
+            // `get` would silently take the first of two entries, so a second one is rejected
+            Some(key @ KEY_VERSION..=KEY_TIMESTAMP) if std::mem::replace(&mut seen[key as usize], true) => {
+                return Err(EnvelopeError::DuplicateKey(key));
+            }
+            Some(KEY_VERSION..=KEY_TIMESTAMP) => {}
+            Some(key) => return Err(EnvelopeError::UnknownKey(key)),
+            None => return Err(EnvelopeError::Malformed("key")),
+        }
+    }
+    let field = |key: i128, name: &'static str| value.get(key).ok_or(EnvelopeError::Missing(name));
+
+    let version = field(KEY_VERSION, "version")?.as_integer().ok_or(EnvelopeError::Malformed("version"))?;
+    if version != ENVELOPE_VERSION {
+        return Err(EnvelopeError::UnsupportedVersion(version));
+    }
+    let public_signals = field(KEY_PUBLIC_SIGNALS, "public signals")?
+        .as_array()
+        .ok_or(EnvelopeError::Malformed("public signals"))?
+        .iter()
+        .map(|signal| unpack(signal, "public signal"))
+        .collect::<Result<_, _>>()?;
+    let signature = match field(KEY_SIGNATURE, "signature")? {
+        CborValue::Text(text) => text.clone(),
+        CborValue::Array(parts) => {
+            let parts = parts.iter().map(|part| part.as_bytes().map(<[u8]>::to_vec)).collect::<Option<Vec<_>>>();
+            let [credential_id, authenticator_data, client_data_json, signature] = parts
+                .and_then(|parts| <[Vec<u8>; 4]>::try_from(parts).ok())
+                .ok_or(EnvelopeError::Malformed("signature"))?;
+            PasskeyAssertion { credential_id, authenticator_data, client_data_json, signature }.to_compact()
+        }
+        _ => return Err(EnvelopeError::Malformed("signature")),
+    };
+    let timestamp = field(KEY_TIMESTAMP, "timestamp")?
+        .as_integer()
+        .and_then(|timestamp| u64::try_from(timestamp).ok())
+        .ok_or(EnvelopeError::Malformed("timestamp"))?;
+
+    Ok(P2PProofResponse {
+        proof: unpack(field(KEY_PROOF, "proof")?, "proof")?,
+        public_signals,
+        signature,
+        nullifier_hash: unpack(field(KEY_NULLIFIER_HASH, "nullifier hash")?, "nullifier hash")?,
+        commitment: unpack(field(KEY_COMMITMENT, "commitment")?, "commitment")?,
+        timestamp,
+    })
+}
+
+pub fn measure(response: &P2PProofResponse) -> EnvelopeSize {
+    let envelope_bytes = encode(response).len();
+    let qr_uri_bytes = QR_URI_PREFIX.len() + base64_len(envelope_bytes);
+    EnvelopeSize {
+        json_bytes: serde_json::to_string(response).map_or(0, |json| json.len()),
+        envelope_bytes,
+        qr_uri_bytes,
+        fits_qr: qr_uri_bytes <= QR_CAPACITY,
+    }
+}
+
+pub fn to_qr_uri(envelope: &[u8]) -> String {
+    format!("{}{}", QR_URI_PREFIX, URL_SAFE_NO_PAD.encode(envelope))
+}
+
+pub fn from_qr_uri(uri: &str) -> Result<P2PProofResponse, EnvelopeError> {
+    let encoded = uri.strip_prefix(QR_URI_PREFIX).ok_or(EnvelopeError::NotAProofUri)?;
+    decode(&URL_SAFE_NO_PAD.decode(encoded).map_err(|_| EnvelopeError::Malformed("base64url"))?)
+}
+
+/// Unpadded base64 length of `bytes` bytes
+fn base64_len(bytes: usize) -> usize {
+    (bytes * 4).div_ceil(3)
+}
+
+/// Lowercase `0x` hex as a byte string, anything else as text
+fn pack(text: &str) -> CborValue {
+    match text.strip_prefix("0x").and_then(|digits| hex::decode(digits).ok()) {
+        Some(bytes) if format!("0x{}", hex::encode(&bytes)) == text => CborValue::Bytes(bytes),
+        _ => CborValue::Text(text.to_string()),
+    }
+}
+
+fn unpack(value: &CborValue, name: &'static str) -> Result<String, EnvelopeError> {
+    match value {
+        CborValue::Bytes(bytes) => Ok(format!("0x{}", hex::encode(bytes))),
+        CborValue::Text(text) => Ok(text.clone()),
+        _ => Err(EnvelopeError::Malformed(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> P2PProofResponse {
+        let assertion = PasskeyAssertion {
+            credential_id: vec![1; 16],
+            authenticator_data: vec![2; 37],
+            client_data_json: br#"{"type":"webauthn.get"}"#.to_vec(),
+            signature: vec![3; 71],
+        };
+        P2PProofResponse {
+            proof: format!("0x{}", "5a".repeat(1024)),
+            public_signals: vec![
+                "0x0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+                "0x0dc00f4afc47a98c718fd54b229844d52061a8aa9dbc71b8f4f7576477c20492".to_string(),
+                "42".to_string(),
+                "0xABCD".to_string(),
+            ],
+            signature: assertion.to_compact(),
+            nullifier_hash: "0x0dc00f4afc47a98c718fd54b229844d52061a8aa9dbc71b8f4f7576477c20492".to_string(),
+            commitment: "0x133956954dd83127fdea953d0e557014d1c6d99198bc91cb53eedd3f7efaab10".to_string(),
+            timestamp: 1759752000000,
+        }
+    }
+
+    fn assert_round_trip(response: &P2PProofResponse) {
+        assert_eq!(&decode(&encode(response)).unwrap(), response);
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let response = response();
+        assert_round_trip(&response);
+
+        // Hex with leading zeros, odd length or upper case, and non-assertion signatures survive too
+        let mut unusual = response.clone();
+        unusual.proof = "proof_1_abab".to_string();
+        unusual.public_signals = vec!["0x0".to_string(), "0x".to_string(), "0x00ff".to_string()];
+        unusual.signature = String::new();
+        assert_round_trip(&unusual);
+        unusual.signature = "passkey_sig_cHJvb2Y".to_string();
+        assert_round_trip(&unusual);
+        unusual.signature = "AQ.Ag.Aw.BA".to_string();
+        assert_round_trip(&unusual);
+
+        // Field elements take 33 bytes instead of 66 characters
+        let value = cbor::decode(&encode(&response)).unwrap();
+        assert_eq!(value.get(KEY_COMMITMENT).and_then(CborValue::as_bytes).map(<[u8]>::len), Some(32));
+        assert_eq!(value.get(KEY_PUBLIC_SIGNALS).unwrap().as_array().unwrap()[3], CborValue::Text("0xABCD".to_string()));
+
+        let uri = to_qr_uri(&encode(&response));
+        assert!(uri.starts_with(QR_URI_PREFIX));
+        assert_eq!(from_qr_uri(&uri).unwrap().proof, response.proof);
+    }
+
+    #[test]
+    fn test_size_reported_before_sending() {
+        let mut response = response();
+        let size = measure(&response);
+        assert_eq!(size.envelope_bytes, encode(&response).len());
+        assert_eq!(size.qr_uri_bytes, to_qr_uri(&encode(&response)).len());
+        assert!(size.envelope_bytes * 10 < size.json_bytes * 6);
+        assert!(size.fits_qr);
+
+        response.proof.push_str(&"5a".repeat(1500));
+        assert!(!measure(&response).fits_qr);
+    }
+
+    #[test]
+    fn test_rejects_other_versions_and_shapes() {
+        let CborValue::Map(mut entries) = cbor::decode(&encode(&response())).unwrap() else { unreachable!() };
+        let decode_entries = |entries: &[(CborValue, CborValue)]| decode(&CborValue::Map(entries.to_vec()).encode());
+        entries[0].1 = CborValue::Integer(2);
+        assert_eq!(decode_entries(&entries), Err(EnvelopeError::UnsupportedVersion(2)));
+
+        entries[0].1 = CborValue::Integer(ENVELOPE_VERSION);
+        entries.push((CborValue::Integer(7), CborValue::Null));
+        assert_eq!(decode_entries(&entries), Err(EnvelopeError::UnknownKey(7)));
+
+        entries.pop();
+        entries.push((CborValue::Integer(KEY_COMMITMENT), entries[5].1.clone()));
+        assert_eq!(decode_entries(&entries), Err(EnvelopeError::DuplicateKey(KEY_COMMITMENT)));
+
+        entries.pop();
+        entries.remove(6);
+        assert_eq!(decode_entries(&entries), Err(EnvelopeError::Missing("timestamp")));
+
+        entries.push((CborValue::Integer(KEY_TIMESTAMP), CborValue::Integer(-1)));
+        assert_eq!(decode_entries(&entries), Err(EnvelopeError::Malformed("timestamp")));
+
+        assert_eq!(decode(&[0xa0, 0x00]), Err(EnvelopeError::Cbor(CborError::TrailingBytes(1))));
+        assert_eq!(from_qr_uri("https://patricon.id"), Err(EnvelopeError::NotAProofUri));
+    }
+}
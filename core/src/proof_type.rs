@@ -0,0 +1,353 @@
+// Proof Type Registry
+// Single source of truth for what each proof type proves, which circuit runs it and what it publishes
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+use crate::p2p_service::IDData;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ProofTypeError {
+    #[error("Unknown proof type: {0}")]
+    UnknownProofType(u8),
+    #[error("Proof type {0} is already registered")]
+    DuplicateProofType(u8),
+    #[error("Proof type {proof_type} cannot be generated from {data_source:?} data")]
+    UnsupportedSource { proof_type: String, data_source: ProofSource },
+    #[error("Proof type {proof_type} requires {field}")]
+    MissingField { proof_type: String, field: IdField },
+    #[error("Proof type {proof_type} does not publish {signal:?}")]
+    MissingSignal { proof_type: String, signal: PublicSignal },
+    #[error("Invalid proof type definition: {0}")]
+    InvalidSpec(String),
+}
+
+/// Built-in proof types; the discriminant is the id carried in requests and public signals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum ProofType {
+    Age = 1,
+    Residency = 2,
+    Nationality = 3,
+    Credit = 4,
+    Composite = 5, // Age, nationality and residency together
+    Contact = 6,   // Knowledge of the mobile/email registered with UIDAI
+}
+
+/// `IDData` inputs a circuit consumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdField {
+    Birthdate,
+    Nationality,
+    ResidencyCode,
+    DocumentHash,
+    CreditScore,
+}
+
+/// Entries of `P2PProofResponse::public_signals`, in layout order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicSignal {
+    ProofType,
+    NullifierHash,
+    MerkleRoot,
+    Commitment,
+    VerifierAddress, // From the P2PProofRequest the proof answers; zero when made without one
+    VerifierNonce,
+    ExpiresAt,
+    RequirementsHash, // See `ProofRequirements::hash`; checked against the request by `verify_response`
+    ContactCommitment, // See `contact_verification::contact_commitment`; bound to the request nonce
+}
+
+impl PublicSignal {
+    /// Signals that tie a proof to one verifier's request
+    pub fn is_request_binding(self) -> bool {
+        matches!(
+            self,
+            PublicSignal::VerifierAddress
+                | PublicSignal::VerifierNonce
+                | PublicSignal::ExpiresAt
+                | PublicSignal::ContactCommitment
+        )
+    }
+}
+
+/// Where the `IDData` for a proof comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofSource {
+    Aadhaar, // UIDAI-signed offline e-KYC or Secure QR
+    Direct,  // Caller-supplied IDData, e.g. manual entry
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofTypeSpec {
+    pub id: u8,
+    pub name: String,
+    pub circuit_artifact: String,
+    pub required_fields: Vec<IdField>,
+    pub public_signals: Vec<PublicSignal>, // Must start with ProofType so verifiers can look the spec up
+    pub sources: Vec<ProofSource>,
+}
+
+const STANDARD_SIGNALS: [PublicSignal; 8] = [
+    PublicSignal::ProofType,
+    PublicSignal::NullifierHash,
+    PublicSignal::MerkleRoot,
+    PublicSignal::Commitment,
+    PublicSignal::VerifierAddress,
+    PublicSignal::VerifierNonce,
+    PublicSignal::ExpiresAt,
+    PublicSignal::RequirementsHash,
+];
+
+impl ProofType {
+    pub const ALL: [ProofType; 6] = [
+        ProofType::Age,
+        ProofType::Residency,
+        ProofType::Nationality,
+        ProofType::Credit,
+        ProofType::Composite,
+        ProofType::Contact,
+    ];
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|proof_type| proof_type.id() == id)
+    }
+
+    pub fn spec(self) -> ProofTypeSpec {
+        let (name, circuit_artifact, required_fields, sources): (&str, &str, &[IdField], &[ProofSource]) = match self {
+            ProofType::Age => (
+                "age",
+                "age_proof_circuit.acir",
+                &[IdField::Birthdate],
+                &[ProofSource::Aadhaar, ProofSource::Direct],
+            ),
+            ProofType::Residency => (
+                "residency",
+                "residency_proof_circuit.acir",
+                &[IdField::ResidencyCode],
+                &[ProofSource::Aadhaar, ProofSource::Direct],
+            ),
+            ProofType::Nationality => (
+                "nationality",
+                "nationality_proof_circuit.acir",
+                &[IdField::Nationality],
+                &[ProofSource::Aadhaar, ProofSource::Direct],
+            ),
+            ProofType::Credit => (
+                "credit",
+                "credit_proof_circuit.acir",
+                &[IdField::CreditScore],
+                &[ProofSource::Direct],
+            ),
+            ProofType::Composite => (
+                "composite",
+                "composite_proof_circuit.acir",
+                &[IdField::Birthdate, IdField::Nationality, IdField::ResidencyCode],
+                &[ProofSource::Aadhaar, ProofSource::Direct],
+            ),
+            ProofType::Contact => (
+                "contact",
+                "contact_proof_circuit.acir",
+                &[IdField::DocumentHash],
+                &[ProofSource::Aadhaar],
+            ),
+        };
+        let mut public_signals = STANDARD_SIGNALS.to_vec();
+        if self == ProofType::Contact {
+            public_signals.push(PublicSignal::ContactCommitment);
+        }
+
+        ProofTypeSpec {
+            id: self.id(),
+            name: name.to_string(),
+            circuit_artifact: circuit_artifact.to_string(),
+            required_fields: required_fields.to_vec(),
+            public_signals,
+            sources: sources.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<u8> for ProofType {
+    type Error = ProofTypeError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Self::from_id(id).ok_or(ProofTypeError::UnknownProofType(id))
+    }
+}
+
+impl From<ProofType> for u8 {
+    fn from(proof_type: ProofType) -> Self {
+        proof_type.id()
+    }
+}
+
+impl fmt::Display for IdField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IdField::Birthdate => "birthdate",
+            IdField::Nationality => "nationality",
+            IdField::ResidencyCode => "residency_code",
+            IdField::DocumentHash => "document_hash",
+            IdField::CreditScore => "credit_score",
+        })
+    }
+}
+
+impl IdField {
+    /// Zero and empty values count as absent
+    pub fn is_present(self, id_data: &IDData) -> bool {
+        match self {
+            IdField::Birthdate => id_data.birthdate != 0,
+            IdField::Nationality => id_data.nationality != 0,
+            IdField::ResidencyCode => id_data.residency_code != 0,
+            IdField::DocumentHash => !id_data.document_hash.is_zero(),
+            IdField::CreditScore => id_data.credit_score != 0,
+        }
+    }
+}
+
+impl ProofTypeSpec {
+    fn label(&self) -> String {
+        format!("{} ({})", self.id, self.name)
+    }
+}
+
+/// Proof types a service accepts, keyed by id
+#[derive(Debug, Clone, Default)]
+pub struct ProofTypeRegistry {
+    specs: BTreeMap<u8, ProofTypeSpec>,
+}
+
+impl ProofTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_builtin_types() -> Self {
+        let mut registry = Self::new();
+        for proof_type in ProofType::ALL {
+            registry.specs.insert(proof_type.id(), proof_type.spec());
+        }
+        registry
+    }
+
+    pub fn register(&mut self, spec: ProofTypeSpec) -> Result<(), ProofTypeError> {
+        if self.specs.contains_key(&spec.id) {
+            return Err(ProofTypeError::DuplicateProofType(spec.id));
+        }
+        if spec.circuit_artifact.trim().is_empty() {
+            return Err(ProofTypeError::InvalidSpec("circuit artifact is empty".to_string()));
+        }
+        if spec.public_signals.first() != Some(&PublicSignal::ProofType) {
+            return Err(ProofTypeError::InvalidSpec("public signals must start with the proof type".to_string()));
+        }
+        if spec.sources.is_empty() {
+            return Err(ProofTypeError::InvalidSpec("no supported sources".to_string()));
+        }
+        self.specs.insert(spec.id, spec);
+        Ok(())
+    }
+
+    pub fn get(&self, id: u8) -> Result<&ProofTypeSpec, ProofTypeError> {
+        self.specs.get(&id).ok_or(ProofTypeError::UnknownProofType(id))
+    }
+
+    pub fn specs(&self) -> impl Iterator<Item = &ProofTypeSpec> {
+        self.specs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.specs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// Check a request against its spec before any circuit work is done
+    pub fn validate(&self, id: u8, source: ProofSource, id_data: &IDData) -> Result<&ProofTypeSpec, ProofTypeError> {
+        let spec = self.get(id)?;
+        if !spec.sources.contains(&source) {
+            return Err(ProofTypeError::UnsupportedSource { proof_type: spec.label(), data_source: source });
+        }
+        if let Some(field) = spec.required_fields.iter().find(|field| !field.is_present(id_data)) {
+            return Err(ProofTypeError::MissingField { proof_type: spec.label(), field: *field });
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field_element::FieldElement;
+
+    fn id_data(birthdate: u64, credit_score: u64) -> IDData {
+        IDData {
+            birthdate,
+            nationality: 356,
+            residency_code: 27,
+            document_hash: FieldElement::from_u64(0xabc),
+            credit_score,
+        }
+    }
+
+    #[test]
+    fn test_builtin_validation() {
+        let registry = ProofTypeRegistry::with_builtin_types();
+        assert_eq!(registry.len(), 6);
+
+        let spec = registry.validate(5, ProofSource::Aadhaar, &id_data(19900815, 0)).unwrap();
+        assert_eq!(spec.circuit_artifact, "composite_proof_circuit.acir");
+
+        assert_eq!(
+            registry.validate(1, ProofSource::Direct, &id_data(0, 0)),
+            Err(ProofTypeError::MissingField { proof_type: "1 (age)".to_string(), field: IdField::Birthdate })
+        );
+        assert!(matches!(
+            registry.validate(4, ProofSource::Aadhaar, &id_data(19900815, 700)),
+            Err(ProofTypeError::UnsupportedSource { data_source: ProofSource::Aadhaar, .. })
+        ));
+        assert_eq!(registry.validate(9, ProofSource::Direct, &id_data(19900815, 0)), Err(ProofTypeError::UnknownProofType(9)));
+
+        assert_eq!(serde_json::to_string(&ProofType::Composite).unwrap(), "5");
+        assert!(serde_json::from_str::<ProofType>("7").is_err());
+
+        // Contact proofs come only from signed documents and publish the contact commitment last
+        let contact = registry.get(ProofType::Contact.id()).unwrap();
+        assert_eq!(contact.public_signals.last(), Some(&PublicSignal::ContactCommitment));
+        assert!(matches!(
+            registry.validate(6, ProofSource::Direct, &id_data(19900815, 0)),
+            Err(ProofTypeError::UnsupportedSource { data_source: ProofSource::Direct, .. })
+        ));
+    }
+
+    #[test]
+    fn test_register_custom_type() {
+        let mut registry = ProofTypeRegistry::with_builtin_types();
+        let mut spec = ProofTypeSpec {
+            id: 7,
+            name: "student".to_string(),
+            circuit_artifact: "student_proof_circuit.acir".to_string(),
+            required_fields: vec![IdField::Birthdate, IdField::DocumentHash],
+            public_signals: vec![PublicSignal::Commitment],
+            sources: vec![ProofSource::Direct],
+        };
+        assert!(matches!(registry.register(spec.clone()), Err(ProofTypeError::InvalidSpec(_))));
+
+        spec.public_signals = vec![PublicSignal::ProofType, PublicSignal::Commitment];
+        registry.register(spec.clone()).unwrap();
+        assert_eq!(registry.register(spec), Err(ProofTypeError::DuplicateProofType(7)));
+        assert!(registry.validate(7, ProofSource::Direct, &id_data(19900815, 0)).is_ok());
+    }
+}
@@ -0,0 +1,34 @@
+// Injectable randomness source
+// Lets nonces and blinding values come from the OS (or crypto.getRandomValues) in production and be fixed in tests
+
+use std::sync::Arc;
+
+pub trait Rng: Send + Sync {
+    fn fill_bytes(&self, dest: &mut [u8]);
+}
+
+/// Operating-system CSPRNG (`crypto.getRandomValues` under WASM)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRng;
+
+impl Rng for SystemRng {
+    fn fill_bytes(&self, dest: &mut [u8]) {
+        getrandom::getrandom(dest).expect("system randomness is unavailable");
+    }
+}
+
+/// Fills every byte with the same value; for deterministic tests only
+#[derive(Debug, Clone, Copy)]
+pub struct FixedRng(pub u8);
+
+impl Rng for FixedRng {
+    fn fill_bytes(&self, dest: &mut [u8]) {
+        dest.fill(self.0);
+    }
+}
+
+impl<R: Rng + ?Sized> Rng for Arc<R> {
+    fn fill_bytes(&self, dest: &mut [u8]) {
+        (**self).fill_bytes(dest)
+    }
+}
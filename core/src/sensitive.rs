@@ -0,0 +1,134 @@
+// Sensitive Value Container
+// Wipes personal data on drop and keeps it out of logs and accidental serialization
+
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Holds PII. `Debug`/`Display` print a placeholder and the value is zeroized on drop.
+/// There is no `Serialize` impl: a field must opt in with `#[serde(with = "crate::sensitive::exposed")]`.
+pub struct Sensitive<T: Zeroize>(T);
+
+impl<T: Zeroize> Sensitive<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Access the raw value; call sites are the places PII actually flows
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    /// Move the value out, leaving a default behind to be wiped on drop
+    pub fn into_inner(mut self) -> T
+    where
+        T: Default,
+    {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl<T: Zeroize> Drop for Sensitive<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for Sensitive<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Sensitive<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Zeroize + PartialEq> PartialEq for Sensitive<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Zeroize + Default> Default for Sensitive<T> {
+    fn default() -> Self {
+        Self(T::default())
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T: Zeroize> fmt::Display for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+/// Explicit serde opt-in for fields that must cross a trust boundary in clear (e.g. to the holder's own UI)
+pub mod exposed {
+    use super::Sensitive;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use zeroize::Zeroize;
+
+    pub fn serialize<T, S>(value: &Sensitive<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Zeroize + Serialize,
+        S: Serializer,
+    {
+        value.expose().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Sensitive<T>, D::Error>
+    where
+        T: Zeroize + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Sensitive::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Record {
+        #[serde(with = "exposed")]
+        name: Sensitive<String>,
+    }
+
+    #[test]
+    fn test_redacted_formatting() {
+        let name = Sensitive::new("Test User".to_string());
+        assert_eq!(format!("{}", name), "[REDACTED]");
+        assert_eq!(format!("{:?}", Some(name.clone())), "Some([REDACTED])");
+        assert_eq!(name.expose(), "Test User");
+    }
+
+    #[test]
+    fn test_explicit_serialization() {
+        let record = Record { name: "Test User".to_string().into() };
+        assert_eq!(format!("{:?}", record), "Record { name: [REDACTED] }");
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"name":"Test User"}"#);
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap().name.expose(), "Test User");
+    }
+
+    #[test]
+    fn test_zeroize_clears_value() {
+        let mut name = Sensitive::new("Test User".to_string());
+        name.expose_mut().zeroize();
+        assert!(name.expose().is_empty());
+    }
+}
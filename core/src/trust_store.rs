@@ -0,0 +1,356 @@
+// UIDAI Trust Store
+// Holds the certificates allowed to sign Aadhaar documents and picks the one a document names
+
+use chrono::{DateTime, Utc};
+use rsa::RsaPublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::aadhaar_xml_parser::AadhaarVerificationError;
+use crate::clock::{Clock, SystemClock};
+
+/// A trusted signing certificate with its RSA key already extracted
+#[derive(Debug, Clone)]
+pub struct TrustedCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub serial_number: String, // Decimal, as written in XMLDSig X509SerialNumber
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    pub fingerprint_sha256: String, // Hex SHA-256 of the DER encoding
+    public_key: RsaPublicKey,
+}
+
+impl TrustedCertificate {
+    pub fn from_der(der: &[u8]) -> Result<Self, AadhaarVerificationError> {
+        use rsa::pkcs8::DecodePublicKey;
+        use x509_cert::der::{Decode, Encode};
+        use x509_cert::Certificate;
+
+        let certificate = Certificate::from_der(der)
+            .map_err(|e| AadhaarVerificationError::CertificateError(e.to_string()))?;
+        let tbs = &certificate.tbs_certificate;
+
+        let spki_der = tbs.subject_public_key_info.to_der()
+            .map_err(|e| AadhaarVerificationError::CertificateError(e.to_string()))?;
+        let public_key = RsaPublicKey::from_public_key_der(&spki_der)
+            .map_err(|e| AadhaarVerificationError::CertificateError(format!("Not an RSA key: {}", e)))?;
+
+        Ok(Self {
+            subject: tbs.subject.to_string(),
+            issuer: tbs.issuer.to_string(),
+            serial_number: rsa::BigUint::from_bytes_be(tbs.serial_number.as_bytes()).to_string(),
+            not_before: to_date_time(tbs.validity.not_before.to_unix_duration())?,
+            not_after: to_date_time(tbs.validity.not_after.to_unix_duration())?,
+            fingerprint_sha256: hex::encode(Sha256::digest(der)),
+            public_key,
+        })
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    pub fn is_valid_at(&self, instant: DateTime<Utc>) -> bool {
+        self.not_before <= instant && instant <= self.not_after
+    }
+
+    pub fn summary(&self) -> SignerCertificate {
+        SignerCertificate {
+            subject: self.subject.clone(),
+            serial_number: self.serial_number.clone(),
+            fingerprint_sha256: self.fingerprint_sha256.clone(),
+            not_after: self.not_after,
+        }
+    }
+}
+
+/// Identifies the certificate that verified a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignerCertificate {
+    pub subject: String,
+    pub serial_number: String,
+    pub fingerprint_sha256: String,
+    pub not_after: DateTime<Utc>,
+}
+
+/// Certificate hints from the XMLDSig `KeyInfo/X509Data` block
+#[derive(Debug, Clone, Default)]
+pub struct KeyInfoSelector {
+    pub certificate_der: Option<Vec<u8>>,
+    pub issuer_name: Option<String>,
+    pub serial_number: Option<String>,
+    pub subject_name: Option<String>,
+}
+
+impl KeyInfoSelector {
+    pub fn is_empty(&self) -> bool {
+        self.certificate_der.is_none()
+            && self.serial_number.is_none()
+            && self.subject_name.is_none()
+    }
+
+    fn matches(&self, certificate: &TrustedCertificate) -> bool {
+        // An embedded certificate is only a pointer into the store, never trusted on its own
+        if let Some(der) = &self.certificate_der {
+            if hex::encode(Sha256::digest(der)) != certificate.fingerprint_sha256 {
+                return false;
+            }
+        }
+        if let Some(serial_number) = &self.serial_number {
+            if serial_number.trim() != certificate.serial_number {
+                return false;
+            }
+            if let Some(issuer) = &self.issuer_name {
+                if normalize_dn(issuer) != normalize_dn(&certificate.issuer) {
+                    return false;
+                }
+            }
+        }
+        if let Some(subject) = &self.subject_name {
+            if normalize_dn(subject) != normalize_dn(&certificate.subject) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub struct TrustStore {
+    certificates: Vec<TrustedCertificate>,
+    clock: Box<dyn Clock>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::with_clock(Box::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
+        Self {
+            certificates: Vec::new(),
+            clock,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Add every certificate in `bytes`, which may be a PEM bundle or a single DER certificate
+    pub fn add_certificates(&mut self, bytes: &[u8]) -> Result<usize, AadhaarVerificationError> {
+        let trimmed = bytes.trim_ascii_start();
+        if trimmed.starts_with(b"-----BEGIN") {
+            self.add_pem(trimmed)
+        } else {
+            self.add_der(bytes).map(|_| 1)
+        }
+    }
+
+    /// Add all certificates from a PEM bundle
+    pub fn add_pem(&mut self, pem: &[u8]) -> Result<usize, AadhaarVerificationError> {
+        use x509_cert::der::Encode;
+        use x509_cert::Certificate;
+
+        let chain = Certificate::load_pem_chain(pem)
+            .map_err(|e| AadhaarVerificationError::CertificateError(e.to_string()))?;
+        if chain.is_empty() {
+            return Err(AadhaarVerificationError::CertificateError(
+                "No certificates found in PEM data".to_string()
+            ));
+        }
+
+        let count = chain.len();
+        for certificate in chain {
+            let der = certificate.to_der()
+                .map_err(|e| AadhaarVerificationError::CertificateError(e.to_string()))?;
+            self.add_der(&der)?;
+        }
+        Ok(count)
+    }
+
+    pub fn add_der(&mut self, der: &[u8]) -> Result<(), AadhaarVerificationError> {
+        let certificate = TrustedCertificate::from_der(der)?;
+        // Re-adding a certificate during rotation is a no-op
+        if !self.certificates.iter().any(|c| c.fingerprint_sha256 == certificate.fingerprint_sha256) {
+            self.certificates.push(certificate);
+        }
+        Ok(())
+    }
+
+    /// Drop a certificate by fingerprint, e.g. after UIDAI retires a signing key
+    pub fn remove(&mut self, fingerprint_sha256: &str) -> bool {
+        let before = self.certificates.len();
+        self.certificates.retain(|c| !c.fingerprint_sha256.eq_ignore_ascii_case(fingerprint_sha256));
+        self.certificates.len() != before
+    }
+
+    pub fn certificates(&self) -> &[TrustedCertificate] {
+        &self.certificates
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.certificates.is_empty()
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn is_currently_valid(&self, certificate: &TrustedCertificate) -> bool {
+        certificate.is_valid_at(self.clock.now())
+    }
+
+    /// Check an RSA PKCS#1 v1.5 SHA-256 signature against the selected certificates,
+    /// returning the one that produced it
+    pub fn verify_rsa_sha256(
+        &self,
+        selector: &KeyInfoSelector,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<Option<&TrustedCertificate>, AadhaarVerificationError> {
+        use rsa::pkcs1v15::{Signature, VerifyingKey};
+        use rsa::signature::Verifier;
+
+        let signature = Signature::try_from(signature)
+            .map_err(|_| AadhaarVerificationError::SignatureVerificationFailed)?;
+
+        if self.is_empty() {
+            return Err(AadhaarVerificationError::NoTrustedCertificates);
+        }
+
+        // Without KeyInfo hints any trusted certificate may have produced the signature
+        let candidates = self.select(selector);
+        if candidates.is_empty() {
+            return Err(AadhaarVerificationError::CertificateError(
+                "Document KeyInfo does not match any trusted certificate".to_string()
+            ));
+        }
+
+        for certificate in candidates {
+            let verifying_key = VerifyingKey::<Sha256>::new(certificate.public_key.clone());
+            if verifying_key.verify(message, &signature).is_ok() {
+                return Ok(Some(certificate));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Trusted certificates the document's KeyInfo could refer to, currently valid ones first
+    pub fn select(&self, selector: &KeyInfoSelector) -> Vec<&TrustedCertificate> {
+        let now = self.clock.now();
+        let mut candidates: Vec<&TrustedCertificate> = self.certificates
+            .iter()
+            .filter(|certificate| selector.matches(certificate))
+            .collect();
+        candidates.sort_by_key(|certificate| !certificate.is_valid_at(now));
+        candidates
+    }
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_date_time(since_epoch: std::time::Duration) -> Result<DateTime<Utc>, AadhaarVerificationError> {
+    DateTime::from_timestamp(since_epoch.as_secs() as i64, 0)
+        .ok_or_else(|| AadhaarVerificationError::CertificateError("Validity out of range".to_string()))
+}
+
+/// Order- and spacing-insensitive form of an RFC 2253/4514 distinguished name
+fn normalize_dn(name: &str) -> Vec<String> {
+    let mut components: Vec<String> = name
+        .split(',')
+        .map(|component| component.trim().to_lowercase().replace(" = ", "="))
+        .filter(|component| !component.is_empty())
+        .collect();
+    components.sort();
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use chrono::TimeZone;
+
+    const TEST_CERT_PEM: &str = include_str!("../testdata/test_uidai_cert.pem");
+    const TEST_CERT_DER: &[u8] = include_bytes!("../testdata/test_uidai_cert.der");
+    const EXPIRED_CERT_PEM: &str = include_str!("../testdata/test_uidai_expired_cert.pem");
+
+    fn store_at(year: i32) -> TrustStore {
+        TrustStore::with_clock(Box::new(FixedClock(Utc.with_ymd_and_hms(year, 6, 1, 0, 0, 0).unwrap())))
+    }
+
+    #[test]
+    fn test_load_pem_bundle_and_der() {
+        let mut store = store_at(2025);
+        let bundle = format!("{}\n{}", TEST_CERT_PEM, EXPIRED_CERT_PEM);
+        assert_eq!(store.add_certificates(bundle.as_bytes()).unwrap(), 2);
+
+        // The same certificate in DER form is recognised as a duplicate
+        assert_eq!(store.add_certificates(TEST_CERT_DER).unwrap(), 1);
+        assert_eq!(store.certificates().len(), 2);
+
+        assert!(store.add_certificates(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_validity_window() {
+        let mut store = store_at(2025);
+        store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        store.add_pem(EXPIRED_CERT_PEM.as_bytes()).unwrap();
+
+        let valid: Vec<bool> = store.certificates().iter().map(|c| store.is_currently_valid(c)).collect();
+        assert_eq!(valid, vec![true, false]);
+
+        let mut past_store = store_at(2016);
+        past_store.add_pem(EXPIRED_CERT_PEM.as_bytes()).unwrap();
+        assert!(past_store.is_currently_valid(&past_store.certificates()[0]));
+    }
+
+    #[test]
+    fn test_key_info_selection() {
+        let mut store = store_at(2025);
+        store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        store.add_pem(EXPIRED_CERT_PEM.as_bytes()).unwrap();
+        let current = store.certificates()[0].clone();
+
+        // No hints: every certificate is a candidate, valid ones first
+        let all = store.select(&KeyInfoSelector::default());
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].fingerprint_sha256, current.fingerprint_sha256);
+
+        let by_certificate = KeyInfoSelector {
+            certificate_der: Some(TEST_CERT_DER.to_vec()),
+            ..Default::default()
+        };
+        assert_eq!(store.select(&by_certificate).len(), 1);
+
+        let by_issuer_serial = KeyInfoSelector {
+            issuer_name: Some("C=IN, O=PatriconID Test, CN=Test UIDAI Signer".to_string()),
+            serial_number: Some(current.serial_number.clone()),
+            ..Default::default()
+        };
+        assert_eq!(store.select(&by_issuer_serial)[0].fingerprint_sha256, current.fingerprint_sha256);
+
+        let unknown = KeyInfoSelector {
+            subject_name: Some("CN=Someone Else".to_string()),
+            ..Default::default()
+        };
+        assert!(store.select(&unknown).is_empty());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut store = store_at(2025);
+        store.add_pem(TEST_CERT_PEM.as_bytes()).unwrap();
+        let fingerprint = store.certificates()[0].fingerprint_sha256.clone();
+
+        assert!(store.remove(&fingerprint.to_uppercase()));
+        assert!(store.is_empty());
+        assert!(!store.remove(&fingerprint));
+    }
+}
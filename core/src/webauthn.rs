@@ -0,0 +1,469 @@
+// WebAuthn Passkey Assertions
+// Verifies the passkey signature binding a proof to its holder: client data, authenticator data and COSE signature
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::cbor::{self, CborError, CborValue};
+use crate::cose::{CoseError, CoseKey};
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+const FLAG_EXTENSIONS: u8 = 0x80;
+
+const PROOF_CHALLENGE_DOMAIN: &[u8] = b"PatriconID/passkey/proof/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WebAuthnError {
+    #[error("Malformed {0}")]
+    Malformed(&'static str),
+    #[error("Malformed CBOR: {0}")]
+    Cbor(#[from] CborError),
+    #[error("{0}")]
+    Cose(#[from] CoseError),
+    #[error("Expected a {expected} ceremony, got {found}")]
+    WrongCeremony { expected: &'static str, found: String },
+    #[error("Client data was signed for a different challenge")]
+    ChallengeMismatch,
+    #[error("Origin {0} is not allowed for this relying party")]
+    OriginNotAllowed(String),
+    #[error("Cross-origin ceremonies are not accepted")]
+    CrossOrigin,
+    #[error("Authenticator data is scoped to a different relying party")]
+    RpIdMismatch,
+    #[error("User presence flag not set")]
+    UserNotPresent,
+    #[error("User verification required but not performed")]
+    UserNotVerified,
+    #[error("Unsupported attestation format: {0}")]
+    UnsupportedAttestation(String),
+    #[error("Invalid attestation: {0}")]
+    InvalidAttestation(&'static str),
+    #[error("Signature counter went from {stored} to {received}; the authenticator may be cloned")]
+    SignCountRegressed { stored: u32, received: u32 },
+}
+
+/// Where passkeys are scoped: the RP ID they were created for and the origins allowed to use them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelyingParty {
+    pub id: String,           // e.g. "patricon.id"
+    pub origins: Vec<String>, // e.g. ["https://patricon.id"]
+    #[serde(default = "require_user_verification")]
+    pub require_user_verification: bool, // Proofs stand for the holder, so biometrics/PIN by default
+}
+
+fn require_user_verification() -> bool {
+    true
+}
+
+impl RelyingParty {
+    pub fn new(id: &str, origins: &[&str]) -> Self {
+        Self {
+            id: id.to_string(),
+            origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            require_user_verification: true,
+        }
+    }
+
+    fn id_hash(&self) -> [u8; 32] {
+        Sha256::digest(self.id.as_bytes()).into()
+    }
+}
+
+/// Credential created during registration, present when the AT flag is set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestedCredential {
+    pub aaguid: [u8; 16],
+    pub credential_id: Vec<u8>,
+    pub public_key: CoseKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+    pub extensions: Option<CborValue>,
+}
+
+impl AuthenticatorData {
+    pub fn parse(bytes: &[u8]) -> Result<Self, WebAuthnError> {
+        let malformed = || WebAuthnError::Malformed("authenticator data");
+        let (header, mut rest) = bytes.split_first_chunk::<37>().ok_or_else(malformed)?;
+        let flags = header[32];
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            let (fixed, tail) = rest.split_first_chunk::<18>().ok_or_else(malformed)?;
+            let id_length = u16::from_be_bytes([fixed[16], fixed[17]]) as usize;
+            if tail.len() < id_length {
+                return Err(malformed());
+            }
+            let (credential_id, tail) = tail.split_at(id_length);
+            let (key, used) = cbor::decode_prefix(tail)?;
+            rest = &tail[used..];
+            Some(AttestedCredential {
+                aaguid: fixed[..16].try_into().expect("16 bytes"),
+                credential_id: credential_id.to_vec(),
+                public_key: CoseKey::from_value(&key)?,
+            })
+        } else {
+            None
+        };
+
+        let extensions = if flags & FLAG_EXTENSIONS != 0 {
+            let (extensions, used) = cbor::decode_prefix(rest)?;
+            rest = &rest[used..];
+            Some(extensions)
+        } else {
+            None
+        };
+        if !rest.is_empty() {
+            return Err(malformed());
+        }
+
+        Ok(Self {
+            rp_id_hash: header[..32].try_into().expect("32 bytes"),
+            flags,
+            sign_count: u32::from_be_bytes(header[33..37].try_into().expect("4 bytes")),
+            attested_credential,
+            extensions,
+        })
+    }
+
+    pub fn user_present(&self) -> bool {
+        self.flags & FLAG_USER_PRESENT != 0
+    }
+
+    pub fn user_verified(&self) -> bool {
+        self.flags & FLAG_USER_VERIFIED != 0
+    }
+}
+
+/// The members of clientDataJSON a relying party checks
+#[derive(Debug, Clone, Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    ceremony: String,
+    challenge: String, // base64url without padding
+    origin: String,
+    #[serde(default, rename = "crossOrigin")]
+    cross_origin: bool,
+}
+
+/// What `navigator.credentials.get()` returns, each buffer base64url-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasskeyAssertion {
+    #[serde(with = "base64url")]
+    pub credential_id: Vec<u8>,
+    #[serde(with = "base64url")]
+    pub authenticator_data: Vec<u8>,
+    #[serde(with = "base64url")]
+    pub client_data_json: Vec<u8>,
+    #[serde(with = "base64url")]
+    pub signature: Vec<u8>,
+}
+
+impl PasskeyAssertion {
+    /// The four buffers as dot-separated base64url, as carried in `P2PProofResponse.signature`
+    pub fn to_compact(&self) -> String {
+        [&self.credential_id, &self.authenticator_data, &self.client_data_json, &self.signature]
+            .map(|part| URL_SAFE_NO_PAD.encode(part))
+            .join(".")
+    }
+
+    pub fn from_compact(compact: &str) -> Result<Self, WebAuthnError> {
+        let parts = compact
+            .split('.')
+            .map(|part| URL_SAFE_NO_PAD.decode(part))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| WebAuthnError::Malformed("passkey assertion"))?;
+        let [credential_id, authenticator_data, client_data_json, signature] = <[Vec<u8>; 4]>::try_from(parts)
+            .map_err(|_| WebAuthnError::Malformed("passkey assertion"))?;
+        Ok(Self { credential_id, authenticator_data, client_data_json, signature })
+    }
+}
+
+/// Challenge a holder signs for a proof: a hash of the proof and its public signals, so the
+/// assertion cannot be moved to another proof or to the same proof claiming other signals
+pub fn proof_challenge(proof: &str, public_signals: &[String]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(PROOF_CHALLENGE_DOMAIN);
+    for part in std::iter::once(proof).chain(public_signals.iter().map(String::as_str)) {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// A credential public key as verifiers receive it: base64url of its COSE encoding
+pub fn decode_public_key(encoded: &str) -> Result<CoseKey, WebAuthnError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|_| WebAuthnError::Malformed("credential public key"))?;
+    Ok(CoseKey::from_cbor(&bytes)?)
+}
+
+pub fn encode_public_key(key: &CoseKey) -> String {
+    URL_SAFE_NO_PAD.encode(key.to_cbor())
+}
+
+/// Verify an assertion over `challenge` from the passkey holding `public_key`. `stored_sign_count`
+/// is the counter seen last for this credential; the returned data carries the new one.
+pub fn verify_assertion(
+    relying_party: &RelyingParty,
+    public_key: &CoseKey,
+    assertion: &PasskeyAssertion,
+    challenge: &[u8],
+    stored_sign_count: u32,
+) -> Result<AuthenticatorData, WebAuthnError> {
+    verify_client_data(relying_party, &assertion.client_data_json, "webauthn.get", challenge)?;
+    let authenticator_data = AuthenticatorData::parse(&assertion.authenticator_data)?;
+    check_authenticator_data(relying_party, &authenticator_data)?;
+
+    // Counters that are zero on both sides mean the authenticator does not keep one
+    let received = authenticator_data.sign_count;
+    if (received != 0 || stored_sign_count != 0) && received <= stored_sign_count {
+        return Err(WebAuthnError::SignCountRegressed { stored: stored_sign_count, received });
+    }
+
+    let mut signed = assertion.authenticator_data.clone();
+    signed.extend_from_slice(&Sha256::digest(&assertion.client_data_json));
+    public_key.verify(&signed, &assertion.signature)?;
+    Ok(authenticator_data)
+}
+
+/// Type, challenge, origin and crossOrigin of clientDataJSON; shared with registration
+pub(crate) fn verify_client_data(
+    relying_party: &RelyingParty,
+    client_data_json: &[u8],
+    ceremony: &'static str,
+    challenge: &[u8],
+) -> Result<(), WebAuthnError> {
+    let client_data: CollectedClientData = serde_json::from_slice(client_data_json)
+        .map_err(|_| WebAuthnError::Malformed("client data"))?;
+    if client_data.ceremony != ceremony {
+        return Err(WebAuthnError::WrongCeremony { expected: ceremony, found: client_data.ceremony });
+    }
+    if URL_SAFE_NO_PAD.decode(&client_data.challenge).ok().as_deref() != Some(challenge) {
+        return Err(WebAuthnError::ChallengeMismatch);
+    }
+    if !relying_party.origins.contains(&client_data.origin) {
+        return Err(WebAuthnError::OriginNotAllowed(client_data.origin));
+    }
+    if client_data.cross_origin {
+        return Err(WebAuthnError::CrossOrigin);
+    }
+    Ok(())
+}
+
+/// RP ID hash and the UP/UV flags; shared with registration
+pub(crate) fn check_authenticator_data(
+    relying_party: &RelyingParty,
+    authenticator_data: &AuthenticatorData,
+) -> Result<(), WebAuthnError> {
+    if authenticator_data.rp_id_hash != relying_party.id_hash() {
+        return Err(WebAuthnError::RpIdMismatch);
+    }
+    if !authenticator_data.user_present() {
+        return Err(WebAuthnError::UserNotPresent);
+    }
+    if relying_party.require_user_verification && !authenticator_data.user_verified() {
+        return Err(WebAuthnError::UserNotVerified);
+    }
+    Ok(())
+}
+
+pub(crate) mod base64url {
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    /// Accepts padded input too, as some encoders emit it
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        URL_SAFE_NO_PAD.decode(encoded.trim_end_matches('=')).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A software Ed25519 passkey standing in for an authenticator in tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use crate::attestation::PasskeyRegistration;
+    use crate::cose::{ed25519_sign, CoseAlgorithm};
+
+    pub const RP_ID: &str = "patricon.id";
+    pub const ORIGIN: &str = "https://patricon.id";
+
+    pub struct SoftwarePasskey {
+        seed: [u8; 32],
+        pub credential_id: Vec<u8>,
+        pub sign_count: u32,
+        pub flags: u8,
+        pub origin: String,
+    }
+
+    impl SoftwarePasskey {
+        pub fn new(seed: u8) -> Self {
+            Self {
+                seed: [seed; 32],
+                credential_id: vec![seed; 16],
+                sign_count: 0,
+                flags: FLAG_USER_PRESENT | FLAG_USER_VERIFIED,
+                origin: ORIGIN.to_string(),
+            }
+        }
+
+        pub fn relying_party() -> RelyingParty {
+            RelyingParty::new(RP_ID, &[ORIGIN])
+        }
+
+        pub fn public_key(&self) -> CoseKey {
+            CoseKey::EdDsa { x: ed25519_sign(&self.seed, &[]).0 }
+        }
+
+        pub fn encoded_public_key(&self) -> String {
+            encode_public_key(&self.public_key())
+        }
+
+        pub fn client_data(&self, ceremony: &str, challenge: &[u8]) -> Vec<u8> {
+            serde_json::json!({
+                "type": ceremony,
+                "challenge": URL_SAFE_NO_PAD.encode(challenge),
+                "origin": self.origin,
+                "crossOrigin": false,
+            })
+            .to_string()
+            .into_bytes()
+        }
+
+        pub fn sign(&self, authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+            let mut signed = authenticator_data.to_vec();
+            signed.extend_from_slice(&Sha256::digest(client_data_json));
+            ed25519_sign(&self.seed, &signed).1.to_vec()
+        }
+
+        /// Authenticator data of a registration, carrying the credential
+        pub fn attested_authenticator_data(&self, aaguid: [u8; 16]) -> Vec<u8> {
+            let mut authenticator_data = Sha256::digest(RP_ID).to_vec();
+            authenticator_data.push(self.flags | FLAG_ATTESTED_CREDENTIAL);
+            authenticator_data.extend_from_slice(&self.sign_count.to_be_bytes());
+            authenticator_data.extend_from_slice(&aaguid);
+            authenticator_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            authenticator_data.extend_from_slice(&self.credential_id);
+            authenticator_data.extend_from_slice(&self.public_key().to_cbor());
+            authenticator_data
+        }
+
+        /// Registration with "none" or self ("packed") attestation
+        pub fn register(&self, challenge: &[u8], format: &str) -> PasskeyRegistration {
+            let authenticator_data = self.attested_authenticator_data([0; 16]);
+            let client_data_json = self.client_data("webauthn.create", challenge);
+            let text = |text: &str| CborValue::Text(text.to_string());
+            let statement = match format {
+                "none" => Vec::new(),
+                _ => vec![
+                    (text("alg"), CborValue::Integer(CoseAlgorithm::EdDsa as i128)),
+                    (text("sig"), CborValue::Bytes(self.sign(&authenticator_data, &client_data_json))),
+                ],
+            };
+            let object = CborValue::Map(vec![
+                (text("fmt"), text(format)),
+                (text("attStmt"), CborValue::Map(statement)),
+                (text("authData"), CborValue::Bytes(authenticator_data)),
+            ]);
+            PasskeyRegistration { attestation_object: object.encode(), client_data_json }
+        }
+
+        pub fn assert(&mut self, challenge: &[u8]) -> PasskeyAssertion {
+            self.sign_count += 1;
+            let mut authenticator_data = Sha256::digest(RP_ID).to_vec();
+            authenticator_data.push(self.flags);
+            authenticator_data.extend_from_slice(&self.sign_count.to_be_bytes());
+            let client_data_json = self.client_data("webauthn.get", challenge);
+            let signature = self.sign(&authenticator_data, &client_data_json);
+            PasskeyAssertion { credential_id: self.credential_id.clone(), authenticator_data, client_data_json, signature }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::SoftwarePasskey;
+    use super::*;
+
+    #[test]
+    fn test_assertion_checks() {
+        let rp = SoftwarePasskey::relying_party();
+        let mut passkey = SoftwarePasskey::new(7);
+        let key = passkey.public_key();
+        let challenge = proof_challenge("proof_1_00", &["1".to_string()]);
+
+        let assertion = passkey.assert(&challenge);
+        let data = verify_assertion(&rp, &key, &assertion, &challenge, 0).unwrap();
+        assert_eq!(data.sign_count, 1);
+        assert!(data.user_verified());
+        assert_eq!(
+            verify_assertion(&rp, &key, &assertion, &challenge, 1),
+            Err(WebAuthnError::SignCountRegressed { stored: 1, received: 1 })
+        );
+
+        let other_challenge = proof_challenge("proof_1_00", &["2".to_string()]);
+        assert_eq!(verify_assertion(&rp, &key, &assertion, &other_challenge, 0), Err(WebAuthnError::ChallengeMismatch));
+        assert_eq!(
+            verify_assertion(&rp, &SoftwarePasskey::new(8).public_key(), &assertion, &challenge, 0),
+            Err(WebAuthnError::Cose(CoseError::InvalidSignature))
+        );
+        let mut tampered = assertion.clone();
+        tampered.authenticator_data[32] |= 0x02;
+        assert_eq!(verify_assertion(&rp, &key, &tampered, &challenge, 0), Err(WebAuthnError::Cose(CoseError::InvalidSignature)));
+
+        let other_rp = RelyingParty::new("evil.example", &[testing::ORIGIN]);
+        assert_eq!(verify_assertion(&other_rp, &key, &passkey.assert(&challenge), &challenge, 0), Err(WebAuthnError::RpIdMismatch));
+
+        passkey.origin = "https://evil.example".to_string();
+        assert_eq!(
+            verify_assertion(&rp, &key, &passkey.assert(&challenge), &challenge, 0),
+            Err(WebAuthnError::OriginNotAllowed("https://evil.example".to_string()))
+        );
+
+        let mut passkey = SoftwarePasskey::new(7);
+        passkey.flags = FLAG_USER_PRESENT;
+        assert_eq!(verify_assertion(&rp, &key, &passkey.assert(&challenge), &challenge, 0), Err(WebAuthnError::UserNotVerified));
+        let lenient = RelyingParty { require_user_verification: false, ..rp.clone() };
+        assert!(verify_assertion(&lenient, &key, &passkey.assert(&challenge), &challenge, 0).is_ok());
+    }
+
+    #[test]
+    fn test_encodings_round_trip() {
+        let mut passkey = SoftwarePasskey::new(3);
+        let assertion = passkey.assert(b"challenge");
+        assert_eq!(PasskeyAssertion::from_compact(&assertion.to_compact()).unwrap(), assertion);
+        assert_eq!(PasskeyAssertion::from_compact("a.b.c"), Err(WebAuthnError::Malformed("passkey assertion")));
+        let json = serde_json::to_string(&assertion).unwrap();
+        assert_eq!(serde_json::from_str::<PasskeyAssertion>(&json).unwrap(), assertion);
+
+        assert_eq!(decode_public_key(&passkey.encoded_public_key()).unwrap(), passkey.public_key());
+
+        // Attested credential data carries the COSE key, and nothing may follow it
+        let mut authenticator_data = assertion.authenticator_data.clone();
+        authenticator_data[32] |= FLAG_ATTESTED_CREDENTIAL;
+        authenticator_data.extend_from_slice(&[0; 16]);
+        authenticator_data.extend_from_slice(&[0, 2, 0xaa, 0xbb]);
+        authenticator_data.extend_from_slice(&passkey.public_key().to_cbor());
+        let parsed = AuthenticatorData::parse(&authenticator_data).unwrap();
+        let credential = parsed.attested_credential.unwrap();
+        assert_eq!(credential.credential_id, vec![0xaa, 0xbb]);
+        assert_eq!(credential.public_key, passkey.public_key());
+        authenticator_data.push(0);
+        assert_eq!(AuthenticatorData::parse(&authenticator_data), Err(WebAuthnError::Malformed("authenticator data")));
+        assert_eq!(AuthenticatorData::parse(&[0; 36]), Err(WebAuthnError::Malformed("authenticator data")));
+    }
+}
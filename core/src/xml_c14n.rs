@@ -0,0 +1,826 @@
+// XML Canonicalization (C14N 1.0 and Exclusive C14N 1.0)
+// Produces the exact octets that XMLDSig signs and digests in UIDAI offline e-KYC documents
+
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::parse_limits::ParseLimits;
+
+pub const C14N_INCLUSIVE: &str = "http://www.w3.org/TR/2001/REC-xml-c14n-20010315";
+pub const C14N_INCLUSIVE_WITH_COMMENTS: &str =
+    "http://www.w3.org/TR/2001/REC-xml-c14n-20010315#WithComments";
+pub const C14N_EXCLUSIVE: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+pub const C14N_EXCLUSIVE_WITH_COMMENTS: &str = "http://www.w3.org/2001/10/xml-exc-c14n#WithComments";
+pub const ENVELOPED_SIGNATURE_TRANSFORM: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+pub const XMLDSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+#[derive(Debug, Error)]
+pub enum CanonicalizationError {
+    #[error("XML parsing failed: {0}")]
+    ParseError(String),
+    #[error("Unsupported canonicalization algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Document type declarations are not allowed")]
+    DoctypeNotAllowed,
+    #[error("Document is larger than {0} bytes")]
+    DocumentTooLarge(u64),
+    #[error("Element nesting deeper than {0} levels")]
+    DepthExceeded(usize),
+    #[error("Element <{element}> has more than {limit} attributes")]
+    TooManyAttributes { element: String, limit: usize },
+    #[error("Text node longer than {0} bytes")]
+    TextTooLong(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalizationMethod {
+    Inclusive,
+    InclusiveWithComments,
+    Exclusive,
+    ExclusiveWithComments,
+}
+
+impl CanonicalizationMethod {
+    pub fn from_uri(uri: &str) -> Result<Self, CanonicalizationError> {
+        match uri {
+            C14N_INCLUSIVE => Ok(Self::Inclusive),
+            C14N_INCLUSIVE_WITH_COMMENTS => Ok(Self::InclusiveWithComments),
+            C14N_EXCLUSIVE => Ok(Self::Exclusive),
+            C14N_EXCLUSIVE_WITH_COMMENTS => Ok(Self::ExclusiveWithComments),
+            _ => Err(CanonicalizationError::UnsupportedAlgorithm(uri.to_string())),
+        }
+    }
+
+    pub fn uri(&self) -> &'static str {
+        match self {
+            Self::Inclusive => C14N_INCLUSIVE,
+            Self::InclusiveWithComments => C14N_INCLUSIVE_WITH_COMMENTS,
+            Self::Exclusive => C14N_EXCLUSIVE,
+            Self::ExclusiveWithComments => C14N_EXCLUSIVE_WITH_COMMENTS,
+        }
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        matches!(self, Self::Exclusive | Self::ExclusiveWithComments)
+    }
+
+    pub fn with_comments(&self) -> bool {
+        matches!(self, Self::InclusiveWithComments | Self::ExclusiveWithComments)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlAttribute {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub namespace: Option<String>,
+    pub value: String,
+}
+
+impl XmlAttribute {
+    fn qualified_name(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.local_name),
+            None => self.local_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+    Comment(String),
+    ProcessingInstruction { target: String, data: Option<String> },
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlElement {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub namespace: Option<String>,
+    pub attributes: Vec<XmlAttribute>,
+    pub children: Vec<XmlNode>,
+    in_scope_namespaces: BTreeMap<String, String>, // "" is the default namespace
+    inherited_xml_attributes: Vec<XmlAttribute>,   // xml:* attributes of ancestors
+}
+
+impl XmlElement {
+    pub fn qualified_name(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.local_name),
+            None => self.local_name.clone(),
+        }
+    }
+
+    /// Value of an attribute without a namespace
+    pub fn attribute(&self, local_name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.namespace.is_none() && attr.local_name == local_name)
+            .map(|attr| attr.value.as_str())
+    }
+
+    pub fn child_elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|node| match node {
+            XmlNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// First direct child with the given namespace and local name
+    pub fn find_child(&self, namespace: &str, local_name: &str) -> Option<&XmlElement> {
+        self.child_elements()
+            .find(|child| child.is(namespace, local_name))
+    }
+
+    /// First descendant (depth-first, document order) with the given namespace and local name
+    pub fn find_descendant(&self, namespace: &str, local_name: &str) -> Option<&XmlElement> {
+        self.descendant_at(&self.descendant_path(namespace, local_name)?)
+    }
+
+    /// Child indices leading to the descendant `find_descendant` returns
+    pub fn descendant_path(&self, namespace: &str, local_name: &str) -> Option<Vec<usize>> {
+        for (index, node) in self.children.iter().enumerate() {
+            let XmlNode::Element(child) = node else { continue };
+            if child.is(namespace, local_name) {
+                return Some(vec![index]);
+            }
+            if let Some(mut path) = child.descendant_path(namespace, local_name) {
+                path.insert(0, index);
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// The descendant at a path of child indices, as `descendant_path` gives
+    pub fn descendant_at(&self, path: &[usize]) -> Option<&XmlElement> {
+        let (first, rest) = path.split_first()?;
+        match self.children.get(*first)? {
+            XmlNode::Element(child) if rest.is_empty() => Some(child),
+            XmlNode::Element(child) => child.descendant_at(rest),
+            _ => None,
+        }
+    }
+
+    pub fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.local_name == local_name && self.namespace.as_deref().unwrap_or("") == namespace
+    }
+
+    /// Concatenated character data of this element and its descendants
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match node {
+                XmlNode::Text(data) => text.push_str(data),
+                XmlNode::Element(child) => text.push_str(&child.text()),
+                _ => {}
+            }
+        }
+        text
+    }
+
+    /// Detach the descendant at a path of child indices
+    fn remove_descendant_at(&mut self, path: &[usize]) -> Option<XmlElement> {
+        let (first, rest) = path.split_first()?;
+        if rest.is_empty() {
+            if !matches!(self.children.get(*first)?, XmlNode::Element(_)) {
+                return None;
+            }
+            let XmlNode::Element(removed) = self.children.remove(*first) else { unreachable!() };
+            return Some(removed);
+        }
+        match self.children.get_mut(*first)? {
+            XmlNode::Element(child) => child.remove_descendant_at(rest),
+            _ => None,
+        }
+    }
+
+    /// Canonicalize this element and its descendants as an XMLDSig document subset
+    pub fn canonicalize(&self, method: CanonicalizationMethod, inclusive_prefixes: &[String]) -> String {
+        let mut output = String::new();
+        let renderer = Renderer { method, inclusive_prefixes };
+        renderer.render_element(self, &BTreeMap::new(), true, &mut output);
+        output
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlDocument {
+    pub prolog: Vec<XmlNode>,
+    pub root: XmlElement,
+    pub epilog: Vec<XmlNode>,
+}
+
+impl XmlDocument {
+    pub fn parse(xml_content: &str) -> Result<Self, CanonicalizationError> {
+        Self::parse_with_limits(xml_content, &ParseLimits::default())
+    }
+
+    /// Parse untrusted input, rejecting DTDs and anything beyond the configured limits
+    pub fn parse_with_limits(xml_content: &str, limits: &ParseLimits) -> Result<Self, CanonicalizationError> {
+        use xml::reader::{EventReader, ParserConfig, XmlEvent};
+
+        if xml_content.len() as u64 > limits.max_uncompressed_size {
+            return Err(CanonicalizationError::DocumentTooLarge(limits.max_uncompressed_size));
+        }
+        // Internal entity declarations are the only expansion vector xml-rs supports
+        if xml_content.contains("<!DOCTYPE") {
+            return Err(CanonicalizationError::DoctypeNotAllowed);
+        }
+
+        let normalized = normalize_input(xml_content);
+        let config = ParserConfig::new()
+            .trim_whitespace(false)
+            .whitespace_to_characters(true)
+            .cdata_to_characters(true)
+            .coalesce_characters(true)
+            .ignore_comments(false);
+        let parser = EventReader::new_with_config(normalized.as_bytes(), config);
+
+        let mut prolog = Vec::new();
+        let mut epilog = Vec::new();
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root: Option<XmlElement> = None;
+
+        for event in parser {
+            let node = match event {
+                Ok(XmlEvent::StartElement { name, attributes, namespace }) => {
+                    if stack.len() >= limits.max_element_depth {
+                        return Err(CanonicalizationError::DepthExceeded(limits.max_element_depth));
+                    }
+                    if attributes.len() > limits.max_attributes {
+                        return Err(CanonicalizationError::TooManyAttributes {
+                            element: name.local_name,
+                            limit: limits.max_attributes,
+                        });
+                    }
+                    let inherited_xml_attributes = match stack.last() {
+                        Some(parent) => merge_xml_attributes(
+                            &parent.inherited_xml_attributes,
+                            &parent.attributes,
+                        ),
+                        None => Vec::new(),
+                    };
+                    let in_scope_namespaces = namespace
+                        .0
+                        .into_iter()
+                        .filter(|(prefix, _)| prefix != "xml" && prefix != "xmlns")
+                        .collect();
+
+                    stack.push(XmlElement {
+                        prefix: name.prefix,
+                        local_name: name.local_name,
+                        namespace: name.namespace.filter(|ns| !ns.is_empty()),
+                        attributes: attributes
+                            .into_iter()
+                            .map(|attr| XmlAttribute {
+                                prefix: attr.name.prefix,
+                                local_name: attr.name.local_name,
+                                namespace: attr.name.namespace.filter(|ns| !ns.is_empty()),
+                                value: attr.value,
+                            })
+                            .collect(),
+                        children: Vec::new(),
+                        in_scope_namespaces,
+                        inherited_xml_attributes,
+                    });
+                    continue;
+                }
+                Ok(XmlEvent::EndElement { .. }) => {
+                    let element = stack.pop().ok_or_else(|| {
+                        CanonicalizationError::ParseError("Unbalanced end element".to_string())
+                    })?;
+                    XmlNode::Element(element)
+                }
+                Ok(XmlEvent::Characters(text)) if text.len() > limits.max_text_length => {
+                    return Err(CanonicalizationError::TextTooLong(limits.max_text_length));
+                }
+                Ok(XmlEvent::Characters(text)) => XmlNode::Text(text),
+                Ok(XmlEvent::Comment(text)) => XmlNode::Comment(text),
+                Ok(XmlEvent::ProcessingInstruction { name, data }) => {
+                    XmlNode::ProcessingInstruction { target: name, data }
+                }
+                Ok(_) => continue,
+                Err(e) => return Err(CanonicalizationError::ParseError(e.to_string())),
+            };
+
+            match (stack.last_mut(), node) {
+                (Some(parent), node) => parent.children.push(node),
+                (None, XmlNode::Element(element)) => root = Some(element),
+                // Whitespace outside the document element is not part of the infoset
+                (None, XmlNode::Text(_)) => {}
+                (None, node) if root.is_none() => prolog.push(node),
+                (None, node) => epilog.push(node),
+            }
+        }
+
+        let root = root.ok_or_else(|| {
+            CanonicalizationError::ParseError("Document has no root element".to_string())
+        })?;
+
+        Ok(Self { prolog, root, epilog })
+    }
+
+    /// Canonicalize the whole document
+    pub fn canonicalize(&self, method: CanonicalizationMethod, inclusive_prefixes: &[String]) -> String {
+        let renderer = Renderer { method, inclusive_prefixes };
+        let mut output = String::new();
+
+        for node in &self.prolog {
+            if renderer.render_top_level(node, &mut output) {
+                output.push('\n');
+            }
+        }
+        renderer.render_element(&self.root, &BTreeMap::new(), false, &mut output);
+        for node in &self.epilog {
+            let mut rendered = String::new();
+            if renderer.render_top_level(node, &mut rendered) {
+                output.push('\n');
+                output.push_str(&rendered);
+            }
+        }
+        output
+    }
+
+    /// Apply the XMLDSig enveloped-signature transform to the Signature at `signature_path`
+    /// (from `descendant_path`), returning it; anything else there is left alone
+    pub fn remove_enveloped_signature(&mut self, signature_path: &[usize]) -> Option<XmlElement> {
+        match self.root.descendant_at(signature_path) {
+            Some(element) if element.is(XMLDSIG_NAMESPACE, "Signature") => self.root.remove_descendant_at(signature_path),
+            _ => None,
+        }
+    }
+}
+
+struct Renderer<'a> {
+    method: CanonicalizationMethod,
+    inclusive_prefixes: &'a [String],
+}
+
+impl Renderer<'_> {
+    fn render_top_level(&self, node: &XmlNode, output: &mut String) -> bool {
+        match node {
+            XmlNode::Comment(_) if !self.method.with_comments() => false,
+            XmlNode::Comment(_) | XmlNode::ProcessingInstruction { .. } => {
+                self.render_node(node, &BTreeMap::new(), output);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render_node(&self, node: &XmlNode, rendered_namespaces: &BTreeMap<String, String>, output: &mut String) {
+        match node {
+            XmlNode::Element(element) => self.render_element(element, rendered_namespaces, false, output),
+            XmlNode::Text(text) => output.push_str(&escape_text(text)),
+            XmlNode::Comment(text) => {
+                if self.method.with_comments() {
+                    output.push_str("<!--");
+                    output.push_str(text);
+                    output.push_str("-->");
+                }
+            }
+            XmlNode::ProcessingInstruction { target, data } => {
+                output.push_str("<?");
+                output.push_str(target);
+                // Whitespace separating the target from the data is not part of the data
+                if let Some(data) = data.as_deref().map(str::trim_start).filter(|data| !data.is_empty()) {
+                    output.push(' ');
+                    output.push_str(data);
+                }
+                output.push_str("?>");
+            }
+        }
+    }
+
+    fn render_element(
+        &self,
+        element: &XmlElement,
+        rendered_namespaces: &BTreeMap<String, String>,
+        is_apex: bool,
+        output: &mut String,
+    ) {
+        let declarations = if self.method.is_exclusive() {
+            self.exclusive_declarations(element, rendered_namespaces)
+        } else {
+            inclusive_declarations(element, rendered_namespaces)
+        };
+
+        let mut attributes: Vec<&XmlAttribute> = element.attributes.iter().collect();
+        // Inclusive C14N of a subset carries xml:* attributes down from omitted ancestors
+        if is_apex && !self.method.is_exclusive() {
+            for inherited in &element.inherited_xml_attributes {
+                let overridden = element.attributes.iter().any(|attr| {
+                    attr.namespace.as_deref() == Some(XML_NAMESPACE) && attr.local_name == inherited.local_name
+                });
+                if !overridden {
+                    attributes.push(inherited);
+                }
+            }
+        }
+        attributes.sort_by(|a, b| {
+            let key_a = (a.namespace.as_deref().unwrap_or(""), a.local_name.as_str());
+            let key_b = (b.namespace.as_deref().unwrap_or(""), b.local_name.as_str());
+            key_a.cmp(&key_b)
+        });
+
+        let qualified_name = element.qualified_name();
+        output.push('<');
+        output.push_str(&qualified_name);
+        for (prefix, uri) in &declarations {
+            if prefix.is_empty() {
+                output.push_str(" xmlns=\"");
+            } else {
+                output.push_str(" xmlns:");
+                output.push_str(prefix);
+                output.push_str("=\"");
+            }
+            output.push_str(&escape_attribute(uri));
+            output.push('"');
+        }
+        for attr in attributes {
+            output.push(' ');
+            output.push_str(&attr.qualified_name());
+            output.push_str("=\"");
+            output.push_str(&escape_attribute(&attr.value));
+            output.push('"');
+        }
+        output.push('>');
+
+        let mut child_namespaces = rendered_namespaces.clone();
+        child_namespaces.extend(declarations);
+        for child in &element.children {
+            self.render_node(child, &child_namespaces, output);
+        }
+
+        output.push_str("</");
+        output.push_str(&qualified_name);
+        output.push('>');
+    }
+
+    /// Namespace declarations visibly utilized by the element, plus the InclusiveNamespaces PrefixList
+    fn exclusive_declarations(
+        &self,
+        element: &XmlElement,
+        rendered_namespaces: &BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        let mut utilized: Vec<String> = vec![element.prefix.clone().unwrap_or_default()];
+        utilized.extend(
+            element
+                .attributes
+                .iter()
+                .filter_map(|attr| attr.prefix.clone())
+                .filter(|prefix| prefix != "xml"),
+        );
+        for prefix in self.inclusive_prefixes {
+            let prefix = if prefix == "#default" { String::new() } else { prefix.clone() };
+            if element.in_scope_namespaces.get(&prefix).is_some_and(|uri| !uri.is_empty()) {
+                utilized.push(prefix);
+            }
+        }
+
+        let mut declarations = BTreeMap::new();
+        for prefix in utilized {
+            let uri = element.in_scope_namespaces.get(&prefix).cloned().unwrap_or_default();
+            if needs_declaration(&prefix, &uri, rendered_namespaces) {
+                declarations.insert(prefix, uri);
+            }
+        }
+        declarations
+    }
+}
+
+/// Every in-scope namespace whose binding differs from the nearest rendered ancestor
+fn inclusive_declarations(
+    element: &XmlElement,
+    rendered_namespaces: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    element
+        .in_scope_namespaces
+        .iter()
+        .filter(|(prefix, uri)| needs_declaration(prefix, uri, rendered_namespaces))
+        .map(|(prefix, uri)| (prefix.clone(), uri.clone()))
+        .collect()
+}
+
+fn needs_declaration(prefix: &str, uri: &str, rendered_namespaces: &BTreeMap<String, String>) -> bool {
+    let rendered = rendered_namespaces.get(prefix).map(String::as_str);
+    if prefix.is_empty() {
+        // xmlns="" is only emitted to undo a non-empty default from an output ancestor
+        rendered.unwrap_or("") != uri
+    } else {
+        !uri.is_empty() && rendered != Some(uri)
+    }
+}
+
+fn merge_xml_attributes(inherited: &[XmlAttribute], own: &[XmlAttribute]) -> Vec<XmlAttribute> {
+    let mut merged: Vec<XmlAttribute> = inherited
+        .iter()
+        .filter(|attr| !own.iter().any(|o| o.namespace.as_deref() == Some(XML_NAMESPACE) && o.local_name == attr.local_name))
+        .cloned()
+        .collect();
+    merged.extend(
+        own.iter()
+            .filter(|attr| attr.namespace.as_deref() == Some(XML_NAMESPACE))
+            .cloned(),
+    );
+    merged
+}
+
+/// Apply XML 1.0 end-of-line handling and attribute-value normalization to literal input.
+/// Character references such as `&#xD;` survive untouched, as C14N requires.
+fn normalize_input(xml_content: &str) -> String {
+    let line_normalized = xml_content.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut output = String::with_capacity(line_normalized.len());
+    let mut rest = line_normalized.as_str();
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let terminator = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            Some("]]>")
+        } else if rest.starts_with("<?") {
+            Some("?>")
+        } else {
+            None
+        };
+
+        if let Some(terminator) = terminator {
+            let end = rest.find(terminator).map(|i| i + terminator.len()).unwrap_or(rest.len());
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        // Element tag: replace literal whitespace inside quoted attribute values
+        let mut quote: Option<char> = None;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match (quote, c) {
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), '\t') | (Some(_), '\n') => {
+                    output.push(' ');
+                    continue;
+                }
+                (None, '>') => {
+                    output.push('>');
+                    end = i + 1;
+                    break;
+                }
+                _ => {}
+            }
+            output.push(c);
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#xD;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push_str("&#x9;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(xml: &str, method: CanonicalizationMethod) -> String {
+        XmlDocument::parse(xml).unwrap().canonicalize(method, &[])
+    }
+
+    #[test]
+    fn test_pis_comments_and_outside_whitespace() {
+        // W3C C14N 1.0 example 3.1
+        let input = "<?xml version=\"1.0\"?>\n\n<?xml-stylesheet   href=\"doc.xsl\"\n   type=\"text/xsl\"   ?>\n\n<doc>Hello, world!<!-- Comment 1 --></doc>\n\n<?pi-without-data     ?>\n\n<!-- Comment 2 -->\n\n<!-- Comment 3 -->";
+
+        assert_eq!(
+            canonical(input, CanonicalizationMethod::Inclusive),
+            "<?xml-stylesheet href=\"doc.xsl\"\n   type=\"text/xsl\"   ?>\n<doc>Hello, world!</doc>\n<?pi-without-data?>"
+        );
+        assert_eq!(
+            canonical(input, CanonicalizationMethod::InclusiveWithComments),
+            "<?xml-stylesheet href=\"doc.xsl\"\n   type=\"text/xsl\"   ?>\n<doc>Hello, world!<!-- Comment 1 --></doc>\n<?pi-without-data?>\n<!-- Comment 2 -->\n<!-- Comment 3 -->"
+        );
+    }
+
+    #[test]
+    fn test_start_and_end_tags() {
+        // W3C C14N 1.0 example 3.3 (without the DTD default attribute)
+        let input = r#"<doc>
+   <e1   />
+   <e2   ></e2>
+   <e3   name = "elem3"   id="elem3"   />
+   <e4   name="elem4"   id="elem4"   ></e4>
+   <e5 a:attr="out" b:attr="sorted" attr2="all" attr="I'm"
+      xmlns:b="http://www.ietf.org"
+      xmlns:a="http://www.w3.org"
+      xmlns="http://example.org"/>
+   <e6 xmlns="" xmlns:a="http://www.w3.org">
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="" xmlns:a="http://www.w3.org">
+            <e9 xmlns="" xmlns:a="http://www.ietf.org"/>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+        let expected = r#"<doc>
+   <e1></e1>
+   <e2></e2>
+   <e3 id="elem3" name="elem3"></e3>
+   <e4 id="elem4" name="elem4"></e4>
+   <e5 xmlns="http://example.org" xmlns:a="http://www.w3.org" xmlns:b="http://www.ietf.org" attr="I'm" attr2="all" b:attr="sorted" a:attr="out"></e5>
+   <e6 xmlns:a="http://www.w3.org">
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="">
+            <e9 xmlns:a="http://www.ietf.org"></e9>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+
+        assert_eq!(canonical(input, CanonicalizationMethod::Inclusive), expected);
+    }
+
+    #[test]
+    fn test_exclusive_omits_unused_namespaces() {
+        let input = r#"<doc>
+   <e6 xmlns="" xmlns:a="http://www.w3.org">
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="" xmlns:a="http://www.w3.org">
+            <e9 xmlns="" xmlns:a="http://www.ietf.org"/>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+        let expected = r#"<doc>
+   <e6>
+      <e7 xmlns="http://www.ietf.org">
+         <e8 xmlns="">
+            <e9></e9>
+         </e8>
+      </e7>
+   </e6>
+</doc>"#;
+
+        assert_eq!(canonical(input, CanonicalizationMethod::Exclusive), expected);
+    }
+
+    #[test]
+    fn test_character_modifications() {
+        // W3C C14N 1.0 example 3.4 (attributes without DTD type declarations)
+        let input = r#"<doc>
+   <text>First line&#x0d;&#10;Second line</text>
+   <value>&#x32;</value>
+   <compute><![CDATA[value>"0" && value<"10" ?"valid":"error"]]></compute>
+   <compute expr='value>"0" &amp;&amp; value&lt;"10" ?"valid":"error"'>valid</compute>
+   <norm attr=' &apos;   &#x20;&#13;&#xa;&#9;   &apos; '/>
+</doc>"#;
+        let expected = r#"<doc>
+   <text>First line&#xD;
+Second line</text>
+   <value>2</value>
+   <compute>value&gt;"0" &amp;&amp; value&lt;"10" ?"valid":"error"</compute>
+   <compute expr="value>&quot;0&quot; &amp;&amp; value&lt;&quot;10&quot; ?&quot;valid&quot;:&quot;error&quot;">valid</compute>
+   <norm attr=" '    &#xD;&#xA;&#x9;   ' "></norm>
+</doc>"#;
+
+        assert_eq!(canonical(input, CanonicalizationMethod::Inclusive), expected);
+        // Literal line breaks inside attribute values normalize to spaces
+        assert_eq!(
+            canonical("<a b=\"x\r\ny\tz\"/>", CanonicalizationMethod::Inclusive),
+            "<a b=\"x y z\"></a>"
+        );
+    }
+
+    #[test]
+    fn test_subset_inherits_namespaces() {
+        let document = XmlDocument::parse(
+            r#"<root xmlns="urn:root" xmlns:ds="http://www.w3.org/2000/09/xmldsig#" xml:lang="en"><ds:SignedInfo><ds:Reference URI=""/></ds:SignedInfo></root>"#,
+        )
+        .unwrap();
+        let signed_info = document.root.find_descendant(XMLDSIG_NAMESPACE, "SignedInfo").unwrap();
+
+        assert_eq!(
+            signed_info.canonicalize(CanonicalizationMethod::Inclusive, &[]),
+            r#"<ds:SignedInfo xmlns="urn:root" xmlns:ds="http://www.w3.org/2000/09/xmldsig#" xml:lang="en"><ds:Reference URI=""></ds:Reference></ds:SignedInfo>"#
+        );
+        assert_eq!(
+            signed_info.canonicalize(CanonicalizationMethod::Exclusive, &[]),
+            r#"<ds:SignedInfo xmlns:ds="http://www.w3.org/2000/09/xmldsig#"><ds:Reference URI=""></ds:Reference></ds:SignedInfo>"#
+        );
+        assert_eq!(
+            signed_info.canonicalize(CanonicalizationMethod::Exclusive, &["#default".to_string()]),
+            r#"<ds:SignedInfo xmlns="urn:root" xmlns:ds="http://www.w3.org/2000/09/xmldsig#"><ds:Reference URI=""></ds:Reference></ds:SignedInfo>"#
+        );
+    }
+
+    #[test]
+    fn test_enveloped_signature_transform() {
+        let mut document = XmlDocument::parse(
+            r#"<Kyc id="1"><UidData/><Signature xmlns="http://www.w3.org/2000/09/xmldsig#"><SignatureValue>abc</SignatureValue></Signature></Kyc>"#,
+        )
+        .unwrap();
+
+        let path = document.root.descendant_path(XMLDSIG_NAMESPACE, "Signature").unwrap();
+        assert!(document.remove_enveloped_signature(&path).is_some());
+        assert_eq!(
+            document.canonicalize(CanonicalizationMethod::Inclusive, &[]),
+            r#"<Kyc id="1"><UidData></UidData></Kyc>"#
+        );
+    }
+
+    #[test]
+    fn test_enveloped_signature_removes_the_verified_element() {
+        // Depth-first, the nested Signature comes first; a shallower one must not be removed instead
+        let mut document = XmlDocument::parse(
+            r#"<Kyc><UidData><Signature xmlns="http://www.w3.org/2000/09/xmldsig#">inner</Signature></UidData><Signature xmlns="http://www.w3.org/2000/09/xmldsig#">outer</Signature></Kyc>"#,
+        )
+        .unwrap();
+        let path = document.root.descendant_path(XMLDSIG_NAMESPACE, "Signature").unwrap();
+        assert_eq!(path, vec![0, 0]);
+        assert_eq!(document.root.find_descendant(XMLDSIG_NAMESPACE, "Signature").unwrap().text(), "inner");
+
+        assert_eq!(document.remove_enveloped_signature(&path).unwrap().text(), "inner");
+        assert_eq!(
+            document.canonicalize(CanonicalizationMethod::Inclusive, &[]),
+            r#"<Kyc><UidData></UidData><Signature xmlns="http://www.w3.org/2000/09/xmldsig#">outer</Signature></Kyc>"#
+        );
+        // A path that no longer leads to a Signature removes nothing
+        assert!(document.remove_enveloped_signature(&[0]).is_none());
+        assert!(document.remove_enveloped_signature(&[5, 1]).is_none());
+    }
+
+    #[test]
+    fn test_unknown_algorithm_rejected() {
+        assert!(CanonicalizationMethod::from_uri(C14N_EXCLUSIVE).is_ok());
+        assert!(matches!(
+            CanonicalizationMethod::from_uri("http://www.w3.org/2006/12/xml-c14n11"),
+            Err(CanonicalizationError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_limits_enforced() {
+        let limits = ParseLimits {
+            max_element_depth: 3,
+            max_attributes: 2,
+            max_text_length: 8,
+            ..ParseLimits::default()
+        };
+        assert!(XmlDocument::parse_with_limits("<a><b><c x=\"1\" y=\"2\">12345678</c></b></a>", &limits).is_ok());
+
+        assert!(matches!(
+            XmlDocument::parse_with_limits("<a><b><c><d/></c></b></a>", &limits),
+            Err(CanonicalizationError::DepthExceeded(3))
+        ));
+        assert!(matches!(
+            XmlDocument::parse_with_limits(r#"<a x="1" y="2" z="3"/>"#, &limits),
+            Err(CanonicalizationError::TooManyAttributes { limit: 2, .. })
+        ));
+        assert!(matches!(
+            XmlDocument::parse_with_limits("<a>123456789</a>", &limits),
+            Err(CanonicalizationError::TextTooLong(8))
+        ));
+
+        let billion_laughs = r#"<?xml version="1.0"?><!DOCTYPE lolz [<!ENTITY lol "lol"><!ENTITY lol2 "&lol;&lol;">]><a>&lol2;</a>"#;
+        assert!(matches!(
+            XmlDocument::parse(billion_laughs),
+            Err(CanonicalizationError::DoctypeNotAllowed)
+        ));
+    }
+}
@@ -55,11 +55,11 @@ check_dependency "cargo"
 check_dependency "npm"
 check_dependency "forge"
 
-# Build Noir circuits (P2P selective disclosure)
+# Build Noir circuits (P2P selective disclosure and contact proof)
 echo ""
 echo "🔧 Building Noir circuits..."
 cd circuits
-nargo compile
+nargo compile --workspace
 if [ $? -eq 0 ]; then
     echo "✅ Circuits compiled successfully"
 else
@@ -224,7 +224,7 @@ if [ "$P2P_MODE" = true ]; then
     cp web/src/components/P2PProofVerifier.tsx dist/p2p/
     cp web/src/lib/p2p-proof-service.ts dist/p2p/
     cp contracts/src/P2PIdentityRegistry.sol dist/p2p/
-    cp circuits/src/p2p_selective_disclosure.nr dist/p2p/
+    cp circuits/p2p_selective_disclosure/src/main.nr dist/p2p/p2p_selective_disclosure.nr
     cp core/src/p2p_service.rs dist/p2p/
     
     # Create P2P integration guide
@@ -94,23 +94,28 @@ const EnhancedP2PProofGenerator: React.FC<P2PProofGeneratorProps> = ({ onProofGe
         birthdate: 19950615,
         nationality: 840,
         residency_code: 840,
-        document_hash: "0x" + Array(64).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join(''),
+        document_hash: "0x" + Array(62).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join(''),
         credit_score: 750,
       };
 
-      const challenge = {
+      const requirements = {
         current_date: Math.floor(Date.now() / 1000),
         min_age: 18 * 365 * 24 * 60 * 60,
         required_nationality: 840,
         required_residency: 840,
         min_credit_score: 700,
-        nullifier_secret: crypto.getRandomValues(new Uint8Array(32)).toString(),
+      };
+
+      // Stays on this device; only the proof leaves it
+      const secrets = {
+        nullifier_secret: "0x" + Array.from(crypto.getRandomValues(new Uint8Array(31)), (b) => b.toString(16).padStart(2, "0")).join(""),
       };
 
       const proofJson = await proofService.generate_proof(
         JSON.stringify(idData),
-        JSON.stringify(challenge),
-        proofType
+        JSON.stringify(requirements),
+        proofType,
+        JSON.stringify(secrets)
       );
 
       const proof = JSON.parse(proofJson);
@@ -105,25 +105,30 @@ const P2PProofGenerator: React.FC<P2PProofGeneratorProps> = ({ onProofGenerated
         birthdate: 19950615, // YYYYMMDD format
         nationality: 840, // ISO country code for US
         residency_code: 840,
-        document_hash: "0x" + Array(64).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join(''),
+        document_hash: "0x" + Array(62).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join(''),
         credit_score: 750,
       }
 
-      // Create challenge for proof
-      const challenge = {
+      // Requirements the proof must meet
+      const requirements = {
         current_date: Math.floor(Date.now() / 1000),
         min_age: 18 * 365 * 24 * 60 * 60, // 18 years in seconds
         required_nationality: 840,
         required_residency: 840,
         min_credit_score: 700,
-        nullifier_secret: crypto.getRandomValues(new Uint8Array(32)).toString(),
+      }
+
+      // Stays on this device; only the proof leaves it
+      const secrets = {
+        nullifier_secret: "0x" + Array.from(crypto.getRandomValues(new Uint8Array(31)), (b) => b.toString(16).padStart(2, "0")).join(""),
       }
 
       // Generate ZK proof locally (no backend)
       const proofJson = await proofService.generate_proof(
         JSON.stringify(idData),
-        JSON.stringify(challenge),
-        proofType
+        JSON.stringify(requirements),
+        proofType,
+        JSON.stringify(secrets)
       )
 
       const proof = JSON.parse(proofJson)
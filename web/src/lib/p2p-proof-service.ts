@@ -3,34 +3,91 @@
 
 export interface P2PProofRequest {
   proof_type: number // 1=age, 2=residency, 3=nationality, 4=credit, 5=composite
-  challenge: ProofChallenge
-  verifier_address: string
-  nonce: string
+  requirements: ProofRequirements
+  verifier_address: string // 0x-prefixed 20-byte address
+  nonce: string // BN254 field element as 0x hex, fresh per request
+  expires_at: number // Unix millis; responses are refused after this
 }
 
-export interface ProofChallenge {
+// Set by the verifier; safe to send
+export interface ProofRequirements {
   current_date: number
   min_age: number
   required_nationality: number
   required_residency: number
   min_credit_score: number
-  nullifier_secret: string
 }
 
+// Known only to the holder; never include in a request or send with a proof
+export interface HolderSecrets {
+  nullifier_secret: string // BN254 field element as 0x hex (31 random bytes always fit)
+}
+
+// Pre-split combined form, still accepted by generate_proof when no secrets are passed
+export interface ProofChallenge extends ProofRequirements, HolderSecrets {}
+
 export interface P2PProofResponse {
   proof: string
   public_signals: string[]
-  signature: string
+  signature: string // Compact passkey assertion, empty until attach_passkey_assertion
   nullifier_hash: string
   commitment: string
   timestamp: number
 }
 
+// From proof_size(proof_json); check it before choosing a send_proof_p2p channel
+export interface EnvelopeSize {
+  json_bytes: number
+  envelope_bytes: number // Versioned CBOR envelope, from encode_proof
+  qr_uri_bytes: number // patricon://verify?proof= link with the envelope as base64url
+  fits_qr: boolean
+}
+
+// What navigator.credentials.get() returned, each buffer base64url-encoded
+export interface PasskeyAssertion {
+  credential_id: string
+  authenticator_data: string
+  client_data_json: string
+  signature: string
+}
+
+// What navigator.credentials.create() returned, each buffer base64url-encoded
+export interface PasskeyRegistration {
+  attestation_object: string
+  client_data_json: string
+}
+
+// Returned by bind_passkey; keep it and restore it with import_passkey_bindings
+export interface PasskeyBinding {
+  commitment: string // Identity commitment the passkey signs for
+  credential_id: string
+  public_key: string // COSE key, base64url; give it to verifiers for verify_proof
+  attestation: 'none' | 'self_attestation' | 'basic'
+  bound_at: number // Unix millis
+}
+
+// Passed to set_relying_party on the verifier
+export interface RelyingParty {
+  id: string // RP ID the passkeys were created for, e.g. "patricon.id"
+  origins: string[] // e.g. ["https://patricon.id"]
+  require_user_verification?: boolean // Defaults to true
+}
+
+export function toBase64Url(buffer: ArrayBuffer): string {
+  const binary = String.fromCharCode(...new Uint8Array(buffer))
+  return btoa(binary).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '')
+}
+
+export function fromBase64Url(encoded: string): Uint8Array {
+  const binary = atob(encoded.replace(/-/g, '+').replace(/_/g, '/'))
+  return Uint8Array.from(binary, c => c.charCodeAt(0))
+}
+
 export interface IDData {
   birthdate: number
   nationality: number
   residency_code: number
-  document_hash: string
+  document_hash: string // BN254 field element as 0x hex; reduce full SHA-256 digests mod p
   credit_score: number
 }
 
@@ -47,8 +104,9 @@ export class P2PProofService {
 
   async generate_proof(
     id_data_json: string,
-    challenge_json: string,
-    proof_type: number
+    requirements_json: string,
+    proof_type: number,
+    secrets_json?: string
   ): Promise<string> {
     if (!this.initialized) {
       throw new Error('Service not initialized')
@@ -58,7 +116,7 @@ export class P2PProofService {
     await new Promise(resolve => setTimeout(resolve, 1500))
 
     const id_data: IDData = JSON.parse(id_data_json)
-    const challenge: ProofChallenge = JSON.parse(challenge_json)
+    const requirements: ProofRequirements = JSON.parse(requirements_json)
 
     // Generate mock proof response
     const response: P2PProofResponse = {
@@ -80,7 +138,7 @@ export class P2PProofService {
 
   async verify_proof(
     proof_json: string,
-    verifier_public_key: string
+    passkey_public_key: string // Holder's COSE credential public key, base64url
   ): Promise<boolean> {
     if (!this.initialized) {
       throw new Error('Service not initialized')
@@ -129,11 +187,12 @@ export class P2PProofService {
   }
 
   // WebAuthn/Passkey integration
-  async register_passkey(): Promise<boolean> {
+  // Answer the challenge from begin_passkey_binding(commitment); pass the result to bind_passkey
+  async register_passkey(challenge: string): Promise<PasskeyRegistration | null> {
     try {
       const credential = await navigator.credentials.create({
         publicKey: {
-          challenge: crypto.getRandomValues(new Uint8Array(32)),
+          challenge: fromBase64Url(challenge),
           rp: {
             name: "PatriconID P2P",
             id: window.location.hostname,
@@ -143,18 +202,56 @@ export class P2PProofService {
             name: "p2p-user@patriconidd.com",
             displayName: "P2P User",
           },
-          pubKeyCredParams: [{ alg: -7, type: "public-key" }],
+          pubKeyCredParams: [
+            { alg: -7, type: "public-key" },
+            { alg: -8, type: "public-key" },
+            { alg: -257, type: "public-key" },
+          ],
           authenticatorSelection: {
             authenticatorAttachment: "platform",
             userVerification: "required",
           },
+          attestation: "direct",
         },
-      })
+      }) as PublicKeyCredential | null
+      if (!credential) {
+        return null
+      }
 
-      return credential !== null
+      const response = credential.response as AuthenticatorAttestationResponse
+      return {
+        attestation_object: toBase64Url(response.attestationObject),
+        client_data_json: toBase64Url(response.clientDataJSON),
+      }
     } catch (error) {
       console.error('Passkey registration failed:', error)
-      return false
+      return null
+    }
+  }
+
+  // Sign the challenge from passkey_challenge(proof_json); pass the result to attach_passkey_assertion
+  async get_passkey_assertion(challenge: string): Promise<PasskeyAssertion | null> {
+    try {
+      const credential = await navigator.credentials.get({
+        publicKey: {
+          challenge: fromBase64Url(challenge),
+          userVerification: "required",
+        },
+      }) as PublicKeyCredential | null
+      if (!credential) {
+        return null
+      }
+
+      const response = credential.response as AuthenticatorAssertionResponse
+      return {
+        credential_id: toBase64Url(credential.rawId),
+        authenticator_data: toBase64Url(response.authenticatorData),
+        client_data_json: toBase64Url(response.clientDataJSON),
+        signature: toBase64Url(response.signature),
+      }
+    } catch (error) {
+      console.error('Passkey assertion failed:', error)
+      return null
     }
   }
 
@@ -188,7 +285,7 @@ export class P2PProofService {
           birthdate: 19950615,
           nationality: 840, // US
           residency_code: 840,
-          document_hash: `0x${Array(64).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join('')}`,
+          document_hash: `0x${Array(62).fill(0).map(() => Math.floor(Math.random() * 16).toString(16)).join('')}`,
           credit_score: 750
         }
       } else {
@@ -49,13 +49,15 @@ export interface ProofGenerationRequest {
     document_hash?: string;
     credit_score?: number;
   };
-  challenge: {
+  requirements: {
     current_date: number;
     min_age?: number;
     required_nationality?: number;
     required_residency?: number;
     min_credit_score?: number;
-    nullifier_secret: string;
+  };
+  secrets: {
+    nullifier_secret: string; // Holder-only; never sent to the verifier
   };
 }
 